@@ -0,0 +1,219 @@
+//! Splitting calls between two closures and comparing the results.
+//!
+//! `AbTest` routes a fixed share of calls to a candidate closure and the rest
+//! to the current one, and keeps per-arm statistics of the outputs. Outputs
+//! are scored through `Outcome`: a `Result` counts as a failure when it is an
+//! `Err`, and numbers are summed so that their mean can be compared.
+
+use core::fmt;
+
+use crate::stable_fn::{StableFnMut,StableFnOnce};
+
+/// How a closure's output counts in the statistics of an `AbTest`.
+pub trait Outcome {
+    /// Whether the call succeeded. Defaults to `true`.
+    fn is_success(&self) -> bool {
+        true
+    }
+    /// A value to average over the calls, if the output has one.
+    fn value(&self) -> Option<f64> {
+        None
+    }
+}
+impl<T, E> Outcome for Result<T, E> {
+    fn is_success(&self) -> bool {
+        self.is_ok()
+    }
+}
+impl<T> Outcome for Option<T> {
+    fn is_success(&self) -> bool {
+        self.is_some()
+    }
+}
+impl Outcome for bool {
+    fn is_success(&self) -> bool {
+        *self
+    }
+}
+impl Outcome for () {}
+macro_rules! numeric_outcome {
+    ($($t:ty),*) => {
+        $(impl Outcome for $t {
+            fn value(&self) -> Option<f64> {
+                Some(*self as f64)
+            }
+        })*
+    };
+}
+numeric_outcome!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+/// The statistics of one arm of an `AbTest`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ArmStats {
+    pub calls: u64,
+    pub failures: u64,
+    /// The sum of the outputs' values.
+    pub total: f64,
+}
+impl ArmStats {
+    /// The share of calls that failed, or `None` before the first call.
+    pub fn failure_rate(&self) -> Option<f64> {
+        (self.calls > 0).then(|| self.failures as f64 / self.calls as f64)
+    }
+    /// The mean of the outputs' values, or `None` before the first call.
+    pub fn mean(&self) -> Option<f64> {
+        (self.calls > 0).then(|| self.total / self.calls as f64)
+    }
+    fn record(&mut self, outcome: &impl Outcome) {
+        self.calls += 1;
+        self.failures += u64::from(!outcome.is_success());
+        self.total += outcome.value().unwrap_or(0.0);
+    }
+}
+
+/// The statistics of both arms of an `AbTest`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AbReport {
+    pub a: ArmStats,
+    pub b: ArmStats,
+}
+
+/// Calls `G` for `b_calls` out of every `period` calls and `F` for the rest.
+///
+/// The split is deterministic and spread evenly: with 1 in 4, every fourth
+/// call goes to `G`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{AbTest,ClosureRef,StableFnMut};
+/// type Parse = ClosureRef<(),(&'static str,),Result<u32,()>>;
+/// let strict:Parse = closure!(ref _s=() => move |s| s.parse().map_err(drop));
+/// let lenient:Parse = closure!(ref _s=() => move |s| s.trim().parse().map_err(drop));
+/// let mut parse = AbTest::new(strict, lenient, 1, 2);
+/// for input in ["1", " 2", " 3", "4"] {
+///     let _ = parse.stable_call_mut((input,));
+/// }
+/// let report = parse.report();
+/// assert_eq!((report.a.calls, report.a.failures), (2, 1));
+/// assert_eq!((report.b.calls, report.b.failures), (2, 0));
+/// ```
+#[derive(Clone, Copy)]
+pub struct AbTest<F, G> {
+    a: F,
+    b: G,
+    b_calls: u32,
+    period: u32,
+    credit: u32,
+    report: AbReport,
+}
+impl<F, G> AbTest<F, G> {
+    /// # Panics
+    ///
+    /// Panics if `period` is zero or `b_calls` is larger than `period`.
+    pub fn new(a: F, b: G, b_calls: u32, period: u32) -> Self {
+        assert!(b_calls <= period && period > 0, "AbTest share {}/{} is not a fraction", b_calls, period);
+        Self { a, b, b_calls, period, credit: 0, report: AbReport::default() }
+    }
+    /// The statistics since the test was created or last reset.
+    pub fn report(&self) -> AbReport {
+        self.report
+    }
+    pub fn reset(&mut self) {
+        self.report = AbReport::default();
+    }
+    pub fn into_inner(self) -> (F, G) {
+        (self.a, self.b)
+    }
+    // spreads the `b_calls` evenly over each period, as in Bresenham's line;
+    // `credit` is compared before adding, so it stays below `period` and
+    // cannot overflow
+    fn next_is_b(&mut self) -> bool {
+        let room = self.period - self.b_calls;
+        let b = self.credit >= room;
+        if b {
+            self.credit -= room;
+        } else {
+            self.credit += self.b_calls;
+        }
+        b
+    }
+}
+impl<F, G> fmt::Debug for AbTest<F, G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AbTest")
+            .field("b_calls", &self.b_calls)
+            .field("period", &self.period)
+            .field("report", &self.report)
+            .finish_non_exhaustive()
+    }
+}
+impl<F, G, Input> StableFnOnce<Input> for AbTest<F, G>
+where
+    F: StableFnOnce<Input>,
+    G: StableFnOnce<Input, Output=F::Output>
+{
+    type Output = F::Output;
+    /// Calls the arm the next call would go to, without recording it.
+    fn stable_call_once(mut self, args: Input) -> F::Output {
+        if self.next_is_b() {
+            self.b.stable_call_once(args)
+        } else {
+            self.a.stable_call_once(args)
+        }
+    }
+}
+impl<F, G, Input> StableFnMut<Input> for AbTest<F, G>
+where
+    F: StableFnMut<Input>,
+    G: StableFnMut<Input, Output=F::Output>,
+    F::Output: Outcome
+{
+    fn stable_call_mut(&mut self, args: Input) -> F::Output {
+        let (output, stats) = if self.next_is_b() {
+            (self.b.stable_call_mut(args), &mut self.report.b)
+        } else {
+            (self.a.stable_call_mut(args), &mut self.report.a)
+        };
+        stats.record(&output);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AbTest,ClosureRef,StableFnMut};
+
+    #[test]
+    fn test_split_and_statistics() {
+        let old:ClosureRef<u32,(u32,),u32> = closure!(ref cost=10 => move |n| *cost * n);
+        let new:ClosureRef<u32,(u32,),u32> = closure!(ref cost=4 => move |n| *cost * n);
+        let mut cost = AbTest::new(old, new, 1, 3);
+        let arms:Vec<u32> = (0..6).map(|_| cost.stable_call_mut((1,))).collect();
+        assert_eq!(arms, [10, 10, 4, 10, 10, 4]);
+        let report = cost.report();
+        assert_eq!((report.a.calls, report.b.calls), (4, 2));
+        assert_eq!(report.a.mean(), Some(10.0));
+        assert_eq!(report.b.mean(), Some(4.0));
+        assert_eq!(report.b.failure_rate(), Some(0.0));
+        cost.reset();
+        assert_eq!(cost.report().a.mean(), None);
+        // all or nothing
+        let mut never = AbTest::new(old, new, 0, 1);
+        assert_eq!(never.stable_call_mut((1,)), 10);
+        let mut always = AbTest::new(old, new, 1, 1);
+        assert_eq!(always.stable_call_mut((1,)), 4);
+    }
+    #[test]
+    fn test_split_near_u32_max() {
+        let a:ClosureRef<u32,(),u32> = closure!(ref arm=0 => move || *arm);
+        let b:ClosureRef<u32,(),u32> = closure!(ref arm=1 => move || *arm);
+        let mut split = AbTest::new(a, b, 3_000_000_000, 4_000_000_000);
+        let arms:Vec<u32> = (0..8).map(|_| split.stable_call_mut(())).collect();
+        assert_eq!(arms, [0, 1, 1, 1, 0, 1, 1, 1]);
+        let mut split = AbTest::new(a, b, u32::MAX - 1, u32::MAX);
+        let arms:Vec<u32> = (0..3).map(|_| split.stable_call_mut(())).collect();
+        assert_eq!(arms, [0, 1, 1]);
+    }
+}
@@ -0,0 +1,164 @@
+//! Combining the outputs of the handlers called by one event.
+//!
+//! `EventEmitter::emit` drops what its handlers return. Query-style events,
+//! such as "who can open this file?", need the answers instead.
+//! `EventEmitter::emit_with` takes an `Aggregate` that receives each output
+//! in turn and produces the result:
+//!
+//! * `FirstSome` stops at the first handler that answers `Some`.
+//! * `CollectAll` keeps every output.
+//! * `Fold` combines the outputs with a namable closure.
+
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+
+use crate::stable_fn::StableFnMut;
+
+/// A policy combining handler outputs of type `T` into an `Output`.
+pub trait Aggregate<T> {
+    type Output;
+    /// Takes one handler's output. `Break` skips the remaining handlers.
+    fn add(&mut self, item: T) -> ControlFlow<()>;
+    fn finish(self) -> Self::Output;
+}
+
+/// Drops every output, as `EventEmitter::emit` does.
+impl<T> Aggregate<T> for () {
+    type Output = ();
+    fn add(&mut self, _: T) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+    fn finish(self) {}
+}
+
+/// The first `Some` output, after which no more handlers are called.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,EventEmitter,FirstSome};
+/// type Opener = ClosureRef<&'static str,(&'static str,),Option<&'static str>>;
+/// let openers:EventEmitter<Opener> = EventEmitter::new();
+/// openers.subscribe(closure!(ref ext=".png" => move |f| f.ends_with(*ext).then_some("viewer")));
+/// openers.subscribe(closure!(ref ext=".txt" => move |f| f.ends_with(*ext).then_some("editor")));
+/// assert_eq!(openers.emit_with(("notes.txt",), FirstSome::new()), Some("editor"));
+/// assert_eq!(openers.emit_with(("song.mp3",), FirstSome::new()), None);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct FirstSome<T>(Option<T>);
+impl<T> Default for FirstSome<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<T> FirstSome<T> {
+    pub const fn new() -> Self {
+        Self(None)
+    }
+}
+impl<T> Aggregate<Option<T>> for FirstSome<T> {
+    type Output = Option<T>;
+    fn add(&mut self, item: Option<T>) -> ControlFlow<()> {
+        match item {
+            Some(t) => {
+                self.0 = Some(t);
+                ControlFlow::Break(())
+            }
+            None => ControlFlow::Continue(()),
+        }
+    }
+    fn finish(self) -> Option<T> {
+        self.0
+    }
+}
+
+/// Every output, in the order the handlers were called.
+#[derive(Clone, Debug)]
+pub struct CollectAll<T>(Vec<T>);
+impl<T> Default for CollectAll<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<T> CollectAll<T> {
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+}
+impl<T> Aggregate<T> for CollectAll<T> {
+    type Output = Vec<T>;
+    fn add(&mut self, item: T) -> ControlFlow<()> {
+        self.0.push(item);
+        ControlFlow::Continue(())
+    }
+    fn finish(self) -> Vec<T> {
+        self.0
+    }
+}
+
+/// The outputs combined by `F`, starting from an initial accumulator.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{std_fns,ClosureRef,EventEmitter,Fold};
+/// type Quota = ClosureRef<u32,(),u32>;
+/// let quotas:EventEmitter<Quota> = EventEmitter::new();
+/// quotas.subscribe(closure!(ref mb=100 => move || *mb));
+/// quotas.subscribe(closure!(ref mb=250 => move || *mb));
+/// assert_eq!(quotas.emit_with((), Fold::new(0, std_fns::add())), 350);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Fold<Acc, F> {
+    // only `None` while `f` runs
+    acc: Option<Acc>,
+    f: F,
+}
+impl<Acc, F> Fold<Acc, F> {
+    pub const fn new(init: Acc, f: F) -> Self {
+        Self { acc: Some(init), f }
+    }
+}
+impl<Acc, F, T> Aggregate<T> for Fold<Acc, F>
+where
+    F: StableFnMut<(Acc, T), Output=Acc>
+{
+    type Output = Acc;
+    fn add(&mut self, item: T) -> ControlFlow<()> {
+        if let Some(acc) = self.acc.take() {
+            self.acc = Some(self.f.stable_call_mut((acc, item)));
+        }
+        ControlFlow::Continue(())
+    }
+    fn finish(self) -> Acc {
+        match self.acc {
+            Some(acc) => acc,
+            None => unreachable!("the accumulator is restored after every call"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ops::ControlFlow;
+    use crate::{Aggregate,ClosureRef,CollectAll,FirstSome,Fold};
+
+    #[test]
+    fn test_aggregates() {
+        let mut first = FirstSome::new();
+        assert_eq!(first.add(None), ControlFlow::Continue(()));
+        assert_eq!(first.add(Some(2)), ControlFlow::Break(()));
+        assert_eq!(first.finish(), Some(2));
+        let mut all = CollectAll::new();
+        assert_eq!(all.add('a'), ControlFlow::Continue(()));
+        assert_eq!(all.add('b'), ControlFlow::Continue(()));
+        assert_eq!(all.finish(), ['a', 'b']);
+        let max_len:ClosureRef<(),(usize,&str),usize> = closure!(ref _s=() => move |max, s| max.max(s.len()));
+        let mut longest = Fold::new(0, max_len);
+        assert_eq!(longest.add("ab"), ControlFlow::Continue(()));
+        assert_eq!(longest.add("abcd"), ControlFlow::Continue(()));
+        assert_eq!(longest.finish(), 4);
+    }
+}
@@ -0,0 +1,136 @@
+//! One type for all five closure kinds.
+//!
+//! An API that wants to accept "whichever closure flavour the caller has" can
+//! take an `AnyClosureKind` instead of being generic (which leaks into its own
+//! signature) or offering five overloads. Every closure struct converts into it
+//! with `From`/`Into`.
+
+use crate::closures::{Closure,ClosureMut,ClosureOnce,ClosureRef,ClosureRefMut};
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// Any of `Closure`, `ClosureRef`, `ClosureMut`, `ClosureRefMut` and `ClosureOnce`
+/// with the same state, input and output types.
+///
+/// `StableFnOnce` is always implemented. `StableFnMut` requires `State: Copy`,
+/// as it does for `ClosureOnce`; `try_call_mut` is available without it.
+/// `StableFn` is not implemented because `ClosureMut` cannot support it.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{AnyClosureKind,ClosureOnce,ClosureRef,StableFnOnce};
+/// fn run<'a>(c: impl Into<AnyClosureKind<'a,i32,(i32,),i32>>) -> i32 {
+///     c.into().stable_call_once((1,))
+/// }
+/// let by_ref:ClosureRef<i32,(i32,),i32> = closure!(ref s=10 => move |i| i+*s);
+/// let by_val:ClosureOnce<i32,(i32,),i32> = closure!(s=20 => move |i| i+s);
+/// assert_eq!(run(by_ref), 11);
+/// assert_eq!(run(by_val), 21);
+/// ```
+pub enum AnyClosureKind<'a, State, Input, Output>
+where
+    State: 'a
+{
+    Closure(Closure<'a, State, Input, Output>),
+    Ref(ClosureRef<State, Input, Output>),
+    Mut(ClosureMut<'a, State, Input, Output>),
+    RefMut(ClosureRefMut<State, Input, Output>),
+    Once(ClosureOnce<State, Input, Output>),
+}
+impl<'a, State, Input, Output> AnyClosureKind<'a, State, Input, Output> {
+    /// Returns `true` if the closure can be called more than once without
+    /// requiring `State: Copy`.
+    pub fn is_reusable(&self) -> bool {
+        !matches!(*self, AnyClosureKind::Once(_))
+    }
+    /// Calls a reusable closure. Hands the input back for `Once`.
+    pub fn try_call_mut(&mut self, i: Input) -> Result<Output, Input> {
+        match *self {
+            AnyClosureKind::Closure(ref mut c) => Ok(c.stable_call_mut(i)),
+            AnyClosureKind::Ref(ref mut c) => Ok(c.stable_call_mut(i)),
+            AnyClosureKind::Mut(ref mut c) => Ok(c.stable_call_mut(i)),
+            AnyClosureKind::RefMut(ref mut c) => Ok(c.stable_call_mut(i)),
+            AnyClosureKind::Once(_) => Err(i),
+        }
+    }
+}
+impl<'a, State, Input, Output> StableFnOnce<Input> for AnyClosureKind<'a, State, Input, Output> {
+    type Output = Output;
+    fn stable_call_once(self, i: Input) -> Output {
+        match self {
+            AnyClosureKind::Closure(c) => c.stable_call_once(i),
+            AnyClosureKind::Ref(c) => c.stable_call_once(i),
+            AnyClosureKind::Mut(c) => c.stable_call_once(i),
+            AnyClosureKind::RefMut(c) => c.stable_call_once(i),
+            AnyClosureKind::Once(c) => c.stable_call_once(i),
+        }
+    }
+}
+impl<'a, State, Input, Output> StableFnMut<Input> for AnyClosureKind<'a, State, Input, Output>
+where
+    State: Copy
+{
+    fn stable_call_mut(&mut self, i: Input) -> Output {
+        match *self {
+            AnyClosureKind::Closure(ref mut c) => c.stable_call_mut(i),
+            AnyClosureKind::Ref(ref mut c) => c.stable_call_mut(i),
+            AnyClosureKind::Mut(ref mut c) => c.stable_call_mut(i),
+            AnyClosureKind::RefMut(ref mut c) => c.stable_call_mut(i),
+            AnyClosureKind::Once(ref c) => c.stable_call(i),
+        }
+    }
+}
+
+impl<'a, State, Input, Output> From<Closure<'a, State, Input, Output>> for AnyClosureKind<'a, State, Input, Output> {
+    fn from(c: Closure<'a, State, Input, Output>) -> Self {
+        AnyClosureKind::Closure(c)
+    }
+}
+impl<'a, State, Input, Output> From<ClosureRef<State, Input, Output>> for AnyClosureKind<'a, State, Input, Output> {
+    fn from(c: ClosureRef<State, Input, Output>) -> Self {
+        AnyClosureKind::Ref(c)
+    }
+}
+impl<'a, State, Input, Output> From<ClosureMut<'a, State, Input, Output>> for AnyClosureKind<'a, State, Input, Output> {
+    fn from(c: ClosureMut<'a, State, Input, Output>) -> Self {
+        AnyClosureKind::Mut(c)
+    }
+}
+impl<'a, State, Input, Output> From<ClosureRefMut<State, Input, Output>> for AnyClosureKind<'a, State, Input, Output> {
+    fn from(c: ClosureRefMut<State, Input, Output>) -> Self {
+        AnyClosureKind::RefMut(c)
+    }
+}
+impl<'a, State, Input, Output> From<ClosureOnce<State, Input, Output>> for AnyClosureKind<'a, State, Input, Output> {
+    fn from(c: ClosureOnce<State, Input, Output>) -> Self {
+        AnyClosureKind::Once(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AnyClosureKind,Closure,ClosureMut,ClosureOnce,ClosureRefMut,StableFnMut};
+
+    #[test]
+    fn test_mixed_kinds() {
+        let base = 100;
+        let mut counter = 0;
+        {
+            let mut kinds:Vec<AnyClosureKind<i32,(i32,),i32>> = vec![
+                Closure::new(|s, (i,)| i+*s, &base).into(),
+                ClosureRefMut::new(|s, (i,)| {*s+=i;*s}, 0).into(),
+                ClosureMut::new(|s, (i,)| {*s+=1;i+*s}, &mut counter).into(),
+                ClosureOnce::new(|s, (i,)| i*s, 3).into(),
+            ];
+            let first:Vec<_> = kinds.iter_mut().map(|c| c.stable_call_mut((2,))).collect();
+            assert_eq!(first, vec![102,2,3,6]);
+            let second:Vec<_> = kinds.iter_mut().map(|c| c.stable_call_mut((2,))).collect();
+            assert_eq!(second, vec![102,4,4,6]);
+            assert!(!kinds[3].is_reusable());
+            assert_eq!(kinds[3].try_call_mut((5,)), Err((5,)));
+            assert_eq!(kinds[1].try_call_mut((5,)), Ok(9));
+        }
+        assert_eq!(counter, 2);
+    }
+}
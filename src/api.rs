@@ -0,0 +1,89 @@
+//! Callback types for public APIs.
+//!
+//! Libraries that take callbacks should spell them with these aliases, so a
+//! caller can hand the same closure to any of them:
+//!
+//! | alias               | closure                          | called to      |
+//! |---------------------|----------------------------------|----------------|
+//! | `OnEvent<State, E>` | `ClosureRefMut<State, (E,), ()>` | react to `E`   |
+//! | `Setter<State, T>`  | `ClosureRefMut<State, (T,), ()>` | store a `T`    |
+//! | `Getter<State, T>`  | `ClosureRef<State, (), T>`       | read a `T`     |
+//! | `Factory<State, T>` | `ClosureRefMut<State, (), T>`    | make a new `T` |
+//!
+//! Callbacks own their state, as the `move` closures do, and only getters
+//! are barred from changing it. A plain function (or a closure that captures
+//! nothing) converts into each of them, keeping the function pointer as the
+//! state.
+
+use crate::closures::{ClosureRef,ClosureRefMut};
+
+/// A handler called with each event of type `E`.
+pub type OnEvent<State, E> = ClosureRefMut<State, (E,), ()>;
+/// A callback that stores the values it is called with.
+pub type Setter<State, T> = ClosureRefMut<State, (T,), ()>;
+/// A callback that reads a value without changing its state.
+pub type Getter<State, T> = ClosureRef<State, (), T>;
+/// A callback that makes a new value on each call.
+pub type Factory<State, T> = ClosureRefMut<State, (), T>;
+
+/// An `OnEvent` (or `Setter`) calling a plain function.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::StableFnMut;
+/// # use namable_closures::api::{on_event,OnEvent};
+/// fn connect<S>(mut on_key: OnEvent<S,char>) {
+///     on_key.stable_call_mut(('q',));
+/// }
+/// connect(on_event(|key| assert_eq!(key, 'q')));
+/// ```
+pub fn on_event<E>(f: fn(E)) -> OnEvent<fn(E), E> {
+    f.into()
+}
+/// A `Getter` (or, with `into`, a `Factory`) calling a plain function.
+pub fn getter<T>(f: fn() -> T) -> Getter<fn() -> T, T> {
+    f.into()
+}
+
+impl<T> From<fn(T)> for ClosureRefMut<fn(T), (T,), ()> {
+    fn from(f: fn(T)) -> Self {
+        ClosureRefMut::new(|f, (t,)| f(t), f)
+    }
+}
+impl<T> From<fn() -> T> for ClosureRef<fn() -> T, (), T> {
+    fn from(f: fn() -> T) -> Self {
+        ClosureRef::new(|f, ()| f(), f)
+    }
+}
+impl<T> From<fn() -> T> for ClosureRefMut<fn() -> T, (), T> {
+    fn from(f: fn() -> T) -> Self {
+        ClosureRefMut::new(|f, ()| f(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{getter,on_event,Factory,Getter,OnEvent,Setter};
+    use crate::{StableFn,StableFnMut};
+
+    struct Widget<S1, S2> {
+        on_click: OnEvent<S1, (i32, i32)>,
+        label: Getter<S2, &'static str>,
+    }
+
+    #[test]
+    fn test_aliases_and_conversions() {
+        let mut w = Widget {
+            on_click: closure!(ref mut n=0 => move |_pos| *n+=1),
+            label: getter(|| "OK"),
+        };
+        w.on_click.stable_call_mut(((1, 2),));
+        assert_eq!(*w.on_click.state(), 1);
+        assert_eq!(w.label.stable_call(()), "OK");
+        let mut quiet:Setter<fn(bool),bool> = on_event(|_| ());
+        quiet.stable_call_mut((true,));
+        let mut zero:Factory<fn() -> Vec<u8>,Vec<u8>> = (Vec::new as fn() -> Vec<u8>).into();
+        assert!(zero.stable_call_mut(()).is_empty());
+    }
+}
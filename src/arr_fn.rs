@@ -0,0 +1,206 @@
+//! Array based inputs, as an alternative to tuples.
+//!
+//! A closure whose `Input` is `[T; N]` takes `N` arguments of the same type.
+//! Unlike tuples, arrays let generic code abstract over the number of arguments
+//! with a const generic, which is handy for homogeneous callbacks such as
+//! per-channel sensor handlers.
+//!
+//! `StableFnOnceArr`, `StableFnMutArr` and `StableFnArr` are implemented for every
+//! closure taking an array, and the `Arrayed`/`Tupled` adapters convert between
+//! the array and tuple conventions.
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// Like `StableFnOnce`, for `N` arguments of type `T` passed as an array.
+pub trait StableFnOnceArr<T, const N: usize> {
+    type Output;
+    fn stable_call_once_arr(self, args:[T; N]) -> Self::Output;
+}
+/// Like `StableFnMut`, for `N` arguments of type `T` passed as an array.
+pub trait StableFnMutArr<T, const N: usize>: StableFnOnceArr<T, N> {
+    fn stable_call_mut_arr(&mut self, args:[T; N]) -> Self::Output;
+}
+/// Like `StableFn`, for `N` arguments of type `T` passed as an array.
+pub trait StableFnArr<T, const N: usize>: StableFnMutArr<T, N> {
+    fn stable_call_arr(&self, args:[T; N]) -> Self::Output;
+}
+
+impl<F, T, const N: usize> StableFnOnceArr<T, N> for F
+where
+    F: StableFnOnce<[T; N]>
+{
+    type Output = F::Output;
+    fn stable_call_once_arr(self, args:[T; N]) -> F::Output {
+        self.stable_call_once(args)
+    }
+}
+impl<F, T, const N: usize> StableFnMutArr<T, N> for F
+where
+    F: StableFnMut<[T; N]>
+{
+    fn stable_call_mut_arr(&mut self, args:[T; N]) -> F::Output {
+        self.stable_call_mut(args)
+    }
+}
+impl<F, T, const N: usize> StableFnArr<T, N> for F
+where
+    F: StableFn<[T; N]>
+{
+    fn stable_call_arr(&self, args:[T; N]) -> F::Output {
+        self.stable_call(args)
+    }
+}
+
+/// Conversion from a homogeneous tuple to an array.
+pub trait IntoArray<T, const N: usize> {
+    fn into_array(self) -> [T; N];
+}
+/// Conversion from an array to a homogeneous tuple.
+pub trait FromArray<T, const N: usize> {
+    fn from_array(args:[T; N]) -> Self;
+}
+
+/// Makes a closure over a homogeneous tuple callable with an array.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,StableFnArr};
+/// # use namable_closures::arr_fn::Arrayed;
+/// let sum:ClosureRef<(),(i32,i32,i32),i32> = closure!(ref s=() => move |a,b,c| a+b+c);
+/// assert_eq!(Arrayed(sum).stable_call_arr([1,2,3]), 6);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Arrayed<F>(pub F);
+
+/// Makes a closure over an array callable with a homogeneous tuple.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::{ClosureRef,StableFn};
+/// # use namable_closures::arr_fn::Tupled;
+/// let avg:ClosureRef<(),[f32;2],f32>
+///     = ClosureRef::new(|_, xs| xs.iter().sum::<f32>() / 2.0, ());
+/// assert_eq!(Tupled(avg).stable_call((1.0,2.0)), 1.5);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Tupled<F>(pub F);
+
+macro_rules! arr_arity {
+    ($n:expr; $($t:ident $a:ident),+) => {
+        impl<T> IntoArray<T, $n> for ($($t,)+) {
+            fn into_array(self) -> [T; $n] {
+                let ($($a,)+) = self;
+                [$($a),+]
+            }
+        }
+        impl<T> FromArray<T, $n> for ($($t,)+) {
+            fn from_array(args:[T; $n]) -> Self {
+                let [$($a),+] = args;
+                ($($a,)+)
+            }
+        }
+        impl<F, T> StableFnOnce<[T; $n]> for Arrayed<F>
+        where
+            F: StableFnOnce<($($t,)+)>
+        {
+            type Output = F::Output;
+            fn stable_call_once(self, args:[T; $n]) -> F::Output {
+                self.0.stable_call_once(FromArray::from_array(args))
+            }
+        }
+        impl<F, T> StableFnMut<[T; $n]> for Arrayed<F>
+        where
+            F: StableFnMut<($($t,)+)>
+        {
+            fn stable_call_mut(&mut self, args:[T; $n]) -> F::Output {
+                self.0.stable_call_mut(FromArray::from_array(args))
+            }
+        }
+        impl<F, T> StableFn<[T; $n]> for Arrayed<F>
+        where
+            F: StableFn<($($t,)+)>
+        {
+            fn stable_call(&self, args:[T; $n]) -> F::Output {
+                self.0.stable_call(FromArray::from_array(args))
+            }
+        }
+        impl<F, T> StableFnOnce<($($t,)+)> for Tupled<F>
+        where
+            F: StableFnOnce<[T; $n]>
+        {
+            type Output = F::Output;
+            fn stable_call_once(self, args:($($t,)+)) -> F::Output {
+                self.0.stable_call_once(args.into_array())
+            }
+        }
+        impl<F, T> StableFnMut<($($t,)+)> for Tupled<F>
+        where
+            F: StableFnMut<[T; $n]>
+        {
+            fn stable_call_mut(&mut self, args:($($t,)+)) -> F::Output {
+                self.0.stable_call_mut(args.into_array())
+            }
+        }
+        impl<F, T> StableFn<($($t,)+)> for Tupled<F>
+        where
+            F: StableFn<[T; $n]>
+        {
+            fn stable_call(&self, args:($($t,)+)) -> F::Output {
+                self.0.stable_call(args.into_array())
+            }
+        }
+    };
+}
+arr_arity!(1; T a0);
+arr_arity!(2; T a0, T a1);
+arr_arity!(3; T a0, T a1, T a2);
+arr_arity!(4; T a0, T a1, T a2, T a3);
+arr_arity!(5; T a0, T a1, T a2, T a3, T a4);
+arr_arity!(6; T a0, T a1, T a2, T a3, T a4, T a5);
+arr_arity!(7; T a0, T a1, T a2, T a3, T a4, T a5, T a6);
+arr_arity!(8; T a0, T a1, T a2, T a3, T a4, T a5, T a6, T a7);
+arr_arity!(9; T a0, T a1, T a2, T a3, T a4, T a5, T a6, T a7, T a8);
+arr_arity!(10; T a0, T a1, T a2, T a3, T a4, T a5, T a6, T a7, T a8, T a9);
+arr_arity!(11; T a0, T a1, T a2, T a3, T a4, T a5, T a6, T a7, T a8, T a9, T a10);
+arr_arity!(12; T a0, T a1, T a2, T a3, T a4, T a5, T a6, T a7, T a8, T a9, T a10, T a11);
+
+#[cfg(test)]
+mod tests {
+    use crate::arr_fn::{Arrayed,FromArray,IntoArray,StableFnArr,StableFnMutArr,Tupled};
+    use crate::{ClosureRef,ClosureRefMut,StableFn};
+
+    fn sum_channels<F, const N: usize>(f:&F, channels:[i32; N]) -> i32
+    where
+        F: StableFnArr<i32, N, Output=i32>
+    {
+        f.stable_call_arr(channels)
+    }
+
+    #[test]
+    fn test_array_input() {
+        let scale:ClosureRef<i32,[i32;4],i32>
+            = ClosureRef::new(|s, xs| xs.iter().map(|x| x * *s).sum(), 2);
+        assert_eq!(sum_channels(&scale, [1,2,3,4]), 20);
+        let mut peak:ClosureRefMut<i32,[i32;3],i32>
+            = ClosureRefMut::new(|s, xs| {
+                *s = xs.iter().cloned().fold(*s, i32::max);
+                *s
+            }, i32::MIN);
+        assert_eq!(peak.stable_call_mut_arr([1,5,3]), 5);
+        assert_eq!(peak.stable_call_mut_arr([4,2,0]), 5);
+    }
+    #[test]
+    fn test_conversions() {
+        let t:(u8,u8,u8) = FromArray::from_array([1,2,3]);
+        assert_eq!(t, (1,2,3));
+        assert_eq!((4,5).into_array(), [4,5]);
+        let add:ClosureRef<(),(i32,i32),i32> = closure!(ref _s=() => move |a,b| a+b);
+        assert_eq!(sum_channels(&Arrayed(add), [3,4]), 7);
+        let prod:ClosureRef<(),[i32;3],i32>
+            = ClosureRef::new(|_, xs| xs.iter().product(), ());
+        assert_eq!(Tupled(prod).stable_call((2,3,4)), 24);
+    }
+}
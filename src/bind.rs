@@ -0,0 +1,109 @@
+//! Closures with their arguments bound.
+//!
+//! A queue of "call this later with these arguments" entries needs one type
+//! for all of them. `Bind` stores the arguments next to the closure, so a
+//! `Bind<ClosureRef<State, (A, B), O>, (A, B)>` is such an entry, callable with
+//! `()`, with no boxed lambda per entry.
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// Calls `F` with the bound arguments, ignoring its own (empty) ones.
+///
+/// Calling by `&mut` or `&` clones the arguments, so the thunk can be run
+/// again.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{Bind,ClosureRef,StableFn};
+/// type Log = ClosureRef<&'static str,(u32,&'static str),String>;
+/// let log:Log = closure!(ref level="warn" => move |line, msg| format!("{} {}: {}", level, line, msg));
+/// let later = [Bind::new(log, (10, "disk full")), Bind::new(log, (12, "retrying"))];
+/// let lines:Vec<String> = later.iter().map(|b| b.stable_call(())).collect();
+/// assert_eq!(lines, ["warn 10: disk full", "warn 12: retrying"]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Bind<F, Input> {
+    f: F,
+    args: Input,
+}
+impl<F, Input> Bind<F, Input> {
+    pub const fn new(f: F, args: Input) -> Self {
+        Self { f, args }
+    }
+    pub fn args(&self) -> &Input {
+        &self.args
+    }
+    pub fn args_mut(&mut self) -> &mut Input {
+        &mut self.args
+    }
+    pub fn into_inner(self) -> (F, Input) {
+        (self.f, self.args)
+    }
+}
+impl<F, Input> ClosureState for Bind<F, Input>
+where
+    F: ClosureState
+{
+    type State = F::State;
+    fn state(&self) -> &F::State {
+        self.f.state()
+    }
+}
+impl<F, Input> ClosureStateMut for Bind<F, Input>
+where
+    F: ClosureStateMut
+{
+    fn state_mut(&mut self) -> &mut F::State {
+        self.f.state_mut()
+    }
+}
+impl<F, Input> StableFnOnce<()> for Bind<F, Input>
+where
+    F: StableFnOnce<Input>
+{
+    type Output = F::Output;
+    fn stable_call_once(self, (): ()) -> F::Output {
+        self.f.stable_call_once(self.args)
+    }
+}
+impl<F, Input> StableFnMut<()> for Bind<F, Input>
+where
+    F: StableFnMut<Input>,
+    Input: Clone
+{
+    fn stable_call_mut(&mut self, (): ()) -> F::Output {
+        self.f.stable_call_mut(self.args.clone())
+    }
+}
+impl<F, Input> StableFn<()> for Bind<F, Input>
+where
+    F: StableFn<Input>,
+    Input: Clone
+{
+    fn stable_call(&self, (): ()) -> F::Output {
+        self.f.stable_call(self.args.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Bind,ClosureOnce,ClosureRefMut,ClosureState,StableFnMut,StableFnOnce};
+
+    #[test]
+    fn test_bound_calls() {
+        let push:ClosureRefMut<Vec<u8>,(u8,u8),usize> = closure!(ref mut v=vec![] => move |a,b| {v.push(a+b); v.len()});
+        let mut push = Bind::new(push, (1, 2));
+        assert_eq!(push.stable_call_mut(()), 1);
+        push.args_mut().1 = 5;
+        assert_eq!(push.stable_call_mut(()), 2);
+        assert_eq!(push.state(), &[3, 6]);
+        let (_, args) = push.into_inner();
+        assert_eq!(args, (1, 5));
+        // arguments that cannot be cloned can still be bound for one call
+        let take:ClosureOnce<(),(String,),usize> = closure!(_s=() => move |s| s.len());
+        assert_eq!(Bind::new(take, (String::from("abc"),)).stable_call_once(()), 3);
+    }
+}
@@ -0,0 +1,92 @@
+//! Running a future to completion on the current thread.
+//!
+//! `RemoteCall` and `JobHandle` are futures as well as blocking waits. Tests
+//! and small programs that want to `.await` them, or combine them in an
+//! `async` block, can run the result with `block_on` instead of bringing in an
+//! executor.
+//!
+//! Only available with the `std` feature.
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context,Poll,Wake,Waker};
+use std::thread::{self,Thread};
+
+// Wakes the blocked thread by unparking it.
+struct Unpark(Thread);
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark()
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark()
+    }
+}
+
+/// Polls `future` on the current thread, parking the thread while it is
+/// pending, and returns its output.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{block_on,ClosureOnce,JobPool};
+/// let pool:JobPool<ClosureOnce<u32,(),u32>> = JobPool::new(2);
+/// let a = pool.submit(closure!(n=20 => move || n + 1));
+/// let b = pool.submit(closure!(n=2 => move || n * 10));
+/// let sum = block_on(async { a.await.unwrap() + b.await.unwrap() });
+/// assert_eq!(sum, 41);
+/// ```
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            // a wake before the park leaves a token, so it is not missed
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context,Poll};
+    use std::thread;
+    use crate::{block_on,ClosureRefMut,Error,RemoteOwner};
+
+    // Pending on its first poll, waking itself before returning.
+    struct YieldOnce(bool);
+    impl Future for YieldOnce {
+        type Output = u8;
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<u8> {
+            if self.0 {
+                return Poll::Ready(7);
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn test_block_on() {
+        assert_eq!(block_on(YieldOnce(false)), 7);
+        let add:ClosureRefMut<i32,(i32,),i32> = closure!(ref mut total=0 => move |i| {*total+=i;*total});
+        let (mut owner, remote) = RemoteOwner::new(add);
+        let waiter = thread::spawn(move || block_on(async {
+            let first = remote.call((2,)).await?;
+            Ok::<_, Error>(first + remote.call((3,)).await?)
+        }));
+        let mut served = 0;
+        while served < 2 {
+            served += owner.serve();
+            thread::yield_now();
+        }
+        assert_eq!(waiter.join().unwrap(), Ok(7));
+    }
+}
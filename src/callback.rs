@@ -0,0 +1,87 @@
+//! Short bounds for closures of a fixed arity.
+//!
+//! `CallbackN<A, .., O>` stands for `StableFn<(A, ..), Output=O>`, and likewise
+//! `CallbackMutN` for `StableFnMut` and `CallbackOnceN` for `StableFnOnce`, so
+//! public APIs can be written without spelling out tuple bounds:
+//!
+//! ```rust
+//! # use namable_closures::closure;
+//! # use namable_closures::ClosureRef;
+//! use namable_closures::callback::Callback1;
+//! struct Click { x: i32 }
+//! fn on_click(f: impl Callback1<Click, i32>) -> i32 {
+//!     f.stable_call((Click { x: 3 },))
+//! }
+//! let offset:ClosureRef<i32,(Click,),i32> = closure!(ref dx=10 => move |c| c.x + *dx);
+//! assert_eq!(on_click(offset), 13);
+//! ```
+//!
+//! The traits are implemented for every closure with a matching signature and
+//! cannot be implemented by hand.
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+mod sealed {
+    pub trait Sealed<Input> {}
+    impl<F, Input> Sealed<Input> for F
+    where
+        F: crate::stable_fn::StableFnOnce<Input> + ?Sized
+    {}
+}
+
+macro_rules! callbacks {
+    ($($once:ident, $mut_:ident, $ref_:ident ($($a:ident),*);)*) => {$(
+        #[doc = concat!("`StableFnOnce<(", $(stringify!($a), ",",)* "), Output=O>`.")]
+        pub trait $once<$($a,)* O>: StableFnOnce<($($a,)*), Output=O> + sealed::Sealed<($($a,)*)> {}
+        impl<F, $($a,)* O> $once<$($a,)* O> for F
+        where
+            F: StableFnOnce<($($a,)*), Output=O> + ?Sized
+        {}
+        #[doc = concat!("`StableFnMut<(", $(stringify!($a), ",",)* "), Output=O>`.")]
+        pub trait $mut_<$($a,)* O>: StableFnMut<($($a,)*), Output=O> + sealed::Sealed<($($a,)*)> {}
+        impl<F, $($a,)* O> $mut_<$($a,)* O> for F
+        where
+            F: StableFnMut<($($a,)*), Output=O> + ?Sized
+        {}
+        #[doc = concat!("`StableFn<(", $(stringify!($a), ",",)* "), Output=O>`.")]
+        pub trait $ref_<$($a,)* O>: StableFn<($($a,)*), Output=O> + sealed::Sealed<($($a,)*)> {}
+        impl<F, $($a,)* O> $ref_<$($a,)* O> for F
+        where
+            F: StableFn<($($a,)*), Output=O> + ?Sized
+        {}
+    )*};
+}
+callbacks! {
+    CallbackOnce0, CallbackMut0, Callback0 ();
+    CallbackOnce1, CallbackMut1, Callback1 (A);
+    CallbackOnce2, CallbackMut2, Callback2 (A, B);
+    CallbackOnce3, CallbackMut3, Callback3 (A, B, C);
+    CallbackOnce4, CallbackMut4, Callback4 (A, B, C, D);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::callback::{Callback0,CallbackMut2,CallbackOnce1};
+    use crate::{ClosureOnce,ClosureRef,ClosureRefMut};
+
+    fn twice(mut f: impl CallbackMut2<i32, i32, i32>) -> i32 {
+        f.stable_call_mut((1, 2));
+        f.stable_call_mut((1, 2))
+    }
+    fn finish(f: impl CallbackOnce1<String, usize>) -> usize {
+        f.stable_call_once(("done".to_string(),))
+    }
+
+    #[test]
+    fn test_bounds_accept_every_kind() {
+        let sum:ClosureRefMut<i32,(i32,i32),i32> = closure!(ref mut n=0 => move |a,b| {*n+=a+b;*n});
+        assert_eq!(twice(sum), 6);
+        let len:ClosureOnce<usize,(String,),usize> = closure!(extra=1 => move |s| s.len()+extra);
+        assert_eq!(finish(len), 5);
+        let seven:ClosureRef<i32,(),i32> = closure!(ref n=7 => move || *n);
+        let f:&dyn Callback0<i32> = &seven;
+        assert_eq!(f.stable_call(()), 7);
+        let add:ClosureRef<i32,(i32,i32),i32> = ClosureRef::new(|n, (a, b)| n + a + b, 5);
+        assert_eq!(twice(add), 8);
+    }
+}
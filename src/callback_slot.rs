@@ -0,0 +1,128 @@
+//! An optional callback field.
+//!
+//! Structs that expose an optional hook usually store an `Option<C>` and repeat
+//! the same `if let Some(ref mut c) = self.hook { ... }` dance at every call
+//! site. `CallbackSlot` wraps that pattern once.
+
+use core::fmt;
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// An optional namable closure of type `C`.
+///
+/// `Debug` is implemented without requiring `C: Debug` and only reports
+/// whether the slot is set.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{CallbackSlot,ClosureRef};
+/// struct Button {
+///     on_click: CallbackSlot<ClosureRef<&'static str,(u32,),String>>,
+/// }
+/// let mut button = Button { on_click: CallbackSlot::default() };
+/// assert_eq!(button.on_click.call_if_set((1,)), None);
+/// button.on_click.set(closure!(ref name="ok" => move |n| format!("{} x{}", name, n)));
+/// assert_eq!(button.on_click.call_if_set((2,)), Some("ok x2".to_string()));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CallbackSlot<C> {
+    c: Option<C>,
+}
+impl<C> Default for CallbackSlot<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<C> From<Option<C>> for CallbackSlot<C> {
+    fn from(c: Option<C>) -> Self {
+        Self { c }
+    }
+}
+impl<C> CallbackSlot<C> {
+    /// An empty slot.
+    pub fn new() -> Self {
+        Self { c: None }
+    }
+    /// A slot holding `c`.
+    pub fn with(c: C) -> Self {
+        Self { c: Some(c) }
+    }
+    pub fn is_set(&self) -> bool {
+        self.c.is_some()
+    }
+    /// Stores `c`, returning the callback it replaced.
+    pub fn set(&mut self, c: C) -> Option<C> {
+        self.c.replace(c)
+    }
+    /// Empties the slot, returning the callback it held.
+    pub fn clear(&mut self) -> Option<C> {
+        self.c.take()
+    }
+    pub fn get(&self) -> Option<&C> {
+        self.c.as_ref()
+    }
+    pub fn get_mut(&mut self) -> Option<&mut C> {
+        self.c.as_mut()
+    }
+    pub fn into_inner(self) -> Option<C> {
+        self.c
+    }
+    /// Calls the callback by reference if the slot is set.
+    pub fn call_if_set<Input>(&self, args: Input) -> Option<C::Output>
+    where
+        C: StableFn<Input>
+    {
+        self.c.as_ref().map(|c| c.stable_call(args))
+    }
+    /// Calls the callback mutably if the slot is set.
+    pub fn call_mut_if_set<Input>(&mut self, args: Input) -> Option<C::Output>
+    where
+        C: StableFnMut<Input>
+    {
+        self.c.as_mut().map(|c| c.stable_call_mut(args))
+    }
+    /// Takes the callback out of the slot and consumes it, if the slot is set.
+    pub fn call_once_if_set<Input>(&mut self, args: Input) -> Option<C::Output>
+    where
+        C: StableFnOnce<Input>
+    {
+        self.c.take().map(|c| c.stable_call_once(args))
+    }
+}
+impl<C> fmt::Debug for CallbackSlot<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = if self.is_set() { "set" } else { "unset" };
+        f.debug_tuple("CallbackSlot").field(&format_args!("{}", state)).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CallbackSlot,ClosureOnce,ClosureRefMut};
+
+    #[test]
+    fn test_set_clear_call() {
+        let mut slot:CallbackSlot<ClosureRefMut<i32,(i32,),i32>> = CallbackSlot::new();
+        assert!(!slot.is_set());
+        assert_eq!(slot.call_mut_if_set((1,)), None);
+        assert_eq!(format!("{:?}", slot), "CallbackSlot(unset)");
+        assert!(slot.set(closure!(ref mut n=0 => move |i| {*n+=i;*n})).is_none());
+        assert_eq!(slot.call_mut_if_set((1,)), Some(1));
+        assert_eq!(slot.call_mut_if_set((2,)), Some(3));
+        assert_eq!(format!("{:?}", slot), "CallbackSlot(set)");
+        let old = slot.set(closure!(ref mut n=10 => move |i| {*n+=i;*n}));
+        assert!(old.is_some());
+        assert_eq!(slot.call_if_set((1,)), Some(11));
+        assert!(slot.clear().is_some());
+        assert!(!slot.is_set());
+    }
+    #[test]
+    fn test_call_once() {
+        let mut slot = CallbackSlot::with(closure!(s=String::from("done") => move || s));
+        let _:&CallbackSlot<ClosureOnce<String,(),String>> = &slot;
+        assert_eq!(slot.call_once_if_set(()), Some("done".to_string()));
+        assert_eq!(slot.call_once_if_set(()), None);
+    }
+}
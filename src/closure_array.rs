@@ -0,0 +1,211 @@
+//! An ordered list of homogeneous namable closures.
+//!
+//! `ClosureArray` is the simplest container for closures of one namable type:
+//! a `Vec` with broadcast calls. Use it when handlers are only ever added and
+//! all called together; `ClosureSlab` is the choice when they must also be
+//! removed individually.
+
+use alloc::vec::{self,Vec};
+use core::fmt;
+use core::iter::FromIterator;
+use core::ops::{Index,IndexMut};
+use core::slice;
+
+use crate::collection::ClosureCollection;
+use crate::error::Error;
+use crate::stable_fn::{StableFn,StableFnMut};
+use crate::state::ClosureStateMut;
+
+/// An ordered collection of closures of type `C`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureArray,ClosureRefMut};
+/// let mut counters:ClosureArray<ClosureRefMut<i32,(i32,),i32>> = ClosureArray::new();
+/// counters.push(closure!(ref mut n=0 => move |i| {*n+=i;*n}));
+/// counters.push(closure!(ref mut n=10 => move |i| {*n+=i;*n}));
+/// counters.call_all_mut((1,));
+/// // reset every counter at once
+/// for n in counters.states_mut() { *n = 0; }
+/// assert_eq!(counters.call_all_mut((2,)), vec![2,2]);
+/// ```
+pub struct ClosureArray<C> {
+    closures: Vec<C>,
+}
+impl<C> Default for ClosureArray<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<C> ClosureArray<C> {
+    pub fn new() -> Self {
+        Self { closures: Vec::new() }
+    }
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { closures: Vec::with_capacity(capacity) }
+    }
+    pub fn len(&self) -> usize {
+        self.closures.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.closures.is_empty()
+    }
+    pub fn push(&mut self, c: C) {
+        self.closures.push(c)
+    }
+    pub fn pop(&mut self) -> Option<C> {
+        self.closures.pop()
+    }
+    pub fn get(&self, index: usize) -> Option<&C> {
+        self.closures.get(index)
+    }
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut C> {
+        self.closures.get_mut(index)
+    }
+    pub fn clear(&mut self) {
+        self.closures.clear()
+    }
+    pub fn iter(&self) -> slice::Iter<'_, C> {
+        self.closures.iter()
+    }
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, C> {
+        self.closures.iter_mut()
+    }
+    /// Keeps only the closures for which `keep` returns `true`, in order.
+    pub fn retain<P>(&mut self, keep: P)
+    where
+        P: FnMut(&mut C) -> bool
+    {
+        self.closures.retain_mut(keep)
+    }
+    /// Iterates over the state of every closure, in order.
+    pub fn states_mut(&mut self) -> impl Iterator<Item=&mut C::State>
+    where
+        C: ClosureStateMut
+    {
+        self.closures.iter_mut().map(C::state_mut)
+    }
+    /// Calls the closure at `index` by reference, or reports `Error::OutOfBounds`.
+    pub fn try_call<Input>(&self, index: usize, args: Input) -> Result<C::Output, Error>
+    where
+        C: StableFn<Input>
+    {
+        self.get(index).map(|c| c.stable_call(args)).ok_or(Error::OutOfBounds)
+    }
+    /// Calls the closure at `index` mutably, or reports `Error::OutOfBounds`.
+    pub fn try_call_mut<Input>(&mut self, index: usize, args: Input) -> Result<C::Output, Error>
+    where
+        C: StableFnMut<Input>
+    {
+        self.get_mut(index).map(|c| c.stable_call_mut(args)).ok_or(Error::OutOfBounds)
+    }
+    /// Calls every closure by reference with a clone of `args`, collecting the outputs.
+    pub fn call_all<Input>(&self, args: Input) -> Vec<C::Output>
+    where
+        C: StableFn<Input>,
+        Input: Clone
+    {
+        self.closures.iter().map(|c| c.stable_call(args.clone())).collect()
+    }
+    /// Calls every closure mutably with a clone of `args`, collecting the outputs.
+    pub fn call_all_mut<Input>(&mut self, args: Input) -> Vec<C::Output>
+    where
+        C: StableFnMut<Input>,
+        Input: Clone
+    {
+        self.closures.iter_mut().map(|c| c.stable_call_mut(args.clone())).collect()
+    }
+}
+impl<C> From<Vec<C>> for ClosureArray<C> {
+    fn from(closures: Vec<C>) -> Self {
+        Self { closures }
+    }
+}
+impl<C> FromIterator<C> for ClosureArray<C> {
+    fn from_iter<I: IntoIterator<Item=C>>(iter: I) -> Self {
+        Self { closures: iter.into_iter().collect() }
+    }
+}
+impl<C> ClosureCollection for ClosureArray<C> {
+    type Closure = C;
+    type Iter<'a> = slice::Iter<'a, C> where C: 'a;
+    type IterMut<'a> = slice::IterMut<'a, C> where C: 'a;
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> slice::Iter<'_, C> {
+        self.iter()
+    }
+    fn iter_mut(&mut self) -> slice::IterMut<'_, C> {
+        self.iter_mut()
+    }
+    fn retain<P>(&mut self, keep: P)
+    where
+        P: FnMut(&mut C) -> bool
+    {
+        self.retain(keep)
+    }
+}
+impl<C> IntoIterator for ClosureArray<C> {
+    type Item = C;
+    type IntoIter = vec::IntoIter<C>;
+    fn into_iter(self) -> vec::IntoIter<C> {
+        self.closures.into_iter()
+    }
+}
+impl<'a, C> IntoIterator for &'a ClosureArray<C> {
+    type Item = &'a C;
+    type IntoIter = slice::Iter<'a, C>;
+    fn into_iter(self) -> slice::Iter<'a, C> {
+        self.iter()
+    }
+}
+impl<'a, C> IntoIterator for &'a mut ClosureArray<C> {
+    type Item = &'a mut C;
+    type IntoIter = slice::IterMut<'a, C>;
+    fn into_iter(self) -> slice::IterMut<'a, C> {
+        self.iter_mut()
+    }
+}
+impl<C> Index<usize> for ClosureArray<C> {
+    type Output = C;
+    fn index(&self, index: usize) -> &C {
+        &self.closures[index]
+    }
+}
+impl<C> IndexMut<usize> for ClosureArray<C> {
+    fn index_mut(&mut self, index: usize) -> &mut C {
+        &mut self.closures[index]
+    }
+}
+impl<C> fmt::Debug for ClosureArray<C>
+where
+    C: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.closures.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureArray,ClosureRef};
+    use crate::StableFn;
+
+    #[test]
+    fn test_broadcast_and_configure() {
+        let mut scales:ClosureArray<ClosureRef<i32,(i32,),i32>> = (1..4)
+            .map(|k| closure!(ref k=k => move |i| i**k))
+            .collect();
+        assert_eq!(scales.call_all((2,)), vec![2,4,6]);
+        for k in scales.states_mut() {
+            *k *= 10;
+        }
+        assert_eq!(scales.call_all((1,)), vec![10,20,30]);
+        assert_eq!(*scales[1].state(), 20);
+        assert_eq!(scales.pop().map(|c| c.stable_call((1,))), Some(30));
+        assert_eq!(scales.len(), 2);
+    }
+}
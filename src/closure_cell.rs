@@ -0,0 +1,286 @@
+//! A closure shared with interrupt handlers.
+//!
+//! `StaticClosureCell` is a `CallbackSlot` that can live in a `static` and be
+//! swapped or called through a shared reference. Every access runs inside a
+//! `critical_section::with`, so it also works on cores without compare-and-swap
+//! (Cortex-M0, AVR, MSP430) as long as the program links a critical section
+//! implementation for its platform.
+//!
+//! `IsrNotify` pairs such a cell with a waker slot, so that an interrupt
+//! handler can wake an async task waiting for the event.
+//!
+//! Only available with the `critical-section` feature.
+
+use core::cell::RefCell;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context,Poll,Waker};
+
+use critical_section::Mutex;
+
+use crate::closures::ClosureRefMut;
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// An optional namable closure of type `C` behind a critical section.
+///
+/// The closure is called with the critical section held, so interrupts are
+/// masked for the duration of the call on single-core targets. The closure
+/// must not access the cell it is stored in.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRefMut,StaticClosureCell};
+/// static ON_TICK: StaticClosureCell<ClosureRefMut<u32,(),u32>> = StaticClosureCell::new();
+///
+/// fn tick_interrupt() -> Option<u32> {
+///     ON_TICK.call_mut_if_set(())
+/// }
+///
+/// assert_eq!(tick_interrupt(), None);
+/// ON_TICK.set(closure!(ref mut ticks=0 => move || {*ticks+=1;*ticks}));
+/// assert_eq!(tick_interrupt(), Some(1));
+/// assert_eq!(tick_interrupt(), Some(2));
+/// ```
+pub struct StaticClosureCell<C> {
+    c: Mutex<RefCell<Option<C>>>,
+}
+impl<C> Default for StaticClosureCell<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<C> StaticClosureCell<C> {
+    /// An empty cell.
+    pub const fn new() -> Self {
+        Self { c: Mutex::new(RefCell::new(None)) }
+    }
+    /// A cell holding `c`.
+    pub const fn with(c: C) -> Self {
+        Self { c: Mutex::new(RefCell::new(Some(c))) }
+    }
+    pub fn is_set(&self) -> bool {
+        critical_section::with(|cs| self.c.borrow(cs).borrow().is_some())
+    }
+    /// Stores `c`, returning the closure it replaced.
+    pub fn set(&self, c: C) -> Option<C> {
+        critical_section::with(|cs| self.c.borrow(cs).replace(Some(c)))
+    }
+    /// Empties the cell, returning the closure it held.
+    pub fn clear(&self) -> Option<C> {
+        critical_section::with(|cs| self.c.borrow(cs).take())
+    }
+    pub fn into_inner(self) -> Option<C> {
+        self.c.into_inner().into_inner()
+    }
+    /// Calls the closure by reference if the cell is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the closure accesses this cell mutably.
+    pub fn call_if_set<Input>(&self, args: Input) -> Option<C::Output>
+    where
+        C: StableFn<Input>
+    {
+        critical_section::with(|cs| self.c.borrow(cs).borrow().as_ref().map(|c| c.stable_call(args)))
+    }
+    /// Calls the closure mutably if the cell is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the closure accesses this cell.
+    pub fn call_mut_if_set<Input>(&self, args: Input) -> Option<C::Output>
+    where
+        C: StableFnMut<Input>
+    {
+        critical_section::with(|cs| self.c.borrow(cs).borrow_mut().as_mut().map(|c| c.stable_call_mut(args)))
+    }
+    /// Takes the closure out of the cell and consumes it, if the cell is set.
+    ///
+    /// The closure runs after the critical section is released.
+    pub fn call_once_if_set<Input>(&self, args: Input) -> Option<C::Output>
+    where
+        C: StableFnOnce<Input>
+    {
+        self.clear().map(|c| c.stable_call_once(args))
+    }
+}
+impl<C> fmt::Debug for StaticClosureCell<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = if self.is_set() { "set" } else { "unset" };
+        f.debug_tuple("StaticClosureCell").field(&format_args!("{}", state)).finish()
+    }
+}
+
+/// An interrupt handler over `State` that wakes the task awaiting it.
+///
+/// The interrupt calls `on_interrupt`, which runs the handler and, if it
+/// returns `true`, wakes the future returned by `wait`. Events signalled
+/// while nobody waits are remembered, and several of them resolve one wait.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")] {
+/// # use namable_closures::closure;
+/// # use namable_closures::{block_on,IsrNotify};
+/// // counts bytes, reporting each full packet of 4
+/// static RX: IsrNotify<u32> = IsrNotify::new();
+///
+/// fn uart_interrupt() {
+///     RX.on_interrupt();
+/// }
+///
+/// RX.handler().set(closure!(ref mut bytes=0 => move || {*bytes+=1; *bytes % 4 == 0}));
+/// let isr = std::thread::spawn(|| (0..8).for_each(|_| uart_interrupt()));
+/// block_on(RX.wait());
+/// isr.join().unwrap();
+/// assert_eq!(RX.with_state(|bytes| *bytes), Some(8));
+/// # }
+/// ```
+pub struct IsrNotify<State> {
+    handler: StaticClosureCell<ClosureRefMut<State, (), bool>>,
+    signal: Mutex<RefCell<Signal>>,
+}
+struct Signal {
+    fired: bool,
+    waker: Option<Waker>,
+}
+impl<State> Default for IsrNotify<State> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<State> IsrNotify<State> {
+    /// A notifier without a handler.
+    pub const fn new() -> Self {
+        Self::from_cell(StaticClosureCell::new())
+    }
+    /// A notifier running `handler` on every interrupt.
+    pub const fn with(handler: ClosureRefMut<State, (), bool>) -> Self {
+        Self::from_cell(StaticClosureCell::with(handler))
+    }
+    const fn from_cell(handler: StaticClosureCell<ClosureRefMut<State, (), bool>>) -> Self {
+        Self { handler, signal: Mutex::new(RefCell::new(Signal { fired: false, waker: None })) }
+    }
+    /// The cell holding the handler, to set or clear it.
+    pub fn handler(&self) -> &StaticClosureCell<ClosureRefMut<State, (), bool>> {
+        &self.handler
+    }
+    /// Runs the handler and signals the event if it returns `true`. Returns
+    /// whether it did; without a handler nothing is signalled.
+    pub fn on_interrupt(&self) -> bool {
+        let fired = self.handler.call_mut_if_set(()).unwrap_or(false);
+        if fired {
+            self.signal();
+        }
+        fired
+    }
+    /// Signals the event without running the handler.
+    pub fn signal(&self) {
+        let waker = critical_section::with(|cs| {
+            let mut signal = self.signal.borrow_ref_mut(cs);
+            signal.fired = true;
+            signal.waker.take()
+        });
+        // woken outside the critical section, which the executor may need
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+    /// A future resolving once the event has been signalled.
+    pub fn wait(&self) -> IsrWait<'_, State> {
+        IsrWait { notify: self }
+    }
+    /// Runs `f` on the handler's state inside a critical section, if a
+    /// handler is set.
+    pub fn with_state<R, F>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut State) -> R
+    {
+        critical_section::with(|cs| self.handler.c.borrow(cs).borrow_mut().as_mut().map(|c| f(c.state_mut())))
+    }
+}
+impl<State> fmt::Debug for IsrNotify<State> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fired = critical_section::with(|cs| self.signal.borrow_ref(cs).fired);
+        f.debug_struct("IsrNotify").field("handler", &self.handler).field("fired", &fired).finish()
+    }
+}
+
+/// The future returned by `IsrNotify::wait`.
+///
+/// Only the last task to poll one of these futures is woken.
+pub struct IsrWait<'a, State> {
+    notify: &'a IsrNotify<State>,
+}
+impl<'a, State> Future for IsrWait<'a, State> {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        critical_section::with(|cs| {
+            let mut signal = self.notify.signal.borrow_ref_mut(cs);
+            if signal.fired {
+                signal.fired = false;
+                return Poll::Ready(());
+            }
+            match signal.waker {
+                Some(ref waker) if waker.will_wake(cx.waker()) => (),
+                _ => signal.waker = Some(cx.waker().clone()),
+            }
+            Poll::Pending
+        })
+    }
+}
+impl<'a, State> fmt::Debug for IsrWait<'a, State> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IsrWait").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureOnce,ClosureRef,StaticClosureCell};
+
+    static HANDLER: StaticClosureCell<ClosureRef<u8,(u8,),u8>> = StaticClosureCell::new();
+
+    #[test]
+    fn test_swap_handler() {
+        assert_eq!(format!("{:?}", HANDLER), "StaticClosureCell(unset)");
+        assert!(HANDLER.set(closure!(ref k=2 => move |i| i**k)).is_none());
+        assert_eq!(HANDLER.call_if_set((3,)), Some(6));
+        let old = HANDLER.set(closure!(ref k=3 => move |i| i**k));
+        assert_eq!(old.map(|c| c.call_with_state(&1, (3,))), Some(3));
+        assert_eq!(HANDLER.call_if_set((3,)), Some(9));
+        assert!(HANDLER.clear().is_some());
+        assert_eq!(HANDLER.call_if_set((3,)), None);
+    }
+    #[test]
+    fn test_call_once() {
+        let cell:StaticClosureCell<ClosureOnce<String,(),String>> = StaticClosureCell::with(closure!(s=String::from("done") => move || s));
+        assert_eq!(cell.call_once_if_set(()), Some("done".to_string()));
+        assert!(!cell.is_set());
+    }
+    #[cfg(feature="std")]
+    #[test]
+    fn test_isr_notify() {
+        let notify:crate::IsrNotify<u8> = crate::IsrNotify::new();
+        assert!(!notify.on_interrupt());
+        notify.handler().set(closure!(ref mut n=0 => move || {*n+=1; *n == 2}));
+        assert!(!notify.on_interrupt());
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                // let the waiter register its waker first, most of the time
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                assert!(notify.on_interrupt());
+            });
+            crate::block_on(notify.wait());
+        });
+        assert_eq!(notify.with_state(|n| *n), Some(2));
+        // a signal before the wait is not lost
+        notify.signal();
+        crate::block_on(notify.wait());
+        assert_eq!(format!("{:?}", notify), "IsrNotify { handler: StaticClosureCell(set), fired: false }");
+    }
+}
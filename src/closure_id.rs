@@ -0,0 +1,75 @@
+//! Identity of namable closures.
+//!
+//! Two closures have the same `ClosureId` when they share a function and a
+//! state type, whatever the state's value. Registries can use it as a key to
+//! dedupe callbacks or to refer to one across crate boundaries without
+//! comparing closure types.
+//!
+//! The id is deterministic only within one build of a program. Function
+//! addresses and `TypeId`s are chosen by the compiler and the linker, and a
+//! function pointer carries no path the crate could read instead, so there is
+//! nothing stable across builds to derive it from. A name that survives
+//! rebuilds, for persisting or for a config file, is the one given to
+//! `static_closure!` and looked up through the `registry` feature.
+
+use core::any::TypeId;
+use core::fmt;
+
+/// The function pointer and state type of a closure, returned by `id()`.
+///
+/// Within a build, the same function and state type always give equal ids.
+/// Across builds they may not: the compiler may merge identical functions or
+/// duplicate one across codegen units, and `TypeId` values are unspecified,
+/// so ids must not be persisted or compared between runs.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClosureId {
+    f: usize,
+    state: TypeId,
+}
+impl ClosureId {
+    pub(crate) fn new<State: ?Sized + 'static>(f: usize) -> Self {
+        Self { f, state: TypeId::of::<State>() }
+    }
+    /// The `TypeId` of the closure's state.
+    pub fn state_type(&self) -> TypeId {
+        self.state
+    }
+}
+impl fmt::Debug for ClosureId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ClosureId")
+            .field("f", &(self.f as *const ()))
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::{ClosureMut,ClosureRec,ClosureRef,StableFn};
+
+    fn double(s:&i32, (i,):(i32,)) -> i32 { i**s*2 }
+
+    #[test]
+    fn test_same_function_same_id() {
+        let a:ClosureRef<i32,(i32,),i32> = ClosureRef::new(double, 1);
+        let b:ClosureRef<i32,(i32,),i32> = ClosureRef::new(double, 2);
+        let c:ClosureRef<i32,(i32,),i32> = closure!(ref s=1 => move |i| i+*s);
+        let u:ClosureRef<u32,(i32,),i32> = closure!(ref s=1 => move |i| i+*s as i32);
+        assert_eq!(a.id(), b.id());
+        assert_ne!(a.id(), c.id());
+        assert_ne!(c.id(), u.id());
+        let ids:HashSet<_> = [a, b, c].iter().map(|c| c.id()).collect();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(b.stable_call((1,)), 4);
+    }
+    #[test]
+    fn test_borrowing_and_recursive_ids() {
+        let mut n = 0;
+        let m:ClosureMut<i32,(),i32> = closure!(mut n=&mut n => || {*n+=1;*n});
+        assert_eq!(m.id().state_type(), std::any::TypeId::of::<i32>());
+        let fac:ClosureRec<(),(u32,),u32> = ClosureRec::new(|me, (i,)| if i == 0 { 1 } else { i * me.stable_call((i-1,)) }, ());
+        assert_eq!(fac.id(), fac.clone().id());
+    }
+}
@@ -0,0 +1,290 @@
+//! Keyed storage for homogeneous namable closures.
+//!
+//! `ClosureMap` is a dispatch table: a `HashMap` from keys (command names,
+//! message ids) to closures of one namable type. Lookups that must succeed can
+//! use indexing, `table[&cmd].stable_call(args)`, and lookups with a fallback
+//! can use `dispatch_or`.
+
+use std::borrow::Borrow;
+use std::collections::hash_map::{self,HashMap};
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Index;
+
+use crate::collection::ClosureCollection;
+use crate::error::Error;
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// A map from keys to closures of a single type.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureMap,ClosureRef,StableFn};
+/// let mut table:ClosureMap<&str,ClosureRef<i32,(i32,),i32>> = ClosureMap::new();
+/// table.insert("add", closure!(ref n=10 => move |i| i+*n));
+/// table.insert("mul", closure!(ref n=10 => move |i| i**n));
+/// assert_eq!(table[&"mul"].stable_call((2,)), 20);
+/// let unknown:ClosureRef<(),(i32,),i32> = closure!(ref _s=() => move |i| -i);
+/// assert_eq!(table.dispatch_or(&"sub", unknown, (2,)), -2);
+/// ```
+pub struct ClosureMap<K, C> {
+    map: HashMap<K, C>,
+}
+impl<K, C> Default for ClosureMap<K, C>
+where
+    K: Eq + Hash
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<K, C> ClosureMap<K, C>
+where
+    K: Eq + Hash
+{
+    pub fn new() -> Self {
+        Self { map: HashMap::new() }
+    }
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { map: HashMap::with_capacity(capacity) }
+    }
+    /// The number of closures currently stored.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    /// Stores `c` under `key`, returning the closure it replaces.
+    pub fn insert(&mut self, key: K, c: C) -> Option<C> {
+        self.map.insert(key, c)
+    }
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<C>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized
+    {
+        self.map.remove(key)
+    }
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized
+    {
+        self.map.contains_key(key)
+    }
+    pub fn get<Q>(&self, key: &Q) -> Option<&C>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized
+    {
+        self.map.get(key)
+    }
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut C>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized
+    {
+        self.map.get_mut(key)
+    }
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+    /// Visits the entries in arbitrary order.
+    pub fn iter(&self) -> hash_map::Iter<'_, K, C> {
+        self.map.iter()
+    }
+    pub fn iter_mut(&mut self) -> hash_map::IterMut<'_, K, C> {
+        self.map.iter_mut()
+    }
+    pub fn keys(&self) -> hash_map::Keys<'_, K, C> {
+        self.map.keys()
+    }
+    /// Keeps only the entries for which `keep` returns `true`.
+    pub fn retain<P>(&mut self, keep: P)
+    where
+        P: FnMut(&K, &mut C) -> bool
+    {
+        self.map.retain(keep)
+    }
+    /// Calls the closure for `key` by reference, if it is present.
+    pub fn call<Q, Input>(&self, key: &Q, args: Input) -> Option<C::Output>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+        C: StableFn<Input>
+    {
+        self.get(key).map(|c| c.stable_call(args))
+    }
+    /// Calls the closure for `key` mutably, if it is present.
+    pub fn call_mut<Q, Input>(&mut self, key: &Q, args: Input) -> Option<C::Output>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+        C: StableFnMut<Input>
+    {
+        self.get_mut(key).map(|c| c.stable_call_mut(args))
+    }
+    /// Removes the closure for `key` and consumes it with `args`.
+    pub fn call_once<Q, Input>(&mut self, key: &Q, args: Input) -> Option<C::Output>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+        C: StableFnOnce<Input>
+    {
+        self.remove(key).map(|c| c.stable_call_once(args))
+    }
+    /// Like `call`, but reports a missing key as `Error::MissingKey`.
+    pub fn try_call<Q, Input>(&self, key: &Q, args: Input) -> Result<C::Output, Error>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+        C: StableFn<Input>
+    {
+        self.call(key, args).ok_or(Error::MissingKey)
+    }
+    /// Like `call_mut`, but reports a missing key as `Error::MissingKey`.
+    pub fn try_call_mut<Q, Input>(&mut self, key: &Q, args: Input) -> Result<C::Output, Error>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+        C: StableFnMut<Input>
+    {
+        self.call_mut(key, args).ok_or(Error::MissingKey)
+    }
+    /// Calls the closure for `key` by reference, or `default` if there is none.
+    ///
+    /// `default` may be of any closure type with the same signature.
+    pub fn dispatch_or<Q, Input, D>(&self, key: &Q, default: D, args: Input) -> C::Output
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+        C: StableFn<Input>,
+        D: StableFnOnce<Input, Output=C::Output>
+    {
+        match self.get(key) {
+            Some(c) => c.stable_call(args),
+            None => default.stable_call_once(args),
+        }
+    }
+    /// Calls the closure for `key` mutably, or `default` if there is none.
+    pub fn dispatch_mut_or<Q, Input, D>(&mut self, key: &Q, default: D, args: Input) -> C::Output
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+        C: StableFnMut<Input>,
+        D: StableFnOnce<Input, Output=C::Output>
+    {
+        match self.get_mut(key) {
+            Some(c) => c.stable_call_mut(args),
+            None => default.stable_call_once(args),
+        }
+    }
+}
+impl<K, C> ClosureCollection for ClosureMap<K, C>
+where
+    K: Eq + Hash
+{
+    type Closure = C;
+    type Iter<'a> = hash_map::Iter<'a, K, C> where K: 'a, C: 'a;
+    type IterMut<'a> = hash_map::IterMut<'a, K, C> where K: 'a, C: 'a;
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> hash_map::Iter<'_, K, C> {
+        self.iter()
+    }
+    fn iter_mut(&mut self) -> hash_map::IterMut<'_, K, C> {
+        self.iter_mut()
+    }
+    fn retain<P>(&mut self, mut keep: P)
+    where
+        P: FnMut(&mut C) -> bool
+    {
+        self.retain(|_, c| keep(c))
+    }
+}
+impl<K, C> IntoIterator for ClosureMap<K, C> {
+    type Item = (K, C);
+    type IntoIter = hash_map::IntoIter<K, C>;
+    fn into_iter(self) -> hash_map::IntoIter<K, C> {
+        self.map.into_iter()
+    }
+}
+impl<'a, K, C> IntoIterator for &'a ClosureMap<K, C> {
+    type Item = (&'a K, &'a C);
+    type IntoIter = hash_map::Iter<'a, K, C>;
+    fn into_iter(self) -> hash_map::Iter<'a, K, C> {
+        self.map.iter()
+    }
+}
+impl<'a, K, C> IntoIterator for &'a mut ClosureMap<K, C> {
+    type Item = (&'a K, &'a mut C);
+    type IntoIter = hash_map::IterMut<'a, K, C>;
+    fn into_iter(self) -> hash_map::IterMut<'a, K, C> {
+        self.map.iter_mut()
+    }
+}
+impl<K, C, Q> Index<&Q> for ClosureMap<K, C>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized
+{
+    type Output = C;
+    /// Returns the closure for `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no closure for `key`; use `get` or `try_call` to
+    /// avoid that.
+    fn index(&self, key: &Q) -> &C {
+        self.get(key).expect("no closure for key in ClosureMap")
+    }
+}
+impl<K, C> fmt::Debug for ClosureMap<K, C>
+where
+    K: fmt::Debug,
+    C: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.map.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureMap,ClosureOnce,ClosureRef,ClosureRefMut};
+    use crate::{StableFn,StableFnMut};
+
+    #[test]
+    fn test_index_and_dispatch() {
+        let mut table:ClosureMap<String,ClosureRef<i32,(i32,),i32>> = ClosureMap::new();
+        table.insert("add".to_string(), closure!(ref n=1 => move |i| i+*n));
+        assert!(table.insert("add".to_string(), closure!(ref n=2 => move |i| i+*n)).is_some());
+        assert_eq!(table["add"].stable_call((1,)), 3);
+        assert_eq!(table.call("add", (2,)), Some(4));
+        assert_eq!(table.call("sub", (2,)), None);
+        let fallback:ClosureOnce<i32,(i32,),i32> = closure!(n=100 => move |i| i+n);
+        assert_eq!(table.dispatch_or("sub", fallback, (2,)), 102);
+        assert_eq!(table.dispatch_or("add", fallback, (2,)), 4);
+    }
+    #[test]
+    #[should_panic(expected = "no closure for key")]
+    fn test_index_missing_key() {
+        let table:ClosureMap<&str,ClosureRef<(),(),()>> = ClosureMap::new();
+        table[&"missing"].stable_call(());
+    }
+    #[test]
+    fn test_mutable_dispatch() {
+        let mut table:ClosureMap<u8,ClosureRefMut<i32,(i32,),i32>> = ClosureMap::new();
+        table.insert(1, closure!(ref mut n=0 => move |i| {*n+=i;*n}));
+        let fallback:ClosureRef<(),(i32,),i32> = closure!(ref _s=() => move |_| -1);
+        assert_eq!(table.dispatch_mut_or(&1, fallback, (5,)), 5);
+        assert_eq!(table.dispatch_mut_or(&2, fallback, (5,)), -1);
+        assert_eq!(table.get_mut(&1).map(|c| c.stable_call_mut((1,))), Some(6));
+        assert_eq!(table.call_once(&1, (1,)), Some(7));
+        assert!(table.is_empty());
+    }
+}
@@ -1,4 +1,11 @@
-use stable_fn::{StableFn,StableFnMut,StableFnOnce};
+#[cfg(feature="nightly")]
+use core::marker::Tuple;
+use core::mem;
+use core::ops::ControlFlow;
+
+use crate::closure_id::ClosureId;
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
 
 pub struct ClosureRec<State,Input,Output> {
     func: fn(&ClosureRec<State,Input,Output>, Input) -> Output,
@@ -17,12 +24,40 @@ where
     }
 }
 impl<State,Input,Output> ClosureRec<State,Input,Output> {
-    pub fn new(func: fn(&Self, Input) -> Output, s: State) -> Self {
-        Self { func: func, state: s}
+    pub const fn new(func: fn(&Self, Input) -> Output, s: State) -> Self {
+        Self { func, state: s}
     }
     pub fn call_with_state(&self, s:State, i:Input) -> Output {
         (self.func)(&Self::new(self.func, s), i)
     }
+    /// Splits the closure into its function and state, which `from_parts`
+    /// puts back together.
+    pub fn into_parts(self) -> (fn(&Self, Input) -> Output, State) {
+        (self.func, self.state)
+    }
+    /// Reassembles a closure split by `into_parts`; the same as `new`.
+    pub const fn from_parts(func: fn(&Self, Input) -> Output, s: State) -> Self {
+        Self { func, state: s}
+    }
+    /// Replaces the state, returning the old one.
+    pub fn replace_state(&mut self, state: State) -> State {
+        mem::replace(&mut self.state, state)
+    }
+    pub fn set_state(&mut self, state: State) {
+        self.state = state
+    }
+    /// Exchanges the states of two closures of the same type, keeping each
+    /// closure's function.
+    pub fn swap_state(&mut self, other: &mut Self) {
+        mem::swap(&mut self.state, &mut other.state)
+    }
+    /// Identifies the closure by its function and state type.
+    pub fn id(&self) -> ClosureId
+    where
+        State: 'static
+    {
+        ClosureId::new::<State>(self.func as usize)
+    }
 }
 
 pub struct ClosureMutRec<State,Input,Output> {
@@ -42,24 +77,107 @@ where
     }
 }
 impl<State,Input,Output> ClosureMutRec<State,Input,Output> {
-    pub fn new(func: fn(&mut Self, Input) -> Output, s: State) -> Self {
-        Self { func: func, state: s}
+    pub const fn new(func: fn(&mut Self, Input) -> Output, s: State) -> Self {
+        Self { func, state: s}
     }
     pub fn call_with_state(&self, s:State, i:Input) -> Output {
         (self.func)(&mut Self::new(self.func, s), i)
     }
+    /// Splits the closure into its function and state, which `from_parts`
+    /// puts back together.
+    pub fn into_parts(self) -> (fn(&mut Self, Input) -> Output, State) {
+        (self.func, self.state)
+    }
+    /// Reassembles a closure split by `into_parts`; the same as `new`.
+    pub const fn from_parts(func: fn(&mut Self, Input) -> Output, s: State) -> Self {
+        Self { func, state: s}
+    }
+    /// Replaces the state, returning the old one.
+    pub fn replace_state(&mut self, state: State) -> State {
+        mem::replace(&mut self.state, state)
+    }
+    pub fn set_state(&mut self, state: State) {
+        self.state = state
+    }
+    /// Exchanges the states of two closures of the same type, keeping each
+    /// closure's function.
+    pub fn swap_state(&mut self, other: &mut Self) {
+        mem::swap(&mut self.state, &mut other.state)
+    }
+    /// Identifies the closure by its function and state type.
+    pub fn id(&self) -> ClosureId
+    where
+        State: 'static
+    {
+        ClosureId::new::<State>(self.func as usize)
+    }
+}
+impl<State,Input,Output> ClosureMutRec<State,Input,ControlFlow<Output,Input>> {
+    /// Calls the closure in a loop instead of recursively: while it returns
+    /// `Continue(next)` it is called again with `next`, and the value it
+    /// breaks with is returned.
+    ///
+    /// The stack does not grow with the number of steps, and changes the
+    /// steps make to the state are kept.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use namable_closures::closure_rec;
+    /// # use namable_closures::{ClosureMutRec,ClosureState,ClosureStateMut};
+    /// # use std::ops::ControlFlow;
+    /// type Gcd = ClosureMutRec<u32,(u64,u64),ControlFlow<u64,(u64,u64)>>;
+    /// let mut gcd:Gcd = closure_rec!(mut me.state=0 => mut |a,b| {
+    ///     *me.state_mut() += 1;
+    ///     if b == 0 { ControlFlow::Break(a) } else { ControlFlow::Continue((b, a % b)) }
+    /// });
+    /// assert_eq!(gcd.iterate((48, 18)), 6);
+    /// assert_eq!(*gcd.state(), 4);
+    /// ```
+    pub fn iterate(&mut self, mut i: Input) -> Output {
+        loop {
+            match (self.func)(self, i) {
+                ControlFlow::Continue(next) => i = next,
+                ControlFlow::Break(o) => return o,
+            }
+        }
+    }
 }
 
+/// The function of a `ClosureRecMut`, which works for any borrow of the state.
+pub type RecMutFn<State,Input,Output> = for<'b> fn(&mut ClosureRecMut<'b, State,Input,Output>, Input) -> Output;
+
 pub struct ClosureRecMut<'a, State,Input,Output>
 where
     State: 'a
 {
-    func: fn(&mut ClosureRecMut<'a, State,Input,Output>, Input) -> Output,
+    func: RecMutFn<State,Input,Output>,
     state: &'a mut State
 }
 impl<'a, State,Input,Output> ClosureRecMut<'a, State,Input,Output> {
-    pub fn new(func: fn(&mut ClosureRecMut<'a, State,Input,Output>, Input) -> Output, s: &'a mut State) -> Self {
-        Self { func: func, state: s}
+    pub const fn new(func: RecMutFn<State,Input,Output>, s: &'a mut State) -> Self {
+        Self { func, state: s}
+    }
+    /// Calls the function on another state, such as a part of this one
+    /// reborrowed for a recursive call.
+    pub fn call_with_state(&self, s:&mut State, i:Input) -> Output {
+        (self.func)(&mut ClosureRecMut::new(self.func, s), i)
+    }
+    /// Splits the closure into its function and state, which `from_parts`
+    /// puts back together.
+    pub fn into_parts(self) -> (RecMutFn<State,Input,Output>, &'a mut State) {
+        (self.func, self.state)
+    }
+    /// Reassembles a closure split by `into_parts`; the same as `new`.
+    pub const fn from_parts(func: RecMutFn<State,Input,Output>, s: &'a mut State) -> Self {
+        Self { func, state: s}
+    }
+    /// Identifies the closure by its function and state type.
+    pub fn id(&self) -> ClosureId
+    where
+        State: 'static
+    {
+        ClosureId::new::<State>(self.func as usize)
     }
 }
 
@@ -80,26 +198,57 @@ where
     }
 }
 impl<State,Input,Output> ClosureOnceRec<State,Input,Output> {
-    pub fn new(func: fn(Self, Input) -> Output, s: State) -> Self {
-        Self { func: func, state: s}
+    pub const fn new(func: fn(Self, Input) -> Output, s: State) -> Self {
+        Self { func, state: s}
     }
     pub fn call_with_state(&self, s:State, i:Input) -> Output {
         (self.func)(Self::new(self.func, s), i)
     }
+    /// Splits the closure into its function and state, which `from_parts`
+    /// puts back together.
+    pub fn into_parts(self) -> (fn(Self, Input) -> Output, State) {
+        (self.func, self.state)
+    }
+    /// Reassembles a closure split by `into_parts`; the same as `new`.
+    pub const fn from_parts(func: fn(Self, Input) -> Output, s: State) -> Self {
+        Self { func, state: s}
+    }
+    /// Replaces the state, returning the old one.
+    pub fn replace_state(&mut self, state: State) -> State {
+        mem::replace(&mut self.state, state)
+    }
+    pub fn set_state(&mut self, state: State) {
+        self.state = state
+    }
+    /// Exchanges the states of two closures of the same type, keeping each
+    /// closure's function.
+    pub fn swap_state(&mut self, other: &mut Self) {
+        mem::swap(&mut self.state, &mut other.state)
+    }
+    /// Identifies the closure by its function and state type.
+    pub fn id(&self) -> ClosureId
+    where
+        State: 'static
+    {
+        ClosureId::new::<State>(self.func as usize)
+    }
 }
 
 impl<State,Input,Output> StableFnOnce<Input> for ClosureRec<State,Input,Output> {
     type Output=Output;
+    #[inline]
     fn stable_call_once(self, i:Input) -> Self::Output {
         (self.func)(&self, i)
     }
 }
 impl<State,Input,Output> StableFnMut<Input> for ClosureRec<State,Input,Output> {
+    #[inline]
     fn stable_call_mut(&mut self, i:Input) -> Output {
         (self.func)(self, i)
     }
 }
 impl<State,Input,Output> StableFn<Input> for ClosureRec<State,Input,Output> {
+    #[inline]
     fn stable_call(&self, i:Input) -> Output {
         (self.func)(self, i)
     }
@@ -108,11 +257,13 @@ impl<State,Input,Output> StableFn<Input> for ClosureRec<State,Input,Output> {
 
 impl<State,Input,Output> StableFnOnce<Input> for ClosureMutRec<State,Input,Output> {
     type Output=Output;
+    #[inline]
     fn stable_call_once(mut self, i:Input) -> Self::Output {
         (self.func)(&mut self, i)
     }
 }
 impl<State,Input,Output> StableFnMut<Input> for ClosureMutRec<State,Input,Output> {
+    #[inline]
     fn stable_call_mut(&mut self, i:Input) -> Output {
         (self.func)(self, i)
     }
@@ -121,6 +272,7 @@ impl<State,Input,Output> StableFn<Input> for ClosureMutRec<State,Input,Output>
 where
     State: Copy
 {
+    #[inline]
     fn stable_call(&self, i:Input) -> Output {
         let mut s = *self;
         (s.func)(&mut s, i)
@@ -129,11 +281,13 @@ where
 
 impl<'a,State,Input,Output> StableFnOnce<Input> for ClosureRecMut<'a,State,Input,Output> {
     type Output=Output;
+    #[inline]
     fn stable_call_once(mut self, i:Input) -> Self::Output {
         (self.func)(&mut self, i)
     }
 }
 impl<'a,State,Input,Output> StableFnMut<Input> for ClosureRecMut<'a,State,Input,Output> {
+    #[inline]
     fn stable_call_mut(&mut self, i:Input) -> Output {
         (self.func)(self, i)
     }
@@ -141,6 +295,7 @@ impl<'a,State,Input,Output> StableFnMut<Input> for ClosureRecMut<'a,State,Input,
 
 impl<State,Input,Output> StableFnOnce<Input> for ClosureOnceRec<State,Input,Output> {
     type Output=Output;
+    #[inline]
     fn stable_call_once(self, i:Input) -> Self::Output {
         (self.func)(self, i)
     }
@@ -149,6 +304,7 @@ impl<State,Input,Output> StableFnMut<Input> for ClosureOnceRec<State,Input,Outpu
 where
     State: Copy
 {
+    #[inline]
     fn stable_call_mut(&mut self, i:Input) -> Output {
         (self.func)(*self, i)
     }
@@ -157,40 +313,106 @@ impl<State,Input,Output> StableFn<Input> for ClosureOnceRec<State,Input,Output>
 where
     State: Copy
 {
+    #[inline]
     fn stable_call(&self, i:Input) -> Output {
         (self.func)(*self, i)
     }
 }
 
+impl<State,Input,Output> ClosureState for ClosureRec<State,Input,Output> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.state
+    }
+}
+impl<State,Input,Output> ClosureState for ClosureMutRec<State,Input,Output> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.state
+    }
+}
+impl<'a, State,Input,Output> ClosureState for ClosureRecMut<'a, State,Input,Output> {
+    type State = State;
+    fn state(&self) -> &State {
+        self.state
+    }
+}
+impl<State,Input,Output> ClosureState for ClosureOnceRec<State,Input,Output> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.state
+    }
+}
+impl<State,Input,Output> ClosureStateMut for ClosureRec<State,Input,Output> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+}
+impl<State,Input,Output> ClosureStateMut for ClosureMutRec<State,Input,Output> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+}
+impl<'a, State,Input,Output> ClosureStateMut for ClosureRecMut<'a, State,Input,Output> {
+    fn state_mut(&mut self) -> &mut State {
+        self.state
+    }
+}
+impl<State,Input,Output> ClosureStateMut for ClosureOnceRec<State,Input,Output> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+}
+
 #[cfg(feature="nightly")]
-impl<State,Input,Output> FnOnce<Input> for ClosureRec<State,Input,Output> {
+impl<State,Input,Output> FnOnce<Input> for ClosureRec<State,Input,Output>
+where
+    Input: Tuple
+{
     type Output=Output;
+    #[inline]
     extern "rust-call" fn call_once(self, i:Input) -> Self::Output {
         (self.func)(&self, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<State,Input,Output> FnMut<Input> for ClosureRec<State,Input,Output> {
+impl<State,Input,Output> FnMut<Input> for ClosureRec<State,Input,Output>
+where
+    Input: Tuple
+{
+    #[inline]
     extern "rust-call" fn call_mut(&mut self, i:Input) -> Output {
         (self.func)(self, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<State,Input,Output> Fn<Input> for ClosureRec<State,Input,Output> {
+impl<State,Input,Output> Fn<Input> for ClosureRec<State,Input,Output>
+where
+    Input: Tuple
+{
+    #[inline]
     extern "rust-call" fn call(&self, i:Input) -> Output {
         (self.func)(self, i)
     }
 }
 
 #[cfg(feature="nightly")]
-impl<State,Input,Output> FnOnce<Input> for ClosureMutRec<State,Input,Output> {
+impl<State,Input,Output> FnOnce<Input> for ClosureMutRec<State,Input,Output>
+where
+    Input: Tuple
+{
     type Output=Output;
+    #[inline]
     extern "rust-call" fn call_once(mut self, i:Input) -> Self::Output {
         (self.func)(&mut self, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<State,Input,Output> FnMut<Input> for ClosureMutRec<State,Input,Output> {
+impl<State,Input,Output> FnMut<Input> for ClosureMutRec<State,Input,Output>
+where
+    Input: Tuple
+{
+    #[inline]
     extern "rust-call" fn call_mut(&mut self, i:Input) -> Output {
         (self.func)(self, i)
     }
@@ -198,8 +420,10 @@ impl<State,Input,Output> FnMut<Input> for ClosureMutRec<State,Input,Output> {
 #[cfg(feature="nightly")]
 impl<State,Input,Output> Fn<Input> for ClosureMutRec<State,Input,Output>
 where
+    Input: Tuple,
     State: Copy
 {
+    #[inline]
     extern "rust-call" fn call(&self, i:Input) -> Output {
         let mut s = *self;
         (s.func)(&mut s, i)
@@ -207,22 +431,34 @@ where
 }
 
 #[cfg(feature="nightly")]
-impl<'a,State,Input,Output> FnOnce<Input> for ClosureRecMut<'a,State,Input,Output> {
+impl<'a,State,Input,Output> FnOnce<Input> for ClosureRecMut<'a,State,Input,Output>
+where
+    Input: Tuple
+{
     type Output=Output;
+    #[inline]
     extern "rust-call" fn call_once(mut self, i:Input) -> Self::Output {
         (self.func)(&mut self, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<'a,State,Input,Output> FnMut<Input> for ClosureRecMut<'a,State,Input,Output> {
+impl<'a,State,Input,Output> FnMut<Input> for ClosureRecMut<'a,State,Input,Output>
+where
+    Input: Tuple
+{
+    #[inline]
     extern "rust-call" fn call_mut(&mut self, i:Input) -> Output {
         (self.func)(self, i)
     }
 }
 
 #[cfg(feature="nightly")]
-impl<State,Input,Output> FnOnce<Input> for ClosureOnceRec<State,Input,Output> {
+impl<State,Input,Output> FnOnce<Input> for ClosureOnceRec<State,Input,Output>
+where
+    Input: Tuple
+{
     type Output=Output;
+    #[inline]
     extern "rust-call" fn call_once(self, i:Input) -> Self::Output {
         (self.func)(self, i)
     }
@@ -230,8 +466,10 @@ impl<State,Input,Output> FnOnce<Input> for ClosureOnceRec<State,Input,Output> {
 #[cfg(feature="nightly")]
 impl<State,Input,Output> FnMut<Input> for ClosureOnceRec<State,Input,Output>
 where
+    Input: Tuple,
     State: Copy
 {
+    #[inline]
     extern "rust-call" fn call_mut(&mut self, i:Input) -> Output {
         (self.func)(*self, i)
     }
@@ -239,8 +477,10 @@ where
 #[cfg(feature="nightly")]
 impl<State,Input,Output> Fn<Input> for ClosureOnceRec<State,Input,Output>
 where
+    Input: Tuple,
     State: Copy
 {
+    #[inline]
     extern "rust-call" fn call(&self, i:Input) -> Output {
         (self.func)(*self, i)
     }
@@ -248,8 +488,8 @@ where
 
 #[cfg(test)]
 mod test {
-    use closure_rec::{ClosureRec,ClosureMutRec};
-    use stable_fn::{StableFn,StableFnMut};
+    use core::ops::ControlFlow;
+    use crate::{ClosureMutRec,ClosureRec,ClosureRecMut,ClosureState,ClosureStateMut,StableFn,StableFnMut};
     #[test]
     fn test_fac() {
         let fac:ClosureRec<(),(i32,),i32> = 
@@ -266,10 +506,57 @@ mod test {
                 match i {
                     0 => i0,
                     1 => i1,
-                    n => me.call_with_state((i1,i0+i1), (i-1,))
+                    _ => me.call_with_state((i1,i0+i1), (i-1,))
                 }                
             });        
         assert_eq!(fib.stable_call((10,)),89);
         assert_eq!(fib.state, (1,1));
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_iterate_keeps_state_without_recursing() {
+        let mut count:ClosureMutRec<Vec<u32>,(u32,),ControlFlow<usize,(u32,)>> = closure_rec!(mut me.state=vec![] => mut |n| {
+            if n % 100_000 == 0 { me.state_mut().push(n); }
+            if n == 0 { ControlFlow::Break(me.state().len()) } else { ControlFlow::Continue((n-1,)) }
+        });
+        assert_eq!(count.iterate((1_000_000,)), 11);
+        assert_eq!(count.state()[..2], [1_000_000, 900_000]);
+        assert_eq!(count.iterate((0,)), 12);
+    }
+    #[test]
+    fn test_parts_round_trip() {
+        let fac:ClosureRec<(),(u64,),u64> = ClosureRec::new(|me, (n,)| if n == 0 { 1 } else { n * me.stable_call((n-1,)) }, ());
+        let (f, s) = fac.into_parts();
+        assert_eq!(ClosureRec::from_parts(f, s).stable_call((5,)), 120);
+        let mut total = 0;
+        let add:ClosureRecMut<i32,(i32,),()> = ClosureRecMut::new(|me, (i,)| *me.state_mut() += i, &mut total);
+        let (f, s) = add.into_parts();
+        ClosureRecMut::from_parts(f, s).stable_call_mut((3,));
+        assert_eq!(total, 3);
+    }
+    #[test]
+    fn test_replace_state() {
+        let mut countdown:ClosureMutRec<u32,(),u32> = ClosureMutRec::new(|me, ()| {
+            let n = *me.state();
+            if n == 0 { 0 } else { me.set_state(n - 1); 1 + me.stable_call_mut(()) }
+        }, 3);
+        assert_eq!(countdown.stable_call_mut(()), 3);
+        assert_eq!(countdown.replace_state(2), 0);
+        assert_eq!(countdown.stable_call_mut(()), 2);
+    }
+    #[test]
+    fn test_rec_mut_call_with_state() {
+        struct Dir { files: u32, subdirs: Vec<Dir> }
+        // counts files into each directory, including its subdirectories
+        let mut root = Dir { files: 1, subdirs: vec![Dir { files: 2, subdirs: vec![] }, Dir { files: 3, subdirs: vec![Dir { files: 4, subdirs: vec![] }] }] };
+        let mut total:ClosureRecMut<Dir,(),u32> = closure_rec!(me.state=&mut root => mut || {
+            let mut subdirs = core::mem::take(&mut me.state_mut().subdirs);
+            let nested:u32 = subdirs.iter_mut().map(|d| me.call_with_state(d, ())).sum();
+            let dir = me.state_mut();
+            dir.subdirs = subdirs;
+            dir.files += nested;
+            dir.files
+        });
+        assert_eq!(total.stable_call_mut(()), 10);
+        assert_eq!((root.subdirs[0].files, root.subdirs[1].files), (2, 7));
+    }
+}
@@ -0,0 +1,418 @@
+//! A generational slot map for storing homogeneous namable closures.
+//!
+//! Because every closure of the same signature and state type has the same
+//! namable type, they can be kept in a plain `Vec` without boxing. `ClosureSlab`
+//! adds stable handles on top of that: removing a closure frees its slot for
+//! reuse, and the slot's generation is bumped so that any outstanding
+//! `SlabKey` for the removed closure stops matching (no ABA problem).
+
+use alloc::vec::{self,Vec};
+use core::fmt;
+use core::iter::Enumerate;
+use core::ops::{Index,IndexMut};
+use core::slice;
+
+use crate::collection::ClosureCollection;
+use crate::error::Error;
+use crate::outcome::CallOutcome;
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// A stable handle to a closure stored in a `ClosureSlab`.
+///
+/// A key is only valid for the slab that issued it, and only until the
+/// closure it refers to is removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SlabKey {
+    index: usize,
+    generation: u32,
+}
+impl SlabKey {
+    /// The slot index this key refers to.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// The generation of the slot at the time the key was issued.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+struct Slot<C> {
+    generation: u32,
+    value: Option<C>,
+}
+
+/// Storage for closures of a single type, addressed by `SlabKey`.
+///
+/// Insertion and removal are `O(1)`. Iteration visits the occupied slots in
+/// index order.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,ClosureSlab,StableFn};
+/// let mut slab:ClosureSlab<ClosureRef<i32,(i32,),i32>> = ClosureSlab::new();
+/// let add_one = slab.insert(closure!(ref s=1 => move |i| i+*s));
+/// let add_ten = slab.insert(closure!(ref s=10 => move |i| i+*s));
+/// assert_eq!(slab.call(add_ten, (1,)), Some(11));
+/// slab.remove(add_one);
+/// assert_eq!(slab.call(add_one, (1,)), None);
+/// assert_eq!(slab.len(), 1);
+/// ```
+pub struct ClosureSlab<C> {
+    slots: Vec<Slot<C>>,
+    free: Vec<usize>,
+    len: usize,
+}
+impl<C> Default for ClosureSlab<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<C> ClosureSlab<C> {
+    pub fn new() -> Self {
+        Self { slots: Vec::new(), free: Vec::new(), len: 0 }
+    }
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { slots: Vec::with_capacity(capacity), free: Vec::new(), len: 0 }
+    }
+    /// The number of closures currently stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// The number of slots, occupied or not, that have been allocated.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+    /// Stores a closure and returns the key to reach it again.
+    ///
+    /// Slots freed by `remove` are reused before new slots are allocated.
+    pub fn insert(&mut self, c: C) -> SlabKey {
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.value = Some(c);
+                SlabKey { index, generation: slot.generation }
+            }
+            None => {
+                self.slots.push(Slot { generation: 0, value: Some(c) });
+                SlabKey { index: self.slots.len() - 1, generation: 0 }
+            }
+        }
+    }
+    /// Removes the closure for `key`, if it is still present.
+    ///
+    /// The slot generation is bumped so `key` (and any copy of it) will
+    /// never match again, even after the slot is reused.
+    pub fn remove(&mut self, key: SlabKey) -> Option<C> {
+        let slot = match self.slots.get_mut(key.index) {
+            Some(slot) if slot.generation == key.generation => slot,
+            _ => return None,
+        };
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(key.index);
+        self.len -= 1;
+        Some(value)
+    }
+    pub fn contains(&self, key: SlabKey) -> bool {
+        self.get(key).is_some()
+    }
+    pub fn get(&self, key: SlabKey) -> Option<&C> {
+        match self.slots.get(key.index) {
+            Some(slot) if slot.generation == key.generation => slot.value.as_ref(),
+            _ => None,
+        }
+    }
+    pub fn get_mut(&mut self, key: SlabKey) -> Option<&mut C> {
+        match self.slots.get_mut(key.index) {
+            Some(slot) if slot.generation == key.generation => slot.value.as_mut(),
+            _ => None,
+        }
+    }
+    /// Removes every closure. Outstanding keys are invalidated.
+    pub fn clear(&mut self) {
+        self.free.clear();
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.value.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
+            }
+            self.free.push(index);
+        }
+        self.len = 0;
+    }
+    /// Keeps only the closures for which `keep` returns `true`.
+    pub fn retain<P>(&mut self, mut keep: P)
+    where
+        P: FnMut(SlabKey, &mut C) -> bool
+    {
+        for index in 0..self.slots.len() {
+            let slot = &mut self.slots[index];
+            let key = SlabKey { index, generation: slot.generation };
+            let kept = match slot.value {
+                Some(ref mut c) => keep(key, c),
+                None => true,
+            };
+            if !kept {
+                self.remove(key);
+            }
+        }
+    }
+    pub fn iter(&self) -> Iter<'_, C> {
+        Iter { inner: self.slots.iter().enumerate(), remaining: self.len }
+    }
+    pub fn iter_mut(&mut self) -> IterMut<'_, C> {
+        IterMut { inner: self.slots.iter_mut().enumerate(), remaining: self.len }
+    }
+    /// Calls the closure for `key` by reference, if it is present.
+    pub fn call<Input>(&self, key: SlabKey, args: Input) -> Option<C::Output>
+    where
+        C: StableFn<Input>
+    {
+        self.get(key).map(|c| c.stable_call(args))
+    }
+    /// Calls the closure for `key` mutably, if it is present.
+    pub fn call_mut<Input>(&mut self, key: SlabKey, args: Input) -> Option<C::Output>
+    where
+        C: StableFnMut<Input>
+    {
+        self.get_mut(key).map(|c| c.stable_call_mut(args))
+    }
+    /// Removes the closure for `key` and consumes it with `args`.
+    pub fn call_once<Input>(&mut self, key: SlabKey, args: Input) -> Option<C::Output>
+    where
+        C: StableFnOnce<Input>
+    {
+        self.remove(key).map(|c| c.stable_call_once(args))
+    }
+    /// Like `call`, but reports a stale key as `Error::StaleKey`.
+    pub fn try_call<Input>(&self, key: SlabKey, args: Input) -> Result<C::Output, Error>
+    where
+        C: StableFn<Input>
+    {
+        self.call(key, args).ok_or(Error::StaleKey)
+    }
+    /// Like `call_mut`, but reports a stale key as `Error::StaleKey`.
+    pub fn try_call_mut<Input>(&mut self, key: SlabKey, args: Input) -> Result<C::Output, Error>
+    where
+        C: StableFnMut<Input>
+    {
+        self.call_mut(key, args).ok_or(Error::StaleKey)
+    }
+    /// Calls the closure for each of `keys` mutably with a clone of `args`,
+    /// reporting a stale key as `Failed(Error::StaleKey)`.
+    pub fn call_each_mut<I, Input>(&mut self, keys: I, args: Input) -> Vec<CallOutcome<C::Output>>
+    where
+        I: IntoIterator<Item=SlabKey>,
+        C: StableFnMut<Input>,
+        Input: Clone
+    {
+        keys.into_iter().map(|key| self.try_call_mut(key, args.clone()).into()).collect()
+    }
+}
+impl<C> ClosureCollection for ClosureSlab<C> {
+    type Closure = C;
+    type Iter<'a> = Iter<'a, C> where C: 'a;
+    type IterMut<'a> = IterMut<'a, C> where C: 'a;
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> Iter<'_, C> {
+        self.iter()
+    }
+    fn iter_mut(&mut self) -> IterMut<'_, C> {
+        self.iter_mut()
+    }
+    fn retain<P>(&mut self, mut keep: P)
+    where
+        P: FnMut(&mut C) -> bool
+    {
+        self.retain(|_, c| keep(c))
+    }
+}
+impl<C> IntoIterator for ClosureSlab<C> {
+    type Item = (SlabKey, C);
+    type IntoIter = IntoIter<C>;
+    fn into_iter(self) -> IntoIter<C> {
+        IntoIter { inner: self.slots.into_iter().enumerate(), remaining: self.len }
+    }
+}
+impl<'a, C> IntoIterator for &'a ClosureSlab<C> {
+    type Item = (SlabKey, &'a C);
+    type IntoIter = Iter<'a, C>;
+    fn into_iter(self) -> Iter<'a, C> {
+        self.iter()
+    }
+}
+impl<'a, C> IntoIterator for &'a mut ClosureSlab<C> {
+    type Item = (SlabKey, &'a mut C);
+    type IntoIter = IterMut<'a, C>;
+    fn into_iter(self) -> IterMut<'a, C> {
+        self.iter_mut()
+    }
+}
+impl<C> Index<SlabKey> for ClosureSlab<C> {
+    type Output = C;
+    /// Returns the closure for `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is stale; use `get` or `try_call` to avoid that.
+    fn index(&self, key: SlabKey) -> &C {
+        self.get(key).expect("stale or invalid SlabKey")
+    }
+}
+impl<C> IndexMut<SlabKey> for ClosureSlab<C> {
+    fn index_mut(&mut self, key: SlabKey) -> &mut C {
+        self.get_mut(key).expect("stale or invalid SlabKey")
+    }
+}
+impl<C> fmt::Debug for ClosureSlab<C>
+where
+    C: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the occupied slots of a `ClosureSlab`.
+pub struct Iter<'a, C: 'a> {
+    inner: Enumerate<slice::Iter<'a, Slot<C>>>,
+    remaining: usize,
+}
+impl<'a, C> Iterator for Iter<'a, C> {
+    type Item = (SlabKey, &'a C);
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, slot) in &mut self.inner {
+            if let Some(ref c) = slot.value {
+                self.remaining -= 1;
+                return Some((SlabKey { index, generation: slot.generation }, c));
+            }
+        }
+        None
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+impl<'a, C> ExactSizeIterator for Iter<'a, C> {}
+
+/// Mutable iterator over the occupied slots of a `ClosureSlab`.
+pub struct IterMut<'a, C: 'a> {
+    inner: Enumerate<slice::IterMut<'a, Slot<C>>>,
+    remaining: usize,
+}
+impl<'a, C> Iterator for IterMut<'a, C> {
+    type Item = (SlabKey, &'a mut C);
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, slot) in &mut self.inner {
+            let generation = slot.generation;
+            if let Some(ref mut c) = slot.value {
+                self.remaining -= 1;
+                return Some((SlabKey { index, generation }, c));
+            }
+        }
+        None
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+impl<'a, C> ExactSizeIterator for IterMut<'a, C> {}
+
+/// Owning iterator over the occupied slots of a `ClosureSlab`.
+pub struct IntoIter<C> {
+    inner: Enumerate<vec::IntoIter<Slot<C>>>,
+    remaining: usize,
+}
+impl<C> Iterator for IntoIter<C> {
+    type Item = (SlabKey, C);
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, slot) in &mut self.inner {
+            if let Some(c) = slot.value {
+                self.remaining -= 1;
+                return Some((SlabKey { index, generation: slot.generation }, c));
+            }
+        }
+        None
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+impl<C> ExactSizeIterator for IntoIter<C> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CallOutcome,ClosureRef,ClosureRefMut,ClosureSlab,Error};
+    use crate::{StableFn,StableFnMut};
+
+    #[test]
+    fn test_insert_remove_reuse() {
+        let mut slab:ClosureSlab<ClosureRef<i32,(i32,),i32>> = ClosureSlab::new();
+        let a = slab.insert(closure!(ref s=1 => move |i| i+*s));
+        let b = slab.insert(closure!(ref s=2 => move |i| i+*s));
+        assert_eq!(slab.len(), 2);
+        assert_eq!(slab.call(a, (10,)), Some(11));
+        assert!(slab.remove(a).is_some());
+        assert!(slab.remove(a).is_none());
+        let c = slab.insert(closure!(ref s=3 => move |i| i+*s));
+        // the slot is reused but the stale key does not alias the new closure
+        assert_eq!(c.index(), a.index());
+        assert_ne!(c.generation(), a.generation());
+        assert_eq!(slab.call(a, (10,)), None);
+        assert_eq!(slab.try_call(a, (10,)), Err(Error::StaleKey));
+        assert_eq!(slab.call(c, (10,)), Some(13));
+        assert_eq!(slab[b].stable_call((10,)), 12);
+        assert_eq!(slab.capacity(), 2);
+    }
+    #[test]
+    fn test_call_each_mut() {
+        let mut slab:ClosureSlab<ClosureRefMut<i32,(i32,),i32>> = ClosureSlab::new();
+        let a = slab.insert(closure!(ref mut s=0 => move |i| {*s+=i;*s}));
+        let b = slab.insert(closure!(ref mut s=10 => move |i| {*s+=i;*s}));
+        slab.remove(a);
+        let outcomes = slab.call_each_mut([a, b, b], (1,));
+        assert_eq!(outcomes, vec![CallOutcome::Failed(Error::StaleKey), CallOutcome::Completed(11), CallOutcome::Completed(12)]);
+    }
+    #[test]
+    fn test_iter_and_retain() {
+        let mut slab:ClosureSlab<ClosureRefMut<i32,(i32,),i32>> = ClosureSlab::new();
+        let keys:Vec<_> = (0..5)
+            .map(|n| slab.insert(closure!(ref mut s=n => move |i| {*s+=i;*s})))
+            .collect();
+        slab.remove(keys[1]);
+        for (_, c) in slab.iter_mut() {
+            c.stable_call_mut((100,));
+        }
+        let seen:Vec<_> = slab.iter().map(|(k, _)| k).collect();
+        assert_eq!(seen, vec![keys[0], keys[2], keys[3], keys[4]]);
+        assert_eq!(slab.iter().len(), 4);
+        slab.retain(|_, c| c.stable_call_mut((0,)) % 2 == 0);
+        assert_eq!(slab.len(), 3);
+        assert!(slab.contains(keys[0]));
+        assert!(!slab.contains(keys[3]));
+        assert_eq!(slab.call_once(keys[4], (1,)), Some(105));
+        assert!(!slab.contains(keys[4]));
+        slab.clear();
+        assert!(slab.is_empty());
+        assert!(!slab.contains(keys[0]));
+    }
+    #[test]
+    fn test_into_iter() {
+        let mut slab:ClosureSlab<ClosureRef<i32,(),i32>> = ClosureSlab::new();
+        for n in 0..3 {
+            slab.insert(closure!(ref s=n => move || *s));
+        }
+        let keys:Vec<_> = (&slab).into_iter().map(|(k, _)| k).collect();
+        slab.remove(keys[1]);
+        let rest:Vec<_> = slab.into_iter().map(|(k, c)| (k, c.stable_call(()))).collect();
+        assert_eq!(rest, vec![(keys[0], 0), (keys[2], 2)]);
+    }
+}
@@ -17,7 +17,7 @@ Correspond to unnamable closures like:
 ```
 # Example:
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::Closure;
 # use namable_closures::StableFnMut;
 # use namable_closures::StableFn;
@@ -39,7 +39,7 @@ assert_eq!(p.y,22);
 # The same example that uses the unstable features:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::Closure;
 # struct Point{x:i32,y:i32}
 # impl Point{ fn new(x:i32,y:i32) -> Point {Point{x:x,y:y}} }
@@ -57,26 +57,122 @@ assert_eq!(p.y,22);
 ")]
 pub struct Closure<'a, State, Input, Output>
 where
-    State: 'a
+    State: 'a + ?Sized
 {
     f: fn(&State, Input) -> Output,
     t: &'a State,
 }
-impl<'a, State, Input, Output> Copy for Closure<'a, State, Input, Output> 
+impl<'a, State, Input, Output> Copy for Closure<'a, State, Input, Output>
+where
+    State: ?Sized
 {}
 impl<'a, State, Input, Output> Clone for Closure<'a, State, Input, Output>
+where
+    State: ?Sized
 {
     fn clone(&self) -> Self {
         *self
     }
 }
-impl<'a, State, Input, Output> Closure<'a, State, Input, Output> {
-    pub fn new(f: fn(&State, Input) -> Output, t: &'a State) -> Self {
+impl<'a, State, Input, Output> Closure<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    #[inline]
+    pub const fn new(f: fn(&State, Input) -> Output, t: &'a State) -> Self {
         Self { f, t }
     }
     pub fn call_with_state(&self, s:&State, i: Input) -> Output {        
         (self.f)(s, i)
     }
+    pub fn state(&self) -> &State {
+        self.t
+    }
+    /// Drops the function, returning the borrow of the state.
+    pub fn into_state(self) -> &'a State {
+        self.t
+    }
+    /// Clones the state into a `ClosureRef` with the same function, which no
+    /// longer borrows from `'a`.
+    ///
+    /// Not named `to_owned`, which `ToOwned` already gives every `Closure` and
+    /// which returns another `Closure`.
+    pub fn to_owned_ref(&self) -> ClosureRef<State, Input, Output>
+    where
+        State: Clone
+    {
+        ClosureRef::new(self.f, self.t.clone())
+    }
+    /// Splits the closure into its function and state, which `from_parts`
+    /// puts back together.
+    pub fn into_parts(self) -> (fn(&State, Input) -> Output, &'a State) {
+        (self.f, self.t)
+    }
+    /// Reassembles a closure split by `into_parts`; the same as `new`.
+    pub const fn from_parts(f: fn(&State, Input) -> Output, t: &'a State) -> Self {
+        Self { f, t }
+    }
+    /// Identifies the closure by its function and state type.
+    pub fn id(&self) -> ClosureId
+    where
+        State: 'static
+    {
+        ClosureId::new::<State>(self.f as usize)
+    }
+}
+/// A read-only view of another closure's state, as returned by
+/// `ClosureRefMut::as_readonly`.
+///
+/// It can be called through `StableFn` and its state read through
+/// `ClosureState`, but nothing handed a view can mutate the state. Unlike a
+/// `Closure`, it cannot be copied, so the view ends with whoever holds it.
+pub struct ClosureView<'a, State, Input, Output>(Closure<'a, State, Input, Output>)
+where
+    State: 'a + ?Sized;
+impl<'a, State, Input, Output> ClosureView<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    pub fn state(&self) -> &State {
+        self.0.t
+    }
+}
+impl<'a, State, Input, Output> StableFnOnce<Input> for ClosureView<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    type Output = Output;
+    #[inline]
+    fn stable_call_once(self, i: Input) -> Output {
+        self.0.stable_call_once(i)
+    }
+}
+impl<'a, State, Input, Output> StableFnMut<Input> for ClosureView<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    #[inline]
+    fn stable_call_mut(&mut self, i: Input) -> Output {
+        self.0.stable_call(i)
+    }
+}
+impl<'a, State, Input, Output> StableFn<Input> for ClosureView<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    #[inline]
+    fn stable_call(&self, i: Input) -> Output {
+        self.0.stable_call(i)
+    }
+}
+impl<'a, State, Input, Output> ClosureState for ClosureView<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    type State = State;
+    fn state(&self) -> &State {
+        self.0.t
+    }
 }
 #[doc="
 A Closure does not own its state, and only refers to the state when called.
@@ -88,7 +184,7 @@ Correspond to unnamable closures like:
 Examples:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureRef;
 # use namable_closures::StableFnMut;
 # use namable_closures::StableFn;
@@ -109,7 +205,7 @@ assert_eq!(p.y,22);
 # The same example that uses the unstable features:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureRef;
 # struct Point{x:i32,y:i32}
 # impl Point{ fn new(x:i32,y:i32) -> Point {Point{x:x,y:y}} }
@@ -142,12 +238,70 @@ where
     }
 }
 impl<State, Input, Output> ClosureRef<State, Input, Output> {
-    pub fn new(f: fn(&State, Input) -> Output, t: State) -> Self {
+    #[inline]
+    pub const fn new(f: fn(&State, Input) -> Output, t: State) -> Self {
         Self { f, t }
     }
     pub fn call_with_state(&self, s:&State, i: Input) -> Output {        
         (self.f)(s, i)
     }
+    /// Keeps the state and replaces the function with one that consumes it.
+    pub fn into_once_with<I, O>(self, f: fn(State, I) -> O) -> ClosureOnce<State, I, O> {
+        ClosureOnce::new(f, self.t)
+    }
+    /// Keeps the state and replaces the function with one that mutates it.
+    pub fn into_ref_mut_with<I, O>(self, f: fn(&mut State, I) -> O) -> ClosureRefMut<State, I, O> {
+        ClosureRefMut::new(f, self.t)
+    }
+    /// Keeps the function and calls it on the part of `state` that `proj`
+    /// borrows. The closure's own state is not used by the new closure, so it
+    /// is returned alongside.
+    pub fn map_state<NewState>(self, proj: fn(&NewState) -> &State, state: NewState) -> (MapStateRef<NewState, State, Input, Output>, State) {
+        (MapStateRef::new(self.f, proj, state), self.t)
+    }
+    /// Lends the closure as a `Closure` borrowing its state, for code that
+    /// takes the borrowed variant.
+    pub fn as_borrowed(&self) -> Closure<'_, State, Input, Output> {
+        Closure::new(self.f, &self.t)
+    }
+    pub fn state(&self) -> &State {
+        &self.t
+    }
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+    /// Drops the function, returning the state.
+    pub fn into_state(self) -> State {
+        self.t
+    }
+    /// Replaces the state, returning the old one.
+    pub fn replace_state(&mut self, state: State) -> State {
+        mem::replace(&mut self.t, state)
+    }
+    pub fn set_state(&mut self, state: State) {
+        self.t = state
+    }
+    /// Exchanges the states of two closures of the same type, keeping each
+    /// closure's function.
+    pub fn swap_state(&mut self, other: &mut Self) {
+        mem::swap(&mut self.t, &mut other.t)
+    }
+    /// Splits the closure into its function and state, which `from_parts`
+    /// puts back together.
+    pub fn into_parts(self) -> (fn(&State, Input) -> Output, State) {
+        (self.f, self.t)
+    }
+    /// Reassembles a closure split by `into_parts`; the same as `new`.
+    pub const fn from_parts(f: fn(&State, Input) -> Output, t: State) -> Self {
+        Self { f, t }
+    }
+    /// Identifies the closure by its function and state type.
+    pub fn id(&self) -> ClosureId
+    where
+        State: 'static
+    {
+        ClosureId::new::<State>(self.f as usize)
+    }
 }
 
 #[doc="
@@ -163,7 +317,7 @@ Correspond to unnamable closures like:
 # Example:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureMut;
 # use namable_closures::StableFnMut;
 let mut state = 0;
@@ -179,7 +333,7 @@ assert_eq!(state,2);
 # The same example that uses the unstable features:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureMut;
 
 let mut state = 0;
@@ -193,18 +347,48 @@ assert_eq!(state,2);
 ")]
 pub struct ClosureMut<'a, State, Input, Output>
 where
-    State: 'a
+    State: 'a + ?Sized
 {
     f: fn(&mut State, Input) -> Output,
     t: &'a mut State,
 }
-impl<'a, State, Input, Output> ClosureMut<'a, State, Input, Output> {
-    pub fn new(f: fn(&mut State, Input) -> Output, t: &'a mut State) -> Self {
+impl<'a, State, Input, Output> ClosureMut<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    #[inline]
+    pub const fn new(f: fn(&mut State, Input) -> Output, t: &'a mut State) -> Self {
         Self { f, t }
     }
     pub fn call_with_state(&self, s:&mut State, i: Input) -> Output {        
         (self.f)(s, i)
     }
+    pub fn state(&self) -> &State {
+        self.t
+    }
+    pub fn state_mut(&mut self) -> &mut State {
+        self.t
+    }
+    /// Drops the function, returning the borrow of the state.
+    pub fn into_state(self) -> &'a mut State {
+        self.t
+    }
+    /// Splits the closure into its function and state, which `from_parts`
+    /// puts back together.
+    pub fn into_parts(self) -> (fn(&mut State, Input) -> Output, &'a mut State) {
+        (self.f, self.t)
+    }
+    /// Reassembles a closure split by `into_parts`; the same as `new`.
+    pub const fn from_parts(f: fn(&mut State, Input) -> Output, t: &'a mut State) -> Self {
+        Self { f, t }
+    }
+    /// Identifies the closure by its function and state type.
+    pub fn id(&self) -> ClosureId
+    where
+        State: 'static
+    {
+        ClosureId::new::<State>(self.f as usize)
+    }
 }
 
 #[doc="
@@ -218,7 +402,7 @@ Correspond to unnamable closures like:
 # Example:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureRefMut;
 # use namable_closures::StableFnMut;
 # use namable_closures::StableFnOnce;
@@ -232,7 +416,7 @@ assert_eq!(accumulate.stable_call_once((2,)),3);
 # The same example that uses the unstable features:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureRefMut;
 let mut accumulate:ClosureRefMut<i32,(i32,),i32>
     = closure!(ref mut state=0 => move |c| {*state+=c;*state});
@@ -257,12 +441,88 @@ where
     }
 }
 impl<State, Input, Output> ClosureRefMut<State, Input, Output> {
-    pub fn new(f: fn(&mut State, Input) -> Output, t: State) -> Self {
+    #[inline]
+    pub const fn new(f: fn(&mut State, Input) -> Output, t: State) -> Self {
         Self { f, t }
     }
     pub fn call_with_state(&mut self, t: &mut State, i:Input) -> Output {
         (self.f)(t, i)
     }
+    /// Keeps the state and replaces the function with one that consumes it.
+    pub fn into_once_with<I, O>(self, f: fn(State, I) -> O) -> ClosureOnce<State, I, O> {
+        ClosureOnce::new(f, self.t)
+    }
+    /// Keeps the state and replaces the function with one that only reads it.
+    pub fn into_ref_with<I, O>(self, f: fn(&State, I) -> O) -> ClosureRef<State, I, O> {
+        ClosureRef::new(f, self.t)
+    }
+    /// Keeps the function and calls it on the part of `state` that `proj`
+    /// borrows. The closure's own state is not used by the new closure, so it
+    /// is returned alongside.
+    pub fn map_state<NewState>(self, proj: fn(&mut NewState) -> &mut State, state: NewState) -> (MapStateRefMut<NewState, State, Input, Output>, State) {
+        (MapStateRefMut::new(self.f, proj, state), self.t)
+    }
+    /// Borrows the state behind a function that only reads it, so that the
+    /// view can be handed to code that must not mutate the state.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use namable_closures::closure;
+    /// # use namable_closures::{ClosureRefMut,ClosureView,StableFn,StableFnMut};
+    /// fn report(total:ClosureView<i32,(),String>) -> String {
+    ///     total.stable_call(())
+    /// }
+    /// let mut add:ClosureRefMut<i32,(i32,),i32> = closure!(ref mut n=0 => move |i| {*n+=i;*n});
+    /// add.stable_call_mut((5,));
+    /// assert_eq!(report(add.as_readonly(|n, ()| format!("total: {}", n))), "total: 5");
+    /// ```
+    pub fn as_readonly<I, O>(&self, f: fn(&State, I) -> O) -> ClosureView<'_, State, I, O> {
+        ClosureView(Closure::new(f, &self.t))
+    }
+    /// Lends the closure as a `ClosureMut` borrowing its state, for code that
+    /// takes the borrowed variant. Calls through it update this closure's state.
+    pub fn as_borrowed_mut(&mut self) -> ClosureMut<'_, State, Input, Output> {
+        ClosureMut::new(self.f, &mut self.t)
+    }
+    pub fn state(&self) -> &State {
+        &self.t
+    }
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+    /// Drops the function, returning the state.
+    pub fn into_state(self) -> State {
+        self.t
+    }
+    /// Replaces the state, returning the old one.
+    pub fn replace_state(&mut self, state: State) -> State {
+        mem::replace(&mut self.t, state)
+    }
+    pub fn set_state(&mut self, state: State) {
+        self.t = state
+    }
+    /// Exchanges the states of two closures of the same type, keeping each
+    /// closure's function.
+    pub fn swap_state(&mut self, other: &mut Self) {
+        mem::swap(&mut self.t, &mut other.t)
+    }
+    /// Splits the closure into its function and state, which `from_parts`
+    /// puts back together.
+    pub fn into_parts(self) -> (fn(&mut State, Input) -> Output, State) {
+        (self.f, self.t)
+    }
+    /// Reassembles a closure split by `into_parts`; the same as `new`.
+    pub const fn from_parts(f: fn(&mut State, Input) -> Output, t: State) -> Self {
+        Self { f, t }
+    }
+    /// Identifies the closure by its function and state type.
+    pub fn id(&self) -> ClosureId
+    where
+        State: 'static
+    {
+        ClosureId::new::<State>(self.f as usize)
+    }
 }
 
 #[doc="
@@ -277,7 +537,7 @@ move |...| { /*consumes captured variables */ }
 Example:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureOnce;
 # use namable_closures::StableFnOnce;
 # use std::io;
@@ -308,7 +568,7 @@ let read_data:ClosureOnce<MyStream,(&mut [u8],usize),Result<(),io::Error>>
 # The same example that uses the unstable features:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureOnce;
 # use std::io;
 # struct RoleSet();
@@ -352,40 +612,116 @@ where
     }
 }
 impl<State, Input, Output> ClosureOnce<State, Input, Output> {
-    pub fn new(f: fn(State, Input) -> Output, t: State) -> ClosureOnce<State, Input, Output> {
+    #[inline]
+    pub const fn new(f: fn(State, Input) -> Output, t: State) -> ClosureOnce<State, Input, Output> {
         Self { f, t }
     }
     pub fn call_with_state(&mut self, t: State, i:Input) -> Output {
         (self.f)(t, i)
     }
+    /// Keeps the state and replaces the function with one that only reads it.
+    pub fn into_ref_with<I, O>(self, f: fn(&State, I) -> O) -> ClosureRef<State, I, O> {
+        ClosureRef::new(f, self.t)
+    }
+    /// Keeps the state and replaces the function with one that mutates it.
+    pub fn into_ref_mut_with<I, O>(self, f: fn(&mut State, I) -> O) -> ClosureRefMut<State, I, O> {
+        ClosureRefMut::new(f, self.t)
+    }
+    /// Keeps the function and calls it on the part of `state` that `proj`
+    /// returns. The closure's own state is not used by the new closure, so it
+    /// is returned alongside.
+    pub fn map_state<NewState>(self, proj: fn(NewState) -> State, state: NewState) -> (MapStateOnce<NewState, State, Input, Output>, State) {
+        (MapStateOnce::new(self.f, proj, state), self.t)
+    }
+    /// Keeps the state in a slot, so that the closure can be called once
+    /// through `&mut` whether or not the state is `Copy`.
+    pub fn into_slot(self) -> ClosureOnceSlot<State, Input, Output> {
+        ClosureOnceSlot::new(self.f, self.t)
+    }
+    pub fn state(&self) -> &State {
+        &self.t
+    }
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+    /// Drops the function, returning the state.
+    pub fn into_state(self) -> State {
+        self.t
+    }
+    /// Replaces the state, returning the old one.
+    pub fn replace_state(&mut self, state: State) -> State {
+        mem::replace(&mut self.t, state)
+    }
+    pub fn set_state(&mut self, state: State) {
+        self.t = state
+    }
+    /// Exchanges the states of two closures of the same type, keeping each
+    /// closure's function.
+    pub fn swap_state(&mut self, other: &mut Self) {
+        mem::swap(&mut self.t, &mut other.t)
+    }
+    /// Splits the closure into its function and state, which `from_parts`
+    /// puts back together.
+    pub fn into_parts(self) -> (fn(State, Input) -> Output, State) {
+        (self.f, self.t)
+    }
+    /// Reassembles a closure split by `into_parts`; the same as `new`.
+    pub const fn from_parts(f: fn(State, Input) -> Output, t: State) -> Self {
+        Self { f, t }
+    }
+    /// Identifies the closure by its function and state type.
+    pub fn id(&self) -> ClosureId
+    where
+        State: 'static
+    {
+        ClosureId::new::<State>(self.f as usize)
+    }
 }
 
-use stable_fn::{StableFn,StableFnMut,StableFnOnce};
+#[cfg(feature="nightly")]
+use core::marker::Tuple;
+use core::mem;
+
+use crate::closure_id::ClosureId;
+use crate::map_state::{MapStateOnce,MapStateRef,MapStateRefMut};
+use crate::once_slot::ClosureOnceSlot;
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
 
 //All Closures implements StableFnOnce
-impl<'a, State, Input, Output> StableFnOnce<Input> for Closure<'a, State, Input, Output> {
+impl<'a, State, Input, Output> StableFnOnce<Input> for Closure<'a, State, Input, Output>
+where
+    State: ?Sized
+{
     type Output = Output;
+    #[inline]
     fn stable_call_once(self, i: Input) -> Output {
         let Self { f, t } = self;
-        f(&t, i)
+        f(t, i)
     }
 }
 impl<State, Input, Output> StableFnOnce<Input> for ClosureRef<State, Input, Output> {
     type Output = Output;
+    #[inline]
     fn stable_call_once(self, i: Input) -> Output {
         let Self { f, t } = self;
         f(&t, i)
     }
 }
-impl<'a, State, Input, Output> StableFnOnce<Input> for ClosureMut<'a, State, Input, Output> {
+impl<'a, State, Input, Output> StableFnOnce<Input> for ClosureMut<'a, State, Input, Output>
+where
+    State: ?Sized
+{
     type Output = Output;
+    #[inline]
     fn stable_call_once(self, i: Input) -> Output {
-        let Self { f, mut t } = self;
-        f(&mut t, i)
+        let Self { f, t } = self;
+        f(t, i)
     }
 }
 impl<State, Input, Output> StableFnOnce<Input> for ClosureRefMut<State, Input, Output> {
     type Output = Output;
+    #[inline]
     fn stable_call_once(self, i: Input) -> Output {
         let Self { f, mut t } = self;
         f(&mut t, i)
@@ -393,53 +729,72 @@ impl<State, Input, Output> StableFnOnce<Input> for ClosureRefMut<State, Input, O
 }
 impl<State, Input, Output> StableFnOnce<Input> for ClosureOnce<State, Input, Output> {
     type Output = Output;
+    #[inline]
     fn stable_call_once(self, i: Input) -> Output {
         let ClosureOnce { f, t } = self;
         f(t, i)
     }
 }
 
-impl<'a, State, Input, Output> StableFnMut<Input> for Closure<'a, State, Input, Output> {
+impl<'a, State, Input, Output> StableFnMut<Input> for Closure<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    #[inline]
     fn stable_call_mut(&mut self, i: Input) -> Output {
         let Self { f, t } = self;
         f(t, i)
     }
 }
 impl<State, Input, Output> StableFnMut<Input> for ClosureRef<State, Input, Output> {
+    #[inline]
     fn stable_call_mut(&mut self, i: Input) -> Output {
         let Self { f, t } = self;
         f(t, i)
     }
 }
-impl<'a, State, Input, Output> StableFnMut<Input> for ClosureMut<'a, State, Input, Output> {
+impl<'a, State, Input, Output> StableFnMut<Input> for ClosureMut<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    #[inline]
     fn stable_call_mut(&mut self, i: Input) -> Output {
         let Self { ref f, ref mut t } = self;
         f(t, i)
     }
 }
 impl<State, Input, Output> StableFnMut<Input> for ClosureRefMut<State, Input, Output> {
+    #[inline]
     fn stable_call_mut(&mut self, i: Input) -> Output {
         let Self { ref f, ref mut t } = self;
         f(t, i)
     }
 }
+/// Each call consumes a copy of the state. For a state that is not `Copy`, see
+/// `ClosureOnceSlot`.
 impl<State, Input, Output> StableFnMut<Input> for ClosureOnce<State, Input, Output>
 where
     State: Copy
 {
+    #[inline]
     fn stable_call_mut(&mut self, i: Input) -> Output {
         let Self { f, t } = *self;
         f(t, i)
     }
 }
 
-impl<'a, State, Input, Output> StableFn<Input> for Closure<'a, State, Input, Output> {
+impl<'a, State, Input, Output> StableFn<Input> for Closure<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    #[inline]
     fn stable_call(&self, i: Input) -> Output {
         let Self { f, t } = self;
         f(t, i)
     }
 }
 impl<State, Input, Output> StableFn<Input> for ClosureRef<State, Input, Output> {
+    #[inline]
     fn stable_call(&self, i: Input) -> Output {
         let Self { f, t } = self;
         f(t, i)
@@ -458,6 +813,7 @@ impl<State, Input, Output> StableFn<Input> for ClosureRefMut<State, Input, Outpu
 where
     State: Copy
 {
+    #[inline]
     fn stable_call(&self, i: Input) -> Output {
         let Self { f, mut t } = *self;
         f(&mut t, i)
@@ -467,47 +823,131 @@ impl<State, Input, Output> StableFn<Input> for ClosureOnce<State, Input, Output>
 where
     State: Copy
 {
+    #[inline]
     fn stable_call(&self, i: Input) -> Output {
         let Self { f, t } = *self;
         f(t, i)
     }
 }
 
+impl<'a, State, Input, Output> ClosureState for Closure<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    type State = State;
+    fn state(&self) -> &State {
+        self.t
+    }
+}
+impl<State, Input, Output> ClosureState for ClosureRef<State, Input, Output> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.t
+    }
+}
+impl<'a, State, Input, Output> ClosureState for ClosureMut<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    type State = State;
+    fn state(&self) -> &State {
+        self.t
+    }
+}
+impl<State, Input, Output> ClosureState for ClosureRefMut<State, Input, Output> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.t
+    }
+}
+impl<State, Input, Output> ClosureState for ClosureOnce<State, Input, Output> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.t
+    }
+}
+
+impl<State, Input, Output> ClosureStateMut for ClosureRef<State, Input, Output> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+}
+impl<'a, State, Input, Output> ClosureStateMut for ClosureMut<'a, State, Input, Output>
+where
+    State: ?Sized
+{
+    fn state_mut(&mut self) -> &mut State {
+        self.t
+    }
+}
+impl<State, Input, Output> ClosureStateMut for ClosureRefMut<State, Input, Output> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+}
+impl<State, Input, Output> ClosureStateMut for ClosureOnce<State, Input, Output> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+}
+
 #[cfg(feature="nightly")]
-impl<'a, State, Input, Output> FnOnce<Input> for Closure<'a, State, Input, Output> {
+impl<'a, State, Input, Output> FnOnce<Input> for Closure<'a, State, Input, Output>
+where
+    State: ?Sized,
+    Input: Tuple
+{
     type Output = Output;
+    #[inline]
     extern "rust-call" fn call_once(self, i: Input) -> Output {
         let Self { f, t } = self;
         f(&t, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<State, Input, Output> FnOnce<Input> for ClosureRef<State, Input, Output> {
+impl<State, Input, Output> FnOnce<Input> for ClosureRef<State, Input, Output>
+where
+    Input: Tuple
+{
     type Output = Output;
+    #[inline]
     extern "rust-call" fn call_once(self, i: Input) -> Output {
         let Self { f, t } = self;
         f(&t, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<'a, State, Input, Output> FnOnce<Input> for ClosureMut<'a, State, Input, Output> {
+impl<'a, State, Input, Output> FnOnce<Input> for ClosureMut<'a, State, Input, Output>
+where
+    State: ?Sized,
+    Input: Tuple
+{
     type Output = Output;
+    #[inline]
     extern "rust-call" fn call_once(self, i: Input) -> Output {
         let Self { f, mut t } = self;
         f(&mut t, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<State, Input, Output> FnOnce<Input> for ClosureRefMut<State, Input, Output> {
+impl<State, Input, Output> FnOnce<Input> for ClosureRefMut<State, Input, Output>
+where
+    Input: Tuple
+{
     type Output = Output;
+    #[inline]
     extern "rust-call" fn call_once(self, i: Input) -> Output {
         let Self { f, mut t } = self;
         f(&mut t, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<State, Input, Output> FnOnce<Input> for ClosureOnce<State, Input, Output> {
+impl<State, Input, Output> FnOnce<Input> for ClosureOnce<State, Input, Output>
+where
+    Input: Tuple
+{
     type Output = Output;
+    #[inline]
     extern "rust-call" fn call_once(self, i: Input) -> Output {
         let Self { f, t } = self;
         f(t, i)
@@ -515,28 +955,46 @@ impl<State, Input, Output> FnOnce<Input> for ClosureOnce<State, Input, Output> {
 }
 
 #[cfg(feature="nightly")]
-impl<'a, State, Input, Output> FnMut<Input> for Closure<'a, State, Input, Output> {
+impl<'a, State, Input, Output> FnMut<Input> for Closure<'a, State, Input, Output>
+where
+    State: ?Sized,
+    Input: Tuple
+{
+    #[inline]
     extern "rust-call" fn call_mut(&mut self, i: Input) -> Output {
         let Self { f, t } = self;
         f(t, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<State, Input, Output> FnMut<Input> for ClosureRef<State, Input, Output> {
+impl<State, Input, Output> FnMut<Input> for ClosureRef<State, Input, Output>
+where
+    Input: Tuple
+{
+    #[inline]
     extern "rust-call" fn call_mut(&mut self, i: Input) -> Output {
         let Self { f, t } = self;
         f(t, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<'a, State, Input, Output> FnMut<Input> for ClosureMut<'a, State, Input, Output> {
+impl<'a, State, Input, Output> FnMut<Input> for ClosureMut<'a, State, Input, Output>
+where
+    State: ?Sized,
+    Input: Tuple
+{
+    #[inline]
     extern "rust-call" fn call_mut(&mut self, i: Input) -> Output {
         let Self { ref f, ref mut t } = self;
         f(t, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<State, Input, Output> FnMut<Input> for ClosureRefMut<State, Input, Output> {
+impl<State, Input, Output> FnMut<Input> for ClosureRefMut<State, Input, Output>
+where
+    Input: Tuple
+{
+    #[inline]
     extern "rust-call" fn call_mut(&mut self, i: Input) -> Output {
         let Self { ref f, ref mut t } = self;
         f(t, i)
@@ -545,8 +1003,10 @@ impl<State, Input, Output> FnMut<Input> for ClosureRefMut<State, Input, Output>
 #[cfg(feature="nightly")]
 impl<State, Input, Output> FnMut<Input> for ClosureOnce<State, Input, Output>
 where
+    Input: Tuple,
     State: Copy
 {
+    #[inline]
     extern "rust-call" fn call_mut(&mut self, i: Input) -> Output {
         let Self { f, t } = *self;
         f(t, i)
@@ -554,14 +1014,23 @@ where
 }
 
 #[cfg(feature="nightly")]
-impl<'a, State, Input, Output> Fn<Input> for Closure<'a, State, Input, Output> {
+impl<'a, State, Input, Output> Fn<Input> for Closure<'a, State, Input, Output>
+where
+    State: ?Sized,
+    Input: Tuple
+{
+    #[inline]
     extern "rust-call" fn call(&self, i: Input) -> Output {
         let Self { f, t } = self;
         f(t, i)
     }
 }
 #[cfg(feature="nightly")]
-impl<State, Input, Output> Fn<Input> for ClosureRef<State, Input, Output> {
+impl<State, Input, Output> Fn<Input> for ClosureRef<State, Input, Output>
+where
+    Input: Tuple
+{
+    #[inline]
     extern "rust-call" fn call(&self, i: Input) -> Output {
         let Self { f, t } = self;
         f(t, i)
@@ -582,8 +1051,10 @@ impl<State, Input, Output> Fn<Input> for ClosureRef<State, Input, Output> {
 #[cfg(feature="nightly")]
 impl<State, Input, Output> Fn<Input> for ClosureOnce<State, Input, Output>
 where
+    Input: Tuple,
     State: Copy
 {
+    #[inline]
     extern "rust-call" fn call(&self, i: Input) -> Output {
         let Self { f, t } = *self;
         f(t, i)
@@ -591,9 +1062,10 @@ where
 }
 
 #[cfg(test)]
+#[allow(clippy::clone_on_copy)]
 mod tests {
-    use {Closure, ClosureMut, ClosureOnce, ClosureRef, ClosureRefMut};
-    use {StableFn,StableFnMut,StableFnOnce};
+    use crate::{Closure, ClosureMut, ClosureOnce, ClosureRef, ClosureRefMut};
+    use crate::{StableFn,StableFnMut,StableFnOnce};
 
     #[cfg(feature="nightly")]
     #[test]
@@ -685,4 +1157,127 @@ mod tests {
         assert_eq!(cloned.stable_call_once((20,)), 30);
         assert_eq!(cloned.stable_call_once((20,)), 30);
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_owning_conversions() {
+        let r:ClosureRef<Vec<i32>,(i32,),usize>
+             = closure!(ref v=vec![1,2] => move |i| v.iter().filter(|&&x| x==i).count());
+        assert_eq!(r.stable_call((1,)), 1);
+        let mut m = r.into_ref_mut_with(|v, (i,)| {v.push(i); v.len()});
+        assert_eq!(m.stable_call_mut((1,)), 3);
+        let o = m.into_once_with(|v, (i,):(i32,)| v.into_iter().filter(|&x| x==i).count());
+        assert_eq!(o.clone().stable_call_once((1,)), 2);
+        let r = o.into_ref_with(|v, ()| v.len());
+        assert_eq!(r.stable_call(()), 3);
+        let m = r.into_once_with(|v, ()| v).into_ref_mut_with(|v, ()| v.pop());
+        assert_eq!(m.into_ref_with(|v, ()| v.len()).stable_call(()), 3);
+    }
+    #[test]
+    fn test_readonly_view() {
+        let mut push:ClosureRefMut<Vec<i32>,(i32,),usize> = closure!(ref mut v=vec![] => move |i| {v.push(i); v.len()});
+        push.stable_call_mut((1,));
+        let contains = push.as_readonly(|v, (i,):(i32,)| v.contains(&i));
+        assert!(contains.stable_call((1,)));
+        assert!(!contains.stable_call((2,)));
+        assert_eq!(contains.state(), &vec![1]);
+        push.stable_call_mut((2,));
+        assert!(push.as_readonly(|v, (i,):(i32,)| v.contains(&i)).stable_call((2,)));
+    }
+    #[test]
+    fn test_state_accessors() {
+        let base = 10;
+        let add:Closure<i32,(i32,),i32> = closure!(b=&base => |i| i+*b);
+        assert_eq!(*add.state(), 10);
+        assert!(core::ptr::eq(add.into_state(), &base));
+        let mut count = 0;
+        let mut inc:ClosureMut<i32,(),()> = closure!(mut n=&mut count => || *n+=1);
+        inc.stable_call_mut(());
+        *inc.state_mut() += 10;
+        *inc.into_state() += 100;
+        assert_eq!(count, 111);
+        let mut name:ClosureRef<String,(),usize> = closure!(ref s=String::from("ab") => move || s.len());
+        name.state_mut().push('c');
+        assert_eq!(name.stable_call(()), 3);
+        assert_eq!(name.into_state(), "abc");
+        let mut push:ClosureRefMut<Vec<i32>,(i32,),()> = closure!(ref mut v=vec![] => move |i| v.push(i));
+        push.stable_call_mut((1,));
+        push.state_mut().push(2);
+        assert_eq!(push.state(), &[1, 2]);
+        assert_eq!(push.into_state(), [1, 2]);
+        let mut once:ClosureOnce<Vec<i32>,(),usize> = closure!(v=vec![1] => move || v.len());
+        once.state_mut().clear();
+        assert!(once.state().is_empty());
+        assert_eq!(once.into_state(), Vec::<i32>::new());
+    }
+    #[test]
+    fn test_parts_round_trip() {
+        struct Component<S> { f: fn(&mut S, (i32,)) -> i32, state: S }
+        let acc:ClosureRefMut<i32,(i32,),i32> = closure!(ref mut n=0 => move |i| {*n+=i;*n});
+        let (f, state) = acc.into_parts();
+        let mut stored = Component { f, state };
+        stored.state += 10;
+        let mut acc = ClosureRefMut::from_parts(stored.f, stored.state);
+        assert_eq!(acc.stable_call_mut((1,)), 11);
+        let (f, s) = ClosureOnce::new(|v:Vec<i32>, ()| v.len(), vec![1, 2]).into_parts();
+        assert_eq!(ClosureOnce::from_parts(f, s).stable_call_once(()), 2);
+        let base = 1;
+        let (f, s) = Closure::new(|b, (i,)| i + *b, &base).into_parts();
+        assert_eq!(Closure::from_parts(f, s).stable_call((1,)), 2);
+    }
+    #[test]
+    fn test_unsized_state() {
+        let words = ["a", "bc", "de"];
+        let count:Closure<[&str],(usize,),usize> = closure!(w=&words[..] => |n| w.iter().filter(|s| s.len() == n).count());
+        assert_eq!(count.stable_call((2,)), 2);
+        let shown:&dyn core::fmt::Display = &5;
+        let show:Closure<dyn core::fmt::Display,(),String> = closure!(d=shown => || d.to_string());
+        assert_eq!(show.stable_call(()), "5");
+        let mut buf = [0u8; 3];
+        {
+            let mut fill:ClosureMut<[u8],(u8,),()> = closure!(mut b=&mut buf[..] => |v| b.fill(v));
+            fill.stable_call_mut((7,));
+            assert_eq!(fill.state().len(), 3);
+        }
+        assert_eq!(buf, [7, 7, 7]);
+    }
+    #[test]
+    fn test_to_owned_ref_escapes_the_borrow() {
+        let owned:ClosureRef<Vec<i32>,(usize,),i32> = {
+            let v = vec![1, 2, 3];
+            let get:Closure<Vec<i32>,(usize,),i32> = closure!(v=&v => |i| v[i]);
+            get.to_owned_ref()
+        };
+        assert_eq!(owned.stable_call((2,)), 3);
+    }
+    #[test]
+    fn test_as_borrowed() {
+        fn twice(f:Closure<String,(),usize>) -> usize {
+            f.stable_call(()) * 2
+        }
+        let len:ClosureRef<String,(),usize> = closure!(ref s=String::from("abc") => move || s.len());
+        assert_eq!(twice(len.as_borrowed()), 6);
+        assert!(core::ptr::eq(len.as_borrowed().state(), len.state()));
+    }
+    #[test]
+    fn test_as_borrowed_mut() {
+        fn run(mut f:ClosureMut<Vec<i32>,(i32,),usize>) -> usize {
+            f.stable_call_mut((1,));
+            f.stable_call_mut((2,))
+        }
+        let mut push:ClosureRefMut<Vec<i32>,(i32,),usize> = closure!(ref mut v=vec![] => move |i| {v.push(i); v.len()});
+        assert_eq!(run(push.as_borrowed_mut()), 2);
+        assert_eq!(push.stable_call_mut((3,)), 3);
+        assert_eq!(push.state(), &[1, 2, 3]);
+    }
+    #[test]
+    fn test_replace_and_swap_state() {
+        let mut greet:ClosureRef<String,(),String> = closure!(ref name=String::from("Ann") => move || format!("hi {}", name));
+        assert_eq!(greet.replace_state("Bo".into()), "Ann");
+        assert_eq!(greet.stable_call(()), "hi Bo");
+        let mut other:ClosureRef<String,(),String> = closure!(ref name=String::from("Cy") => move || format!("bye {}", name));
+        greet.swap_state(&mut other);
+        assert_eq!((greet.stable_call(()), other.stable_call(())), ("hi Cy".into(), "bye Bo".into()));
+        let mut count:ClosureRefMut<u32,(),u32> = closure!(ref mut n=5 => move || {*n+=1;*n});
+        count.set_state(0);
+        assert_eq!(count.stable_call_mut(()), 1);
+    }
+}
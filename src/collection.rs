@@ -0,0 +1,62 @@
+//! The inspection API shared by the closure containers.
+//!
+//! `ClosureArray`, `ClosureMap`, `ClosureSlab` and `EventEmitter` all store
+//! closures of a single type. Their inherent methods differ where the
+//! containers do (keys, ordering, interior mutability), but counting,
+//! iterating and filtering work the same way everywhere and are collected in
+//! `ClosureCollection` for code that is generic over the container.
+
+/// A container of closures of type `Closure`.
+///
+/// The iterators yield what the container's inherent `iter` and `iter_mut`
+/// yield: bare closures for `ClosureArray`, and closures paired with their
+/// key for the keyed containers.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureArray,ClosureCollection,ClosureRef,ClosureSlab,StableFn};
+/// fn keep_positive<T>(c: &mut T) -> usize
+/// where
+///     T: ClosureCollection,
+///     T::Closure: StableFn<(), Output=i32>
+/// {
+///     c.retain(|c| c.stable_call(()) > 0);
+///     c.len()
+/// }
+/// let mut array:ClosureArray<ClosureRef<i32,(),i32>> = (-1..3)
+///     .map(|n| closure!(ref n=n => move || *n))
+///     .collect();
+/// let mut slab = ClosureSlab::new();
+/// for c in &array {
+///     slab.insert(*c);
+/// }
+/// assert_eq!(keep_positive(&mut array), 2);
+/// assert_eq!(keep_positive(&mut slab), 2);
+/// # }
+/// ```
+pub trait ClosureCollection {
+    type Closure;
+    type Iter<'a>: Iterator
+    where
+        Self: 'a;
+    type IterMut<'a>: Iterator
+    where
+        Self: 'a;
+    /// The number of closures currently stored.
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn iter(&self) -> Self::Iter<'_>;
+    fn iter_mut(&mut self) -> Self::IterMut<'_>;
+    /// Keeps only the closures for which `keep` returns `true`.
+    ///
+    /// The keyed containers also have an inherent `retain` whose predicate
+    /// receives the key.
+    fn retain<P>(&mut self, keep: P)
+    where
+        P: FnMut(&mut Self::Closure) -> bool;
+}
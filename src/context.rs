@@ -0,0 +1,281 @@
+//! Closures that borrow a shared context at call time.
+//!
+//! Handlers often need something large and shared, such as an asset cache or
+//! a database handle. Capturing it in every handler's state copies it (or a
+//! reference counted pointer to it) once per handler. A `ContextClosure`
+//! instead takes a `&Ctx` before its other arguments, supplied by whoever
+//! calls it. Its function is higher-ranked over that borrow, so a container
+//! can hold such closures for longer than any one context lives, and an
+//! `EventEmitter` passes the context by emitting `(&ctx, event)`.
+//!
+//! `WithContext` binds a context to a closure, so that callers see the plain
+//! signature.
+
+use core::fmt;
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// A closure over `&mut State` that also borrows a caller-provided `&Ctx`.
+///
+/// It is callable with the context followed by the arguments in `Input`: a
+/// `ContextClosure<Ctx, State, (A, B), Output>` implements
+/// `StableFnMut<(&Ctx, A, B)>` for every lifetime of the borrow.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use namable_closures::{ContextClosure,EventEmitter};
+/// # use std::collections::HashMap;
+/// struct Assets { sizes: HashMap<&'static str, usize> }
+/// type OnLoad = ContextClosure<Assets,usize,(&'static str,),()>;
+/// let emitter:EventEmitter<OnLoad> = EventEmitter::new();
+/// emitter.subscribe(ContextClosure::new(|total, assets, (name,)| *total += assets.sizes[name], 0));
+/// emitter.subscribe(ContextClosure::new(|count, _, _| *count += 1, 0));
+/// let assets = Assets { sizes: HashMap::from([("logo", 120), ("font", 800)]) };
+/// emitter.emit((&assets, "logo"));
+/// emitter.emit((&assets, "font"));
+/// let states:Vec<usize> = emitter.into_iter().map(|(_, h)| h.into_inner()).collect();
+/// assert_eq!(states, [920, 2]);
+/// # }
+/// ```
+pub struct ContextClosure<Ctx, State, Input, Output>
+where
+    Ctx: ?Sized
+{
+    f: fn(&mut State, &Ctx, Input) -> Output,
+    t: State,
+}
+impl<Ctx, State, Input, Output> Copy for ContextClosure<Ctx, State, Input, Output>
+where
+    Ctx: ?Sized,
+    State: Copy
+{}
+impl<Ctx, State, Input, Output> Clone for ContextClosure<Ctx, State, Input, Output>
+where
+    Ctx: ?Sized,
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<Ctx, State, Input, Output> ContextClosure<Ctx, State, Input, Output>
+where
+    Ctx: ?Sized
+{
+    pub const fn new(f: fn(&mut State, &Ctx, Input) -> Output, t: State) -> Self {
+        Self { f, t }
+    }
+    /// Calls the closure, lending it `ctx` for the duration of the call.
+    pub fn call_with_context(&mut self, ctx: &Ctx, i: Input) -> Output {
+        (self.f)(&mut self.t, ctx, i)
+    }
+    /// Binds `ctx` so the closure can be passed where a plain
+    /// `StableFnMut<Input>` is expected.
+    pub fn with_context<'c>(&'c mut self, ctx: &'c Ctx) -> WithContext<'c, &'c mut Self, Ctx> {
+        WithContext::new(self, ctx)
+    }
+    pub fn into_inner(self) -> State {
+        self.t
+    }
+}
+impl<Ctx, State, Input, Output> fmt::Debug for ContextClosure<Ctx, State, Input, Output>
+where
+    Ctx: ?Sized,
+    State: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ContextClosure").field("state", &self.t).finish_non_exhaustive()
+    }
+}
+impl<Ctx, State, Input, Output> ClosureState for ContextClosure<Ctx, State, Input, Output>
+where
+    Ctx: ?Sized
+{
+    type State = State;
+    fn state(&self) -> &State {
+        &self.t
+    }
+}
+impl<Ctx, State, Input, Output> ClosureStateMut for ContextClosure<Ctx, State, Input, Output>
+where
+    Ctx: ?Sized
+{
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+}
+
+/// Calls `F` with a borrow of the context `Ctx` before the other arguments.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,StableFn,WithContext};
+/// fn twice<F: StableFn<(u32,), Output=u32>>(f: F) -> u32 {
+///     f.stable_call((1,)) + f.stable_call((2,))
+/// }
+/// let scale = 10;
+/// let scaled:ClosureRef<(),(&u32,u32),u32> = closure!(ref _s=() => move |k, i| i * *k);
+/// assert_eq!(twice(WithContext::new(scaled, &scale)), 30);
+/// ```
+pub struct WithContext<'c, F, Ctx>
+where
+    Ctx: ?Sized
+{
+    f: F,
+    ctx: &'c Ctx,
+}
+impl<'c, F, Ctx> Copy for WithContext<'c, F, Ctx>
+where
+    F: Copy,
+    Ctx: ?Sized
+{}
+impl<'c, F, Ctx> Clone for WithContext<'c, F, Ctx>
+where
+    F: Clone,
+    Ctx: ?Sized
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f.clone(), ctx: self.ctx }
+    }
+}
+impl<'c, F, Ctx> WithContext<'c, F, Ctx>
+where
+    Ctx: ?Sized
+{
+    pub const fn new(f: F, ctx: &'c Ctx) -> Self {
+        Self { f, ctx }
+    }
+    pub fn context(&self) -> &'c Ctx {
+        self.ctx
+    }
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
+impl<'c, F, Ctx> fmt::Debug for WithContext<'c, F, Ctx>
+where
+    Ctx: ?Sized + fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WithContext").field("context", &self.ctx).finish_non_exhaustive()
+    }
+}
+impl<'c, F, Ctx> ClosureState for WithContext<'c, F, Ctx>
+where
+    F: ClosureState,
+    Ctx: ?Sized
+{
+    type State = F::State;
+    fn state(&self) -> &F::State {
+        self.f.state()
+    }
+}
+impl<'c, F, Ctx> ClosureStateMut for WithContext<'c, F, Ctx>
+where
+    F: ClosureStateMut,
+    Ctx: ?Sized
+{
+    fn state_mut(&mut self) -> &mut F::State {
+        self.f.state_mut()
+    }
+}
+
+macro_rules! context_arity {
+    ($($t:ident $a:ident),*) => {
+        impl<'c, Ctx, State, Output, $($t),*> StableFnOnce<(&'c Ctx, $($t,)*)> for ContextClosure<Ctx, State, ($($t,)*), Output>
+        where
+            Ctx: ?Sized
+        {
+            type Output = Output;
+            #[inline]
+            fn stable_call_once(mut self, (ctx, $($a,)*): (&'c Ctx, $($t,)*)) -> Output {
+                (self.f)(&mut self.t, ctx, ($($a,)*))
+            }
+        }
+        impl<'c, Ctx, State, Output, $($t),*> StableFnMut<(&'c Ctx, $($t,)*)> for ContextClosure<Ctx, State, ($($t,)*), Output>
+        where
+            Ctx: ?Sized
+        {
+            #[inline]
+            fn stable_call_mut(&mut self, (ctx, $($a,)*): (&'c Ctx, $($t,)*)) -> Output {
+                (self.f)(&mut self.t, ctx, ($($a,)*))
+            }
+        }
+        impl<'c, F, Ctx, $($t),*> StableFnOnce<($($t,)*)> for WithContext<'c, F, Ctx>
+        where
+            F: StableFnOnce<(&'c Ctx, $($t,)*)>,
+            Ctx: ?Sized
+        {
+            type Output = F::Output;
+            fn stable_call_once(self, ($($a,)*): ($($t,)*)) -> F::Output {
+                self.f.stable_call_once((self.ctx, $($a,)*))
+            }
+        }
+        impl<'c, F, Ctx, $($t),*> StableFnMut<($($t,)*)> for WithContext<'c, F, Ctx>
+        where
+            F: StableFnMut<(&'c Ctx, $($t,)*)>,
+            Ctx: ?Sized
+        {
+            fn stable_call_mut(&mut self, ($($a,)*): ($($t,)*)) -> F::Output {
+                self.f.stable_call_mut((self.ctx, $($a,)*))
+            }
+        }
+        impl<'c, F, Ctx, $($t),*> StableFn<($($t,)*)> for WithContext<'c, F, Ctx>
+        where
+            F: StableFn<(&'c Ctx, $($t,)*)>,
+            Ctx: ?Sized
+        {
+            fn stable_call(&self, ($($a,)*): ($($t,)*)) -> F::Output {
+                self.f.stable_call((self.ctx, $($a,)*))
+            }
+        }
+    };
+}
+context_arity!();
+context_arity!(A a);
+context_arity!(A a, B b);
+context_arity!(A a, B b, C c);
+context_arity!(A a, B b, C c, D d);
+context_arity!(A a, B b, C c, D d, E e);
+
+#[cfg(test)]
+mod tests {
+    use crate::{ContextClosure,StableFnMut,StableFnOnce,WithContext};
+
+    struct Db { rows: Vec<(u32, &'static str)> }
+
+    type Lookup = ContextClosure<Db,usize,(u32,),Option<&'static str>>;
+
+    fn drive<F:StableFnMut<(u32,),Output=Option<&'static str>>>(mut f:F) -> usize {
+        (1..=3).filter_map(|id| f.stable_call_mut((id,))).count()
+    }
+
+    #[test]
+    fn test_context_is_lent_per_call() {
+        let mut lookups:Vec<Lookup> = vec![
+            ContextClosure::new(|hits, db, (id,)| {
+                let row = db.rows.iter().find(|r| r.0 == id).map(|r| r.1);
+                *hits += row.is_some() as usize;
+                row
+            }, 0),
+            ContextClosure::new(|calls, db, (id,)| { *calls += 1; db.rows.get(id as usize).map(|r| r.1) }, 0),
+        ];
+        // the closures outlive both contexts
+        {
+            let db = Db { rows: vec![(1, "one"), (3, "three")] };
+            assert_eq!(lookups[0].call_with_context(&db, (3,)), Some("three"));
+            assert_eq!(lookups[1].stable_call_mut((&db, 1)), Some("three"));
+        }
+        let db = Db { rows: vec![(2, "two")] };
+        assert_eq!(drive(lookups[0].with_context(&db)), 1);
+        assert_eq!(lookups[0].stable_call_once((&db, 2)), Some("two"));
+        assert_eq!(WithContext::new(&mut lookups[1], &db).stable_call_mut((0,)), Some("two"));
+        assert_eq!(lookups[0].into_inner(), 2);
+        assert_eq!(lookups[1].into_inner(), 2);
+    }
+}
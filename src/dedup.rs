@@ -0,0 +1,145 @@
+//! Skipping calls that repeat the previous input.
+//!
+//! Change handlers are often called again with a value they have already
+//! seen. `Dedup` remembers the last input and its output, and only calls the
+//! closure when the input changes.
+
+use core::fmt;
+
+use crate::stable_fn::{StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// Calls `F` only when the input differs from the previous call's, and
+/// otherwise returns a clone of the previous output.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRefMut,ClosureState,Dedup,StableFnMut};
+/// let redraw:ClosureRefMut<u32,(&str,),u32> = closure!(ref mut n=0 => move |_text| {*n+=1;*n});
+/// let mut on_change = Dedup::new(redraw);
+/// for text in ["a", "a", "ab", "ab", "a"] {
+///     on_change.stable_call_mut((text,));
+/// }
+/// assert_eq!(*on_change.state(), 3);
+/// ```
+pub struct Dedup<F, Input>
+where
+    F: StableFnOnce<Input>
+{
+    f: F,
+    last: Option<(Input, F::Output)>,
+}
+impl<F, Input> Dedup<F, Input>
+where
+    F: StableFnOnce<Input>
+{
+    pub fn new(f: F) -> Self {
+        Self { f, last: None }
+    }
+    /// The input of the previous call, if it is remembered.
+    pub fn last_input(&self) -> Option<&Input> {
+        self.last.as_ref().map(|(i, _)| i)
+    }
+    /// Forgets the previous call, so that the next call goes through.
+    pub fn reset(&mut self) {
+        self.last = None
+    }
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
+impl<F, Input> Clone for Dedup<F, Input>
+where
+    F: StableFnOnce<Input> + Clone,
+    Input: Clone,
+    F::Output: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f.clone(), last: self.last.clone() }
+    }
+}
+impl<F, Input> fmt::Debug for Dedup<F, Input>
+where
+    F: StableFnOnce<Input>,
+    Input: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Dedup").field("last_input", &self.last_input()).finish_non_exhaustive()
+    }
+}
+impl<F, Input> ClosureState for Dedup<F, Input>
+where
+    F: StableFnOnce<Input> + ClosureState
+{
+    type State = F::State;
+    fn state(&self) -> &F::State {
+        self.f.state()
+    }
+}
+impl<F, Input> ClosureStateMut for Dedup<F, Input>
+where
+    F: StableFnOnce<Input> + ClosureStateMut
+{
+    /// Borrows the state mutably and forgets the previous call, whose output
+    /// may no longer be what the closure would return.
+    fn state_mut(&mut self) -> &mut F::State {
+        self.reset();
+        self.f.state_mut()
+    }
+}
+impl<F, Input> StableFnOnce<Input> for Dedup<F, Input>
+where
+    F: StableFnOnce<Input>,
+    Input: PartialEq,
+    F::Output: Clone
+{
+    type Output = F::Output;
+    fn stable_call_once(self, args: Input) -> F::Output {
+        match self.last {
+            Some((last, output)) if last == args => output,
+            _ => self.f.stable_call_once(args),
+        }
+    }
+}
+impl<F, Input> StableFnMut<Input> for Dedup<F, Input>
+where
+    F: StableFnMut<Input>,
+    Input: PartialEq + Clone,
+    F::Output: Clone
+{
+    fn stable_call_mut(&mut self, args: Input) -> F::Output {
+        match self.last {
+            Some((ref last, ref output)) if *last == args => output.clone(),
+            _ => {
+                let output = self.f.stable_call_mut(args.clone());
+                self.last = Some((args, output.clone()));
+                output
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureRefMut,ClosureState,ClosureStateMut,Dedup,StableFnMut,StableFnOnce};
+
+    #[test]
+    fn test_skips_repeats_until_reset() {
+        let count:ClosureRefMut<u32,(i32,),i32> = closure!(ref mut n=0 => move |i| {*n+=1; i*10});
+        let mut count = Dedup::new(count);
+        assert_eq!(count.stable_call_mut((1,)), 10);
+        assert_eq!(count.stable_call_mut((1,)), 10);
+        assert_eq!(*count.state(), 1);
+        assert_eq!(count.last_input(), Some(&(1,)));
+        count.reset();
+        assert_eq!(count.stable_call_mut((1,)), 10);
+        assert_eq!(*count.state(), 2);
+        *count.state_mut() = 0;
+        assert_eq!(count.last_input(), None);
+        assert_eq!(count.stable_call_mut((1,)), 10);
+        assert_eq!(count.clone().stable_call_once((1,)), 10);
+        assert_eq!(count.stable_call_once((2,)), 20);
+    }
+}
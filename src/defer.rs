@@ -0,0 +1,101 @@
+//! A namable scope guard.
+//!
+//! `DeferGuard` owns a state and a function that consumes it. The function runs
+//! when the guard is dropped, unless the guard is dismissed first.
+
+use core::fmt;
+use core::ops::{Deref,DerefMut};
+
+use crate::raw::DropWith;
+
+/// Runs `f(state)` when dropped.
+///
+/// The guard dereferences to its state, so it can be used in place of the
+/// guarded value until the end of the scope.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::defer;
+/// # use namable_closures::DeferGuard;
+/// # use std::cell::Cell;
+/// let released = Cell::new(false);
+/// {
+///     defer!(flag=&released => flag.set(true));
+///     assert!(!released.get());
+/// }
+/// assert!(released.get());
+/// ```
+pub struct DeferGuard<State> {
+    t: DropWith<State>,
+}
+impl<State> DeferGuard<State> {
+    pub fn new(f: fn(State), t: State) -> Self {
+        Self { t: DropWith::new(t, f) }
+    }
+    /// Disarms the guard and returns its state without running the function.
+    pub fn dismiss(self) -> State {
+        self.t.into_inner()
+    }
+}
+impl<State> Deref for DeferGuard<State> {
+    type Target = State;
+    fn deref(&self) -> &State {
+        &self.t
+    }
+}
+impl<State> DerefMut for DeferGuard<State> {
+    fn deref_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+}
+impl<State> fmt::Debug for DeferGuard<State>
+where
+    State: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("DeferGuard").field(&*self.t).finish()
+    }
+}
+
+/// Creates a guard that calls `f(state)` when dropped.
+pub fn guard<State>(state: State, f: fn(State)) -> DeferGuard<State> {
+    DeferGuard::new(f, state)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell,RefCell};
+    use crate::defer::{guard,DeferGuard};
+
+    #[test]
+    fn test_runs_on_drop() {
+        let log = RefCell::new(Vec::new());
+        {
+            defer!(log=&log => log.borrow_mut().push(1));
+            let mut g = guard((&log, 2), |(log, n)| log.borrow_mut().push(n));
+            g.1 += 1;
+            log.borrow_mut().push(0);
+        }
+        // guards drop in reverse declaration order
+        assert_eq!(*log.borrow(), vec![0,3,1]);
+    }
+    #[test]
+    fn test_dismiss() {
+        let hits = Cell::new(0);
+        let g:DeferGuard<(&Cell<i32>,i32)> = guard((&hits, 5), |(h, n)| h.set(h.get()+n));
+        let (_, n) = g.dismiss();
+        assert_eq!(n, 5);
+        assert_eq!(hits.get(), 0);
+    }
+    #[test]
+    fn test_runs_on_unwind() {
+        let hits = Cell::new(0);
+        let r = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+            defer!(h=&hits => h.set(h.get()+1));
+            panic!("boom");
+        }));
+        assert!(r.is_err());
+        assert_eq!(hits.get(), 1);
+    }
+}
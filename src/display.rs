@@ -0,0 +1,222 @@
+//! Deferred formatting with a namable type.
+//!
+//! `DisplayWith` pairs a formatting function with the state it formats, so a
+//! "how to print this" value can be stored in a struct field (a log record, an
+//! error context) and only rendered when it is actually displayed.
+//!
+//! Nothing is allocated: the value is written straight into the formatter, so
+//! a `DisplayWith` can be queued in a fixed-size ring buffer and handed to a
+//! log front-end later. With the `log` feature, `DisplayWith::log` does that
+//! for the `log` crate.
+//!
+//! `FmtWriter` goes the other way: it turns a namable closure over `&str` into
+//! a `fmt::Write` sink, so `write!` can target a UART or a fixed buffer held
+//! in the closure's state.
+
+use core::fmt;
+
+use crate::stable_fn::{StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// A value that formats its state with a stored function.
+///
+/// Both `Display` and `Debug` forward to the stored function.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::display_closure;
+/// # use namable_closures::DisplayWith;
+/// let point:DisplayWith<(i32,i32)>
+///     = display_closure!(p=(1,2) => |f| write!(f, "({}, {})", p.0, p.1));
+/// assert_eq!(point.to_string(), "(1, 2)");
+/// assert_eq!(format!("{:?}", point), "(1, 2)");
+/// ```
+pub struct DisplayWith<State> {
+    f: fn(&State, &mut fmt::Formatter) -> fmt::Result,
+    t: State,
+}
+impl<State> Copy for DisplayWith<State>
+where
+    State: Copy
+{}
+impl<State> Clone for DisplayWith<State>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<State> DisplayWith<State> {
+    pub fn new(f: fn(&State, &mut fmt::Formatter) -> fmt::Result, t: State) -> Self {
+        Self { f, t }
+    }
+    /// Logs the value as a record of `level` for `target`.
+    ///
+    /// The value is only formatted if the logger accepts the record.
+    #[cfg(feature="log")]
+    pub fn log(&self, target: &str, level: log::Level) {
+        log::log!(target: target, level, "{}", self)
+    }
+}
+impl<State> fmt::Display for DisplayWith<State> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.f)(&self.t, f)
+    }
+}
+impl<State> fmt::Debug for DisplayWith<State> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.f)(&self.t, f)
+    }
+}
+
+/// A `fmt::Write` implemented by a state plus a `fn(&mut State, &str)`.
+///
+/// Like `IoWriter`, the function is higher-ranked over the string, which a
+/// `ClosureMut<State, (&str,), fmt::Result>` cannot be.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::FmtWriter;
+/// # use std::fmt::Write;
+/// // a fixed buffer that reports an error instead of truncating
+/// let mut line = FmtWriter::new(|s:&mut ([u8;16],usize), text| {
+///     let end = s.1 + text.len();
+///     s.0.get_mut(s.1..end).ok_or(std::fmt::Error)?.copy_from_slice(text.as_bytes());
+///     s.1 = end;
+///     Ok(())
+/// }, ([0;16], 0));
+/// write!(line, "t={}ms", 250).unwrap();
+/// assert!(write!(line, "{}", "too much text").is_err());
+/// let (buf, len) = line.into_inner();
+/// assert_eq!(&buf[..len], b"t=250ms");
+/// ```
+pub struct FmtWriter<State> {
+    f: fn(&mut State, &str) -> fmt::Result,
+    t: State,
+}
+impl<State> Copy for FmtWriter<State>
+where
+    State: Copy
+{}
+impl<State> Clone for FmtWriter<State>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<State> FmtWriter<State> {
+    pub fn new(f: fn(&mut State, &str) -> fmt::Result, t: State) -> Self {
+        Self { f, t }
+    }
+    pub fn into_inner(self) -> State {
+        self.t
+    }
+}
+impl<State> fmt::Write for FmtWriter<State> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        (self.f)(&mut self.t, s)
+    }
+}
+impl<State> fmt::Debug for FmtWriter<State>
+where
+    State: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FmtWriter").field("state", &self.t).finish_non_exhaustive()
+    }
+}
+impl<State> ClosureState for FmtWriter<State> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.t
+    }
+}
+impl<State> ClosureStateMut for FmtWriter<State> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+}
+impl<'a, State> StableFnOnce<(&'a str,)> for FmtWriter<State> {
+    type Output = fmt::Result;
+    fn stable_call_once(mut self, (s,): (&'a str,)) -> fmt::Result {
+        self.stable_call_mut((s,))
+    }
+}
+impl<'a, State> StableFnMut<(&'a str,)> for FmtWriter<State> {
+    fn stable_call_mut(&mut self, (s,): (&'a str,)) -> fmt::Result {
+        (self.f)(&mut self.t, s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+    use crate::display::{DisplayWith,FmtWriter};
+    use crate::ClosureState;
+
+    struct Record {
+        level: &'static str,
+        message: DisplayWith<(&'static str,u32)>,
+    }
+
+    #[test]
+    fn test_display_with() {
+        let r = Record {
+            level: "warn",
+            message: display_closure!(s=("retries",3) => |f| write!(f, "{} left: {}", s.0, s.1)),
+        };
+        assert_eq!(format!("[{}] {}", r.level, r.message), "[warn] retries left: 3");
+        let padded:DisplayWith<u8> = display_closure!(n=7 => |f| write!(f, "{:>3}", n));
+        let copied = padded;
+        assert_eq!(copied.to_string(), "  7");
+        assert_eq!(format!("{:?}", padded), "  7");
+    }
+    #[cfg(feature="log")]
+    #[test]
+    fn test_log_formats_only_enabled_records() {
+        use std::cell::Cell;
+        use std::sync::Mutex;
+        struct Capture(Mutex<Vec<String>>);
+        impl log::Log for Capture {
+            fn enabled(&self, m: &log::Metadata) -> bool {
+                m.level() <= log::Level::Info
+            }
+            fn log(&self, r: &log::Record) {
+                if self.enabled(r.metadata()) {
+                    self.0.lock().unwrap().push(format!("{}: {}", r.target(), r.args()));
+                }
+            }
+            fn flush(&self) {}
+        }
+        static LOGGER: Capture = Capture(Mutex::new(Vec::new()));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        let renders = Cell::new(0);
+        let msg:DisplayWith<&Cell<i32>> = display_closure!(n=&renders => |f| {
+            n.set(n.get()+1);
+            write!(f, "rendered {}", n.get())
+        });
+        msg.log("app", log::Level::Debug);
+        msg.log("app", log::Level::Warn);
+        assert_eq!(renders.get(), 1);
+        assert_eq!(*LOGGER.0.lock().unwrap(), vec!["app: rendered 1"]);
+    }
+    #[test]
+    fn test_fmt_writer_receives_display_with() {
+        let point:DisplayWith<(i32,i32)> = display_closure!(p=(3,-4) => |f| write!(f, "({}, {})", p.0, p.1));
+        // counts the pieces the formatter hands over, as a UART driver would see them
+        let mut uart = FmtWriter::new(|s:&mut (String,usize), text| {
+            s.0.push_str(text);
+            s.1 += 1;
+            Ok(())
+        }, (String::new(), 0));
+        write!(uart, "at {}", point).unwrap();
+        assert_eq!(uart.state().0, "at (3, -4)");
+        assert!(uart.state().1 > 1);
+    }
+}
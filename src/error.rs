@@ -0,0 +1,51 @@
+//! The crate's error type.
+//!
+//! Every API that checks a precondition at run time and would otherwise panic
+//! (indexing a container, calling a finished chain) has a `try_*` variant
+//! returning this error instead, so that builds with `panic = "abort"` and a
+//! minimal panic handler never need to reach it.
+
+use core::fmt;
+
+/// Why a checked call could not be made.
+///
+/// More variants may be added, so matches need a wildcard arm.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Error {
+    /// The `SlabKey` refers to a closure that was removed, or to another slab.
+    StaleKey,
+    /// No closure is stored under the key.
+    MissingKey,
+    /// The index is past the end of the container.
+    OutOfBounds,
+    /// The `ClosureChain` has no closure left to call.
+    Finished,
+    /// The `ClosureOnceSlot` was called before and its state is gone.
+    AlreadyCalled,
+    /// No hook with the name and signature is registered.
+    UnknownHook,
+    /// The closure is registered with a different signature than the call.
+    WrongSignature,
+    /// A `TimedOut` closure did not return within its time limit.
+    Timeout,
+    /// The owner of a `RemoteClosure` was dropped, or a `JobPool` job or an
+    /// `EventEmitter` handler panicked, before answering the call.
+    Disconnected,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Error::StaleKey => "stale or invalid SlabKey",
+            Error::MissingKey => "no closure for key",
+            Error::OutOfBounds => "index out of bounds",
+            Error::Finished => "closure chain is finished",
+            Error::AlreadyCalled => "once-closure already called",
+            Error::UnknownHook => "no hook registered with this name and signature",
+            Error::WrongSignature => "closure has a different signature",
+            Error::Timeout => "closure timed out",
+            Error::Disconnected => "the other side of the call is gone or panicked",
+        })
+    }
+}
+impl core::error::Error for Error {}
@@ -0,0 +1,581 @@
+//! A simple event emitter over homogeneous namable closures.
+//!
+//! All handlers of an `EventEmitter` share one namable closure type, so they are
+//! kept in a `ClosureSlab` without boxing. Handlers are allowed to subscribe and
+//! unsubscribe while an `emit` is running; see `EventEmitter::emit` for the rules.
+
+use alloc::vec::{self,Vec};
+use core::cell::{Cell,Ref,RefCell};
+use core::fmt;
+use core::ops::ControlFlow;
+#[cfg(feature="std")]
+use core::panic::AssertUnwindSafe;
+
+use crate::aggregate::Aggregate;
+use crate::closure_slab::{self,ClosureSlab,SlabKey};
+use crate::collection::ClosureCollection;
+#[cfg(feature="std")]
+use crate::error::Error;
+use crate::outcome::CallOutcome;
+use crate::raw::DropWith;
+use crate::stable_fn::StableFnMut;
+use crate::state::ClosureStateMut;
+use crate::weak::WeakClosure;
+
+/// An event emitter whose handlers are all of type `C`.
+///
+/// The emitter uses interior mutability so that handlers can hold a reference
+/// to it and call `subscribe`/`unsubscribe` (or even `emit`) from inside a call.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRefMut,EventEmitter};
+/// let emitter:EventEmitter<ClosureRefMut<i32,(i32,),()>> = EventEmitter::new();
+/// let sum = emitter.subscribe(closure!(ref mut total=0 => move |i| *total+=i));
+/// emitter.emit((1,));
+/// emitter.emit((2,));
+/// emitter.unsubscribe(sum);
+/// assert!(emitter.is_empty());
+/// ```
+pub struct EventEmitter<C> {
+    // A handler is `None` while it is being called.
+    handlers: RefCell<ClosureSlab<Option<C>>>,
+    // Unsubscriptions requested while emitting, applied afterwards.
+    pending: RefCell<Vec<SlabKey>>,
+    depth: Cell<usize>,
+}
+impl<C> Default for EventEmitter<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<C> EventEmitter<C> {
+    pub fn new() -> Self {
+        Self {
+            handlers: RefCell::new(ClosureSlab::new()),
+            pending: RefCell::new(Vec::new()),
+            depth: Cell::new(0),
+        }
+    }
+    /// The number of subscribed handlers, including those with a pending
+    /// unsubscription.
+    pub fn len(&self) -> usize {
+        self.handlers.borrow().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns `true` while an `emit` is in progress.
+    pub fn is_emitting(&self) -> bool {
+        self.depth.get() > 0
+    }
+    /// Adds a handler and returns the key to unsubscribe it.
+    ///
+    /// A handler subscribed while an `emit` is running will not be called by
+    /// that `emit`; it first receives the next event.
+    pub fn subscribe(&self, c: C) -> SlabKey {
+        self.handlers.borrow_mut().insert(Some(c))
+    }
+    /// Removes a handler. Returns `false` if `key` was not subscribed, or its
+    /// removal is already pending.
+    ///
+    /// When called during an `emit`, the removal is deferred until the
+    /// outermost `emit` returns, so the handler still receives the current
+    /// event if it has not been called yet.
+    pub fn unsubscribe(&self, key: SlabKey) -> bool {
+        if !self.handlers.borrow().contains(key) || self.pending.borrow().contains(&key) {
+            return false;
+        }
+        if self.is_emitting() {
+            self.pending.borrow_mut().push(key);
+        } else {
+            self.handlers.borrow_mut().remove(key);
+        }
+        true
+    }
+    /// Removes every handler, deferred in the same way as `unsubscribe`.
+    pub fn clear(&self) {
+        if self.is_emitting() {
+            let keys = self.handlers.borrow().iter().map(|(k, _)| k).collect::<Vec<_>>();
+            self.pending.borrow_mut().extend(keys);
+        } else {
+            self.handlers.borrow_mut().clear();
+        }
+    }
+    /// Calls every handler subscribed at the start of the call with a clone of `args`.
+    ///
+    /// Handlers may subscribe, unsubscribe and emit re-entrantly. A nested `emit`
+    /// skips the handlers that are currently running. Deferred unsubscriptions
+    /// are applied once the outermost `emit` returns (or unwinds).
+    pub fn emit<Input>(&self, args: Input)
+    where
+        C: StableFnMut<Input>,
+        Input: Clone
+    {
+        self.emit_with(args, ())
+    }
+    /// Calls every handler with a borrow of `payload`, which is never cloned.
+    ///
+    /// The handlers must accept the borrow for every lifetime, as an
+    /// `EventHandler` or a `ContextClosure` does, so none of them can keep it
+    /// after its call. Otherwise this is the same as `emit((payload,))`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use namable_closures::{EventEmitter,EventHandler};
+    /// struct Frame { pixels: Vec<u8> }
+    /// let emitter:EventEmitter<EventHandler<usize,Frame>> = EventEmitter::new();
+    /// emitter.subscribe(EventHandler::new(|lit, f| *lit += f.pixels.iter().filter(|&&p| p > 0).count(), 0));
+    /// emitter.emit_ref(&Frame { pixels: vec![0, 3, 7] });
+    /// emitter.emit_ref(&Frame { pixels: vec![1; 4] });
+    /// let lit:Vec<usize> = emitter.into_iter().map(|(_, h)| h.into_inner()).collect();
+    /// assert_eq!(lit, [6]);
+    /// ```
+    ///
+    /// A handler typed for one lifetime of the borrow, which could keep it,
+    /// is rejected:
+    ///
+    /// ```compile_fail
+    /// # use namable_closures::closure;
+    /// # use namable_closures::{ClosureRefMut,EventEmitter};
+    /// fn keep<'k>(emitter: &EventEmitter<ClosureRefMut<Vec<&'k u32>,(&'k u32,),()>>) {
+    ///     emitter.emit_ref(&1);
+    /// }
+    /// ```
+    pub fn emit_ref<P>(&self, payload: &P)
+    where
+        C: for<'p> StableFnMut<(&'p P,)>,
+        P: ?Sized
+    {
+        self.emit((payload,))
+    }
+    /// Like `emit`, but passes each handler's output to `agg` and returns
+    /// the aggregate.
+    ///
+    /// Handlers after one for which `agg` breaks are not called. Handlers that
+    /// are running, as for `emit`, contribute nothing.
+    pub fn emit_with<Input, A>(&self, args: Input, mut agg: A) -> A::Output
+    where
+        C: StableFnMut<Input>,
+        Input: Clone,
+        A: Aggregate<C::Output>
+    {
+        self.for_each_handler(|c| c.stable_call_mut(args.clone()), |_, output| match output {
+            Some(output) => agg.add(output),
+            None => ControlFlow::Continue(()),
+        });
+        agg.finish()
+    }
+    /// Like `emit`, but reports what happened to each handler subscribed at
+    /// the start of the call.
+    ///
+    /// A handler that is running (when called from inside an `emit`) is
+    /// `Skipped`, as is one removed by another handler before its turn. With
+    /// the `std` feature, a handler that panics is reported as
+    /// `Failed(Error::Disconnected)` and the remaining handlers are still
+    /// called; without it, the panic unwinds out of `emit_report`. Either way
+    /// the handler stays subscribed.
+    pub fn emit_report<Input>(&self, args: Input) -> Vec<(SlabKey, CallOutcome<C::Output>)>
+    where
+        C: StableFnMut<Input>,
+        Input: Clone
+    {
+        let mut report = Vec::new();
+        self.for_each_handler(|c| call_reporting(c, args.clone()), |key, outcome| {
+            report.push((key, outcome.unwrap_or(CallOutcome::Skipped)));
+            ControlFlow::Continue(())
+        });
+        report
+    }
+    /// Iterates over the handlers subscribed when the iteration starts.
+    ///
+    /// Each handler is borrowed from the emitter for as long as its `Ref` is
+    /// held; subscribing or unsubscribing meanwhile panics. Handlers that are
+    /// running (when iterating from inside an `emit`) are skipped.
+    pub fn iter(&self) -> Iter<'_, C> {
+        let keys = self.handlers.borrow().iter().map(|(k, _)| k).collect::<Vec<_>>();
+        Iter { handlers: &self.handlers, keys: keys.into_iter() }
+    }
+    pub fn iter_mut(&mut self) -> IterMut<'_, C> {
+        IterMut { inner: self.handlers.get_mut().iter_mut() }
+    }
+    /// Unsubscribes the handlers for which `keep` returns `false`.
+    pub fn retain<P>(&mut self, mut keep: P)
+    where
+        P: FnMut(SlabKey, &mut C) -> bool
+    {
+        self.handlers.get_mut().retain(|k, c| c.as_mut().is_none_or(|c| keep(k, c)))
+    }
+    /// Iterates over the state of every handler, for example to reset them all.
+    pub fn states_mut(&mut self) -> impl Iterator<Item=&mut C::State>
+    where
+        C: ClosureStateMut
+    {
+        self.handlers.get_mut().iter_mut().filter_map(|(_, c)| c.as_mut()).map(C::state_mut)
+    }
+    // The loop behind every `emit`: runs `call` on each handler subscribed at
+    // the start and passes its key and result to `f`, with `None` for one that
+    // is running or gone, until `f` breaks.
+    fn for_each_handler<R>(&self, mut call: impl FnMut(&mut C) -> R, mut f: impl FnMut(SlabKey, Option<R>) -> ControlFlow<()>) {
+        let keys = self.handlers.borrow().iter().map(|(k, _)| k).collect::<Vec<_>>();
+        let _guard = EmitGuard::enter(self);
+        for key in keys {
+            if f(key, self.call_handler(key, &mut call)).is_break() {
+                break;
+            }
+        }
+    }
+    // Takes the handler out of its slot for `call`, or returns `None` if it is
+    // running or gone. The handler is put back even if `call` panics.
+    fn call_handler<R>(&self, key: SlabKey, call: impl FnOnce(&mut C) -> R) -> Option<R> {
+        let c = self.handlers.borrow_mut().get_mut(key)?.take()?;
+        let mut running = DropWith::new((self, key, c), Self::restore);
+        Some(call(&mut running.2))
+    }
+    // If the handlers are borrowed, as when unwinding out of an `iter`, the
+    // handler is dropped and its slot removed once the `emit` ends.
+    fn restore((emitter, key, c): (&Self, SlabKey, C)) {
+        match emitter.handlers.try_borrow_mut() {
+            Ok(mut handlers) => {
+                if let Some(slot) = handlers.get_mut(key) {
+                    *slot = Some(c);
+                }
+            }
+            Err(_) => emitter.pending.borrow_mut().push(key),
+        }
+    }
+    fn apply_pending(&self) {
+        let pending = ::core::mem::take(&mut *self.pending.borrow_mut());
+        let mut handlers = self.handlers.borrow_mut();
+        for key in pending {
+            handlers.remove(key);
+        }
+    }
+}
+impl<State, Input, Output> EventEmitter<WeakClosure<State, Input, Output>> {
+    /// Unsubscribes every handler whose state has been dropped and returns
+    /// how many there were.
+    ///
+    /// Removal is deferred in the same way as `unsubscribe` when called during
+    /// an `emit`. Handlers that are running are left alone.
+    pub fn sweep(&self) -> usize {
+        let dead = self.handlers.borrow().iter()
+            .filter(|(_, c)| c.as_ref().is_some_and(|c| !c.is_alive()))
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>();
+        for &key in &dead {
+            self.unsubscribe(key);
+        }
+        dead.len()
+    }
+}
+impl<C> fmt::Debug for EventEmitter<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventEmitter")
+            .field("len", &self.len())
+            .field("emitting", &self.is_emitting())
+            .finish()
+    }
+}
+
+impl<C> ClosureCollection for EventEmitter<C> {
+    type Closure = C;
+    type Iter<'a> = Iter<'a, C> where C: 'a;
+    type IterMut<'a> = IterMut<'a, C> where C: 'a;
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> Iter<'_, C> {
+        self.iter()
+    }
+    fn iter_mut(&mut self) -> IterMut<'_, C> {
+        self.iter_mut()
+    }
+    fn retain<P>(&mut self, mut keep: P)
+    where
+        P: FnMut(&mut C) -> bool
+    {
+        self.retain(|_, c| keep(c))
+    }
+}
+impl<C> IntoIterator for EventEmitter<C> {
+    type Item = (SlabKey, C);
+    type IntoIter = IntoIter<C>;
+    fn into_iter(self) -> IntoIter<C> {
+        IntoIter { inner: self.handlers.into_inner().into_iter() }
+    }
+}
+impl<'a, C> IntoIterator for &'a EventEmitter<C> {
+    type Item = (SlabKey, Ref<'a, C>);
+    type IntoIter = Iter<'a, C>;
+    fn into_iter(self) -> Iter<'a, C> {
+        self.iter()
+    }
+}
+impl<'a, C> IntoIterator for &'a mut EventEmitter<C> {
+    type Item = (SlabKey, &'a mut C);
+    type IntoIter = IterMut<'a, C>;
+    fn into_iter(self) -> IterMut<'a, C> {
+        self.iter_mut()
+    }
+}
+
+/// Iterator over the handlers of an `EventEmitter`, borrowing each in turn.
+pub struct Iter<'a, C: 'a> {
+    handlers: &'a RefCell<ClosureSlab<Option<C>>>,
+    keys: vec::IntoIter<SlabKey>,
+}
+impl<'a, C> Iterator for Iter<'a, C> {
+    type Item = (SlabKey, Ref<'a, C>);
+    fn next(&mut self) -> Option<Self::Item> {
+        for key in &mut self.keys {
+            let handler = Ref::filter_map(self.handlers.borrow(), |h| h.get(key).and_then(Option::as_ref));
+            if let Ok(c) = handler {
+                return Some((key, c));
+            }
+        }
+        None
+    }
+}
+
+/// Mutable iterator over the handlers of an `EventEmitter`.
+pub struct IterMut<'a, C: 'a> {
+    inner: closure_slab::IterMut<'a, Option<C>>,
+}
+impl<'a, C> Iterator for IterMut<'a, C> {
+    type Item = (SlabKey, &'a mut C);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.by_ref().find_map(|(k, c)| c.as_mut().map(|c| (k, c)))
+    }
+}
+
+/// Owning iterator over the handlers of an `EventEmitter`.
+pub struct IntoIter<C> {
+    inner: closure_slab::IntoIter<Option<C>>,
+}
+impl<C> Iterator for IntoIter<C> {
+    type Item = (SlabKey, C);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.by_ref().find_map(|(k, c)| c.map(|c| (k, c)))
+    }
+}
+
+// Calls `c`, catching a panic where `std` can.
+fn call_reporting<C, Input>(c: &mut C, args: Input) -> CallOutcome<C::Output>
+where
+    C: StableFnMut<Input>
+{
+    #[cfg(feature="std")]
+    {
+        match std::panic::catch_unwind(AssertUnwindSafe(|| c.stable_call_mut(args))) {
+            Ok(output) => CallOutcome::Completed(output),
+            Err(_) => CallOutcome::Failed(Error::Disconnected),
+        }
+    }
+    #[cfg(not(feature="std"))]
+    CallOutcome::Completed(c.stable_call_mut(args))
+}
+
+struct EmitGuard<'a, C: 'a> {
+    emitter: &'a EventEmitter<C>,
+}
+impl<'a, C> EmitGuard<'a, C> {
+    fn enter(emitter: &'a EventEmitter<C>) -> Self {
+        emitter.depth.set(emitter.depth.get() + 1);
+        EmitGuard { emitter }
+    }
+}
+impl<'a, C> Drop for EmitGuard<'a, C> {
+    fn drop(&mut self) {
+        let depth = self.emitter.depth.get() - 1;
+        self.emitter.depth.set(depth);
+        if depth == 0 {
+            self.emitter.apply_pending();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell,RefCell};
+    use std::panic::{self,AssertUnwindSafe};
+    use std::rc::Rc;
+    use crate::{ClosureRef,ClosureRefMut,CollectAll,EventEmitter,EventHandler,FirstSome,SlabKey,WeakClosure};
+    use crate::{StableFnMut};
+
+    struct Bus<'a> {
+        emitter: EventEmitter<ClosureRef<i32,(&'a Bus<'a>,i32),()>>,
+        log: RefCell<Vec<i32>>,
+        first: Cell<Option<SlabKey>>,
+    }
+
+    #[test]
+    fn test_emit() {
+        let emitter:EventEmitter<ClosureRefMut<i32,(i32,),i32>> = EventEmitter::new();
+        let a = emitter.subscribe(closure!(ref mut total=0 => move |i| {*total+=i;*total}));
+        emitter.subscribe(closure!(ref mut total=100 => move |i| {*total+=i;*total}));
+        emitter.emit((1,));
+        emitter.emit((2,));
+        assert_eq!(emitter.len(), 2);
+        assert!(emitter.unsubscribe(a));
+        assert!(!emitter.unsubscribe(a));
+        assert_eq!(emitter.len(), 1);
+        emitter.clear();
+        assert!(emitter.is_empty());
+    }
+    #[test]
+    fn test_subscribe_and_unsubscribe_during_emit() {
+        let bus = Bus {
+            emitter: EventEmitter::new(),
+            log: RefCell::new(Vec::new()),
+            first: Cell::new(None),
+        };
+        // the first handler subscribes a new handler and unsubscribes itself
+        let first = bus.emitter.subscribe(closure!(ref id=1 => move |bus,n| {
+            bus.log.borrow_mut().push(*id*100+n);
+            bus.emitter.subscribe(closure!(ref id=3 => move |bus,n| bus.log.borrow_mut().push(*id*100+n)));
+            bus.emitter.unsubscribe(bus.first.get().unwrap());
+        }));
+        bus.first.set(Some(first));
+        // the second handler unsubscribes the first one again; still called this time
+        bus.emitter.subscribe(closure!(ref id=2 => move |bus,n| {
+            bus.log.borrow_mut().push(*id*100+n);
+            assert!(!bus.emitter.unsubscribe(bus.first.get().unwrap()));
+        }));
+        bus.emitter.emit((&bus,1));
+        assert_eq!(*bus.log.borrow(), vec![101,201]);
+        assert!(!bus.emitter.is_emitting());
+        assert_eq!(bus.emitter.len(), 2);
+        bus.emitter.emit((&bus,2));
+        assert_eq!(*bus.log.borrow(), vec![101,201,202,302]);
+    }
+    #[test]
+    fn test_panicking_handler_stays_subscribed() {
+        let emitter:EventEmitter<ClosureRefMut<i32,(i32,),i32>> = EventEmitter::new();
+        emitter.subscribe(closure!(ref mut total=0 => move |i| {assert!(i >= 0);*total+=i;*total}));
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| emitter.emit((-1,)))).is_err());
+        assert!(!emitter.is_emitting());
+        assert_eq!(emitter.len(), 1);
+        assert_eq!(emitter.emit_with((2,), CollectAll::new()), [2]);
+    }
+    #[test]
+    fn test_nested_emit_skips_running_handler() {
+        let bus = Bus {
+            emitter: EventEmitter::new(),
+            log: RefCell::new(Vec::new()),
+            first: Cell::new(None),
+        };
+        bus.emitter.subscribe(closure!(ref id=1 => move |bus,n| {
+            bus.log.borrow_mut().push(*id*100+n);
+            if n == 0 { bus.emitter.emit((bus,n+1)); }
+        }));
+        bus.emitter.subscribe(closure!(ref id=2 => move |bus,n| bus.log.borrow_mut().push(*id*100+n)));
+        bus.emitter.emit((&bus,0));
+        assert_eq!(*bus.log.borrow(), vec![100,201,200]);
+    }
+    #[test]
+    fn test_emit_report() {
+        let bus = Bus {
+            emitter: EventEmitter::new(),
+            log: RefCell::new(Vec::new()),
+            first: Cell::new(None),
+        };
+        bus.emitter.subscribe(closure!(ref id=1 => move |bus,n| {
+            bus.log.borrow_mut().push(*id*100+n);
+            if n == 0 {
+                let report = bus.emitter.emit_report((bus,n+1));
+                let skipped:Vec<_> = report.iter().map(|(_, o)| o.is_skipped()).collect();
+                assert_eq!(skipped, vec![true,false]);
+            }
+        }));
+        let second = bus.emitter.subscribe(closure!(ref id=2 => move |bus,n| bus.log.borrow_mut().push(*id*100+n)));
+        let report = bus.emitter.emit_report((&bus,0));
+        assert_eq!(report.len(), 2);
+        assert!(report.iter().all(|(_, o)| o.is_completed()));
+        assert_eq!(report[1].0, second);
+        assert_eq!(*bus.log.borrow(), vec![100,201,200]);
+    }
+    #[test]
+    #[cfg(feature="std")]
+    fn test_emit_report_catches_panics() {
+        use crate::{CallOutcome,Error};
+        let emitter:EventEmitter<ClosureRefMut<i32,(i32,),i32>> = EventEmitter::new();
+        emitter.subscribe(closure!(ref mut total=0 => move |i| {assert!(i >= 0);*total+=i;*total}));
+        emitter.subscribe(closure!(ref mut total=100 => move |i| {*total+=i;*total}));
+        let report:Vec<_> = emitter.emit_report((-1,)).into_iter().map(|(_, o)| o).collect();
+        assert_eq!(report, [CallOutcome::Failed(Error::Disconnected), CallOutcome::Completed(99)]);
+        let report:Vec<_> = emitter.emit_report((2,)).into_iter().map(|(_, o)| o).collect();
+        assert_eq!(report, [CallOutcome::Completed(2), CallOutcome::Completed(101)]);
+    }
+    #[test]
+    fn test_states_mut() {
+        let mut emitter:EventEmitter<ClosureRefMut<i32,(i32,),i32>> = EventEmitter::new();
+        let a = emitter.subscribe(closure!(ref mut total=0 => move |i| {*total+=i;*total}));
+        emitter.subscribe(closure!(ref mut total=100 => move |i| {*total+=i;*total}));
+        emitter.emit((5,));
+        let mut totals:Vec<_> = emitter.states_mut().map(|t| *t).collect();
+        totals.sort();
+        assert_eq!(totals, vec![5,105]);
+        emitter.states_mut().for_each(|t| *t = 0);
+        emitter.unsubscribe(a);
+        emitter.emit((1,));
+        assert_eq!(emitter.states_mut().map(|t| *t).collect::<Vec<_>>(), vec![1]);
+    }
+    #[test]
+    fn test_iteration_and_retain() {
+        let mut emitter:EventEmitter<ClosureRefMut<i32,(i32,),i32>> = EventEmitter::new();
+        for n in 0..4 {
+            emitter.subscribe(closure!(ref mut total=n => move |i| {*total+=i;*total}));
+        }
+        let seen:Vec<_> = emitter.iter().map(|(_, c)| *c.state()).collect();
+        assert_eq!(seen, vec![0,1,2,3]);
+        for (_, c) in &mut emitter {
+            c.stable_call_mut((1,));
+        }
+        emitter.retain(|_, c| c.stable_call_mut((0,)) % 2 == 0);
+        assert_eq!(emitter.len(), 2);
+        let totals:Vec<_> = emitter.into_iter().map(|(_, mut c)| c.stable_call_mut((0,))).collect();
+        assert_eq!(totals, vec![2,4]);
+    }
+    #[test]
+    fn test_sweep_dead_handlers() {
+        let emitter:EventEmitter<WeakClosure<RefCell<Vec<i32>>,(i32,),()>> = EventEmitter::new();
+        let kept = Rc::new(RefCell::new(Vec::new()));
+        let dropped = Rc::new(RefCell::new(Vec::new()));
+        emitter.subscribe(WeakClosure::new(|log, (i,)| log.borrow_mut().push(i), &kept));
+        emitter.subscribe(WeakClosure::new(|log, (i,)| log.borrow_mut().push(i), &dropped));
+        emitter.emit((1,));
+        assert_eq!(emitter.sweep(), 0);
+        drop(dropped);
+        emitter.emit((2,));
+        assert_eq!(emitter.len(), 2);
+        assert_eq!(emitter.sweep(), 1);
+        assert_eq!(emitter.len(), 1);
+        assert_eq!(*kept.borrow(), vec![1,2]);
+    }
+    #[test]
+    fn test_emit_ref_does_not_clone() {
+        // not Clone, so only a borrow can be passed to every handler
+        struct Big([u64; 64]);
+        let emitter:EventEmitter<EventHandler<u64,Big>> = EventEmitter::new();
+        emitter.subscribe(EventHandler::new(|sum, b| *sum += b.0.iter().sum::<u64>(), 0));
+        emitter.subscribe(EventHandler::new(|max, b| *max = (*max).max(b.0[63]), 0));
+        emitter.emit_ref(&Big([1; 64]));
+        emitter.emit_ref(&Big([2; 64]));
+        let states:Vec<u64> = emitter.into_iter().map(|(_, h)| h.into_inner()).collect();
+        assert_eq!(states, [192, 2]);
+    }
+    #[test]
+    fn test_emit_with_aggregate() {
+        let emitter:EventEmitter<ClosureRefMut<u32,(u32,),Option<u32>>> = EventEmitter::new();
+        for limit in [5, 10, 20] {
+            emitter.subscribe(closure!(ref mut calls=limit => move |n| (n <= *calls).then_some(n * 100 / *calls)));
+        }
+        assert_eq!(emitter.emit_with((8,), FirstSome::new()), Some(80));
+        assert_eq!(emitter.emit_with((8,), CollectAll::new()), [None, Some(80), Some(40)]);
+        assert_eq!(emitter.emit_with((50,), FirstSome::new()), None);
+    }
+}
@@ -0,0 +1,147 @@
+//! A flight recorder for closure calls.
+//!
+//! `History` remembers the inputs of the last `N` calls in a fixed-size ring
+//! buffer, without allocating, so that firmware can report what a callback
+//! was doing after something went wrong.
+
+use core::fmt;
+
+use crate::stable_fn::{StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// Calls `F` and records a clone of each input, keeping the last `N`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRefMut,History,StableFnMut};
+/// let set_duty:ClosureRefMut<u8,(u8,),()> = closure!(ref mut duty=0 => move |d| *duty = d);
+/// let mut set_duty:History<_,(u8,),2> = History::new(set_duty);
+/// for d in [10, 20, 30] {
+///     set_duty.stable_call_mut((d,));
+/// }
+/// assert_eq!(set_duty.recent().collect::<Vec<_>>(), [&(20,), &(30,)]);
+/// assert_eq!(set_duty.last(), Some(&(30,)));
+/// ```
+pub struct History<F, Input, const N: usize> {
+    f: F,
+    // slot `next` holds the oldest input once the buffer is full
+    inputs: [Option<Input>; N],
+    next: usize,
+}
+impl<F, Input, const N: usize> History<F, Input, N> {
+    pub fn new(f: F) -> Self {
+        Self { f, inputs: [const { None }; N], next: 0 }
+    }
+    /// The recorded inputs, oldest first.
+    pub fn recent(&self) -> impl DoubleEndedIterator<Item=&Input> {
+        let (older, newer) = self.inputs.split_at(self.next);
+        newer.iter().chain(older).filter_map(Option::as_ref)
+    }
+    /// The input of the latest call.
+    pub fn last(&self) -> Option<&Input> {
+        self.recent().next_back()
+    }
+    /// Forgets the recorded inputs.
+    pub fn clear(&mut self) {
+        self.inputs = [const { None }; N];
+        self.next = 0;
+    }
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+    fn record(&mut self, args: &Input)
+    where
+        Input: Clone
+    {
+        if N > 0 {
+            self.inputs[self.next] = Some(args.clone());
+            self.next = (self.next + 1) % N;
+        }
+    }
+}
+impl<F, Input, const N: usize> Clone for History<F, Input, N>
+where
+    F: Clone,
+    Input: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f.clone(), inputs: self.inputs.clone(), next: self.next }
+    }
+}
+impl<F, Input, const N: usize> fmt::Debug for History<F, Input, N>
+where
+    Input: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        struct Recent<'a, F, Input, const N: usize>(&'a History<F, Input, N>);
+        impl<F, Input, const N: usize> fmt::Debug for Recent<'_, F, Input, N>
+        where
+            Input: fmt::Debug
+        {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_list().entries(self.0.recent()).finish()
+            }
+        }
+        f.debug_struct("History").field("recent", &Recent(self)).finish_non_exhaustive()
+    }
+}
+impl<F, Input, const N: usize> ClosureState for History<F, Input, N>
+where
+    F: ClosureState
+{
+    type State = F::State;
+    fn state(&self) -> &F::State {
+        self.f.state()
+    }
+}
+impl<F, Input, const N: usize> ClosureStateMut for History<F, Input, N>
+where
+    F: ClosureStateMut
+{
+    fn state_mut(&mut self) -> &mut F::State {
+        self.f.state_mut()
+    }
+}
+impl<F, Input, const N: usize> StableFnOnce<Input> for History<F, Input, N>
+where
+    F: StableFnOnce<Input>
+{
+    type Output = F::Output;
+    fn stable_call_once(self, args: Input) -> F::Output {
+        self.f.stable_call_once(args)
+    }
+}
+impl<F, Input, const N: usize> StableFnMut<Input> for History<F, Input, N>
+where
+    F: StableFnMut<Input>,
+    Input: Clone
+{
+    fn stable_call_mut(&mut self, args: Input) -> F::Output {
+        self.record(&args);
+        self.f.stable_call_mut(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureRef,History,StableFnMut};
+
+    #[test]
+    fn test_ring_wraps_and_clears() {
+        let add:ClosureRef<i32,(i32,),i32> = closure!(ref n=1 => move |i| i+*n);
+        let mut add:History<_,(i32,),3> = History::new(add);
+        assert_eq!(add.last(), None);
+        for i in 0..5 {
+            assert_eq!(add.stable_call_mut((i,)), i+1);
+        }
+        assert_eq!(add.recent().map(|i| i.0).collect::<Vec<_>>(), vec![2,3,4]);
+        assert_eq!(format!("{:?}", add), "History { recent: [(2,), (3,), (4,)], .. }");
+        add.clear();
+        assert_eq!(add.recent().count(), 0);
+        let mut none:History<_,(i32,),0> = History::new(add.into_inner());
+        assert_eq!(none.stable_call_mut((1,)), 2);
+        assert_eq!(none.last(), None);
+    }
+}
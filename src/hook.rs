@@ -0,0 +1,136 @@
+//! Closures selected by name, for configuration.
+//!
+//! A `Hook<Input, Output>` is a registered closure with its state type erased,
+//! so configuration structs can hold "which behaviour to use" as a value:
+//! `on_error = "retry"` deserializes (with the `serde` feature) by looking the
+//! name up in the `registry`. Hooks are declared with
+//! `static_closure!(hook ...)`.
+//!
+//! Only the name is serialized. The state of a hook is part of its `static`
+//! and never leaves the binary, so persisted configuration holds no closure
+//! state (and no secrets kept in it) to protect.
+//!
+//! Only available with the `registry` feature.
+
+use core::fmt;
+
+use crate::error::Error;
+use crate::registry;
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// A named, registered closure taking `Input` and returning `Output`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::static_closure;
+/// # use namable_closures::{ClosureRef,Hook,StableFn};
+/// static_closure!(hook static RETRY: ClosureRef<u32,(u32,),bool> = ref max=3 => move |attempt| attempt < *max);
+/// static_closure!(hook static GIVE_UP: ClosureRef<(),(u32,),bool> = ref _s=() => move |_| false);
+///
+/// let on_error:Hook<(u32,),bool> = Hook::resolve("RETRY").unwrap();
+/// assert!(on_error.stable_call((1,)));
+/// assert_eq!(on_error.name(), "RETRY");
+/// assert!(Hook::<(u32,),bool>::resolve("retry").is_err());
+/// ```
+pub struct Hook<Input, Output> {
+    name: &'static str,
+    f: fn(Input) -> Output,
+}
+impl<Input, Output> Hook<Input, Output> {
+    pub const fn new(name: &'static str, f: fn(Input) -> Output) -> Self {
+        Self { name, f }
+    }
+    /// The name the hook was registered under.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+impl<Input: 'static, Output: 'static> Hook<Input, Output> {
+    /// Finds the registered hook with this name and signature.
+    pub fn resolve(name: &str) -> Result<Self, Error> {
+        registry::lookup_hook(name).ok_or(Error::UnknownHook)
+    }
+}
+impl<Input, Output> Copy for Hook<Input, Output> {}
+impl<Input, Output> Clone for Hook<Input, Output> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<Input, Output> PartialEq for Hook<Input, Output> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+impl<Input, Output> Eq for Hook<Input, Output> {}
+impl<Input, Output> fmt::Debug for Hook<Input, Output> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Hook").field(&self.name).finish()
+    }
+}
+impl<Input, Output> StableFnOnce<Input> for Hook<Input, Output> {
+    type Output = Output;
+    fn stable_call_once(self, i: Input) -> Output {
+        (self.f)(i)
+    }
+}
+impl<Input, Output> StableFnMut<Input> for Hook<Input, Output> {
+    fn stable_call_mut(&mut self, i: Input) -> Output {
+        (self.f)(i)
+    }
+}
+impl<Input, Output> StableFn<Input> for Hook<Input, Output> {
+    fn stable_call(&self, i: Input) -> Output {
+        (self.f)(i)
+    }
+}
+
+#[cfg(feature="serde")]
+impl<Input, Output> serde::Serialize for Hook<Input, Output> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(self.name)
+    }
+}
+#[cfg(feature="serde")]
+impl<'de, Input: 'static, Output: 'static> serde::Deserialize<'de> for Hook<Input, Output> {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        use serde::de::{DeserializeSeed,Error as _};
+        use crate::serde_visitor::StrVisitor;
+        StrVisitor::new(|_:(), name| Self::resolve(name), ())
+            .deserialize(d)?
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureRef,Error,Hook,StableFn};
+
+    static_closure!(hook static SCALE: ClosureRef<u8,(i32,),i32> = ref k=2 => move |i| i*i32::from(*k));
+    static_closure!(hook static NEGATE: crate::ClosureRef<(),(i32,),i32> = ref _s=() => move |i| -i);
+    static_closure!(hook static LEN: ClosureRef<(),(&'static str,),usize> = ref _s=() => move |s| s.len());
+
+    #[test]
+    fn test_resolve_by_name_and_signature() {
+        let scale = Hook::<(i32,),i32>::resolve("SCALE").unwrap();
+        assert_eq!(scale.stable_call((4,)), 8);
+        assert_eq!(Hook::<(i32,),i32>::resolve("NEGATE").map(|h| h.stable_call((4,))), Ok(-4));
+        assert_eq!(Hook::<(i32,),i32>::resolve("LEN"), Err(Error::UnknownHook));
+        assert_eq!(Hook::<(&str,),usize>::resolve("LEN").map(|h| h.stable_call(("abc",))), Ok(3));
+        assert_eq!(SCALE.stable_call((1,)), 2);
+    }
+
+    #[cfg(feature="serde")]
+    #[test]
+    fn test_deserialize_from_name() {
+        use serde::Deserialize;
+        use serde::de::IntoDeserializer;
+        use serde::de::value::Error as DeError;
+        let d = IntoDeserializer::<DeError>::into_deserializer("NEGATE");
+        let hook = Hook::<(i32,),i32>::deserialize(d).unwrap();
+        assert_eq!(hook.stable_call((7,)), -7);
+        let d = IntoDeserializer::<DeError>::into_deserializer("MISSING");
+        assert!(Hook::<(i32,),i32>::deserialize(d).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Building closures from `(function, state)` pairs.
+//!
+//! Each closure struct converts (`From`) from the tuple of its function pointer
+//! and its state. For the owning structs `IntoClosure` also picks the struct
+//! from the shape of the pair:
+//!
+//! | pair                                       | closure         |
+//! |--------------------------------------------|-----------------|
+//! | `(fn(&State, Input) -> Output, State)`     | `ClosureRef`    |
+//! | `(fn(&mut State, Input) -> Output, State)` | `ClosureRefMut` |
+//! | `(fn(State, Input) -> Output, State)`      | `ClosureOnce`   |
+//!
+//! A borrowing pair such as `(fn(&State, Input) -> Output, &State)` also fits
+//! `ClosureOnce` with a reference as its state, so `Closure` and `ClosureMut`
+//! are only built with `From`/`Into` where the target type is known.
+//!
+//! The first element must already be a function pointer: a function item or a
+//! non-capturing closure has to be cast (`f as fn(&i32, (i32,)) -> i32`) or
+//! bound to a typed variable first, as trait selection does not coerce it.
+
+use crate::closures::{Closure,ClosureMut,ClosureOnce,ClosureRef,ClosureRefMut};
+
+/// Conversion into one of the closure structs.
+///
+/// Implemented by the closure structs themselves and by `(function, state)`
+/// pairs, so APIs can take `impl IntoClosure<Closure=...>` and accept either.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::{closure,ClosureRef,IntoClosure,StableFn};
+/// fn install(h: impl IntoClosure<Closure=ClosureRef<i32,(i32,),i32>>) -> i32 {
+///     h.into_closure().stable_call((1,))
+/// }
+/// fn add(n:&i32, (i,):(i32,)) -> i32 { i+*n }
+/// let add:fn(&i32,(i32,)) -> i32 = add;
+/// assert_eq!(install((add, 10)), 11);
+/// assert_eq!(install(closure!(ref n=20 => move |i| i+*n)), 21);
+/// ```
+pub trait IntoClosure {
+    type Closure;
+    fn into_closure(self) -> Self::Closure;
+}
+
+macro_rules! from_pair {
+    ($([$($lt:lifetime)?] $closure:ident, $f:ty, $t:ty;)*) => {$(
+        impl<$($lt,)? State, Input, Output> From<($f, $t)> for $closure<$($lt,)? State, Input, Output> {
+            fn from((f, t): ($f, $t)) -> Self {
+                Self::new(f, t)
+            }
+        }
+        impl<$($lt,)? State, Input, Output> IntoClosure for $closure<$($lt,)? State, Input, Output> {
+            type Closure = Self;
+            fn into_closure(self) -> Self {
+                self
+            }
+        }
+    )*};
+}
+from_pair! {
+    ['a] Closure, fn(&State, Input) -> Output, &'a State;
+    [] ClosureRef, fn(&State, Input) -> Output, State;
+    ['a] ClosureMut, fn(&mut State, Input) -> Output, &'a mut State;
+    [] ClosureRefMut, fn(&mut State, Input) -> Output, State;
+    [] ClosureOnce, fn(State, Input) -> Output, State;
+}
+
+macro_rules! into_closure {
+    ($($closure:ident, $f:ty;)*) => {$(
+        impl<State, Input, Output> IntoClosure for ($f, State) {
+            type Closure = $closure<State, Input, Output>;
+            fn into_closure(self) -> Self::Closure {
+                self.into()
+            }
+        }
+    )*};
+}
+into_closure! {
+    ClosureRef, fn(&State, Input) -> Output;
+    ClosureRefMut, fn(&mut State, Input) -> Output;
+    ClosureOnce, fn(State, Input) -> Output;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureMut,ClosureOnce,ClosureRefMut,IntoClosure};
+    use crate::{StableFnMut,StableFnOnce};
+
+    fn bump(n:&mut i32, (by,):(i32,)) -> i32 {
+        *n += by;
+        *n
+    }
+
+    #[test]
+    fn test_pairs_pick_the_closure_kind() {
+        let bump:fn(&mut i32,(i32,)) -> i32 = bump;
+        let mut owned:ClosureRefMut<i32,(i32,),i32> = (bump, 1).into_closure();
+        assert_eq!(owned.stable_call_mut((1,)), 2);
+        let mut n = 10;
+        {
+            let mut borrowed:ClosureMut<i32,(i32,),i32> = (bump, &mut n).into();
+            assert_eq!(borrowed.stable_call_mut((1,)), 11);
+        }
+        assert_eq!(n, 11);
+        let take:fn(String,(usize,)) -> String = |s, (n,)| s[..n].to_string();
+        let once = ClosureOnce::from((take, "hello".to_string()));
+        assert_eq!(once.stable_call_once((2,)), "he");
+    }
+}
@@ -0,0 +1,218 @@
+//! Namable closures as `std::io` sinks and sources.
+//!
+//! `io::Write::write` takes a buffer of any lifetime, which a
+//! `ClosureMut<State, (&[u8],), io::Result<usize>>` cannot accept because its
+//! type fixes that lifetime. `IoWriter` and `IoReader` are the equivalent
+//! namable closures whose functions are higher-ranked over the buffer, and
+//! they implement `Write` and `Read`.
+//!
+//! Only available with the `std` feature.
+
+use std::fmt;
+use std::io::{self,Read,Write};
+
+use crate::stable_fn::{StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// An `io::Write` implemented by a state plus a `fn(&mut State, &[u8])`.
+///
+/// `flush` does nothing unless a flush function is given with `with_flush`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::IoWriter;
+/// # use std::io::Write;
+/// // a sink that keeps only the first 8 bytes and swallows the rest
+/// let mut head = IoWriter::new(|buf:&mut Vec<u8>, data| {
+///     let room = 8usize.saturating_sub(buf.len());
+///     buf.extend_from_slice(&data[..room.min(data.len())]);
+///     Ok(data.len())
+/// }, Vec::new());
+/// write!(head, "{}-{}", "hello", "world").unwrap();
+/// assert_eq!(head.into_inner(), b"hello-wo");
+/// ```
+pub struct IoWriter<State> {
+    f: fn(&mut State, &[u8]) -> io::Result<usize>,
+    flush: fn(&mut State) -> io::Result<()>,
+    t: State,
+}
+impl<State> Copy for IoWriter<State>
+where
+    State: Copy
+{}
+impl<State> Clone for IoWriter<State>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, flush: self.flush, t: self.t.clone() }
+    }
+}
+impl<State> IoWriter<State> {
+    pub fn new(f: fn(&mut State, &[u8]) -> io::Result<usize>, t: State) -> Self {
+        Self { f, flush: |_| Ok(()), t }
+    }
+    /// Uses `flush` for `Write::flush`.
+    pub fn with_flush(self, flush: fn(&mut State) -> io::Result<()>) -> Self {
+        Self { flush, ..self }
+    }
+    pub fn into_inner(self) -> State {
+        self.t
+    }
+}
+impl<State> Write for IoWriter<State> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (self.f)(&mut self.t, buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        (self.flush)(&mut self.t)
+    }
+}
+impl<State> fmt::Debug for IoWriter<State>
+where
+    State: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IoWriter").field("state", &self.t).finish_non_exhaustive()
+    }
+}
+impl<State> ClosureState for IoWriter<State> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.t
+    }
+}
+impl<State> ClosureStateMut for IoWriter<State> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+}
+impl<'a, State> StableFnOnce<(&'a [u8],)> for IoWriter<State> {
+    type Output = io::Result<usize>;
+    fn stable_call_once(mut self, (buf,): (&'a [u8],)) -> io::Result<usize> {
+        self.write(buf)
+    }
+}
+impl<'a, State> StableFnMut<(&'a [u8],)> for IoWriter<State> {
+    fn stable_call_mut(&mut self, (buf,): (&'a [u8],)) -> io::Result<usize> {
+        self.write(buf)
+    }
+}
+
+/// An `io::Read` implemented by a state plus a `fn(&mut State, &mut [u8])`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::IoReader;
+/// # use std::io::Read;
+/// // an endless source of a repeating pattern
+/// let mut pattern = IoReader::new(|next:&mut u8, buf| {
+///     for b in buf.iter_mut() {
+///         *b = b'a' + *next;
+///         *next = (*next + 1) % 3;
+///     }
+///     Ok(buf.len())
+/// }, 0);
+/// let mut s = String::new();
+/// pattern.by_ref().take(7).read_to_string(&mut s).unwrap();
+/// assert_eq!(s, "abcabca");
+/// ```
+pub struct IoReader<State> {
+    f: fn(&mut State, &mut [u8]) -> io::Result<usize>,
+    t: State,
+}
+impl<State> Copy for IoReader<State>
+where
+    State: Copy
+{}
+impl<State> Clone for IoReader<State>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<State> IoReader<State> {
+    pub fn new(f: fn(&mut State, &mut [u8]) -> io::Result<usize>, t: State) -> Self {
+        Self { f, t }
+    }
+    pub fn into_inner(self) -> State {
+        self.t
+    }
+}
+impl<State> Read for IoReader<State> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (self.f)(&mut self.t, buf)
+    }
+}
+impl<State> fmt::Debug for IoReader<State>
+where
+    State: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IoReader").field("state", &self.t).finish_non_exhaustive()
+    }
+}
+impl<State> ClosureState for IoReader<State> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.t
+    }
+}
+impl<State> ClosureStateMut for IoReader<State> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+}
+impl<'a, State> StableFnOnce<(&'a mut [u8],)> for IoReader<State> {
+    type Output = io::Result<usize>;
+    fn stable_call_once(mut self, (buf,): (&'a mut [u8],)) -> io::Result<usize> {
+        self.read(buf)
+    }
+}
+impl<'a, State> StableFnMut<(&'a mut [u8],)> for IoReader<State> {
+    fn stable_call_mut(&mut self, (buf,): (&'a mut [u8],)) -> io::Result<usize> {
+        self.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self,BufRead,BufReader,Write};
+    use crate::{ClosureState,IoReader,IoWriter};
+
+    #[test]
+    fn test_writer_flush_and_errors() {
+        let mut lines = IoWriter::new(|s:&mut (Vec<u8>,Vec<String>), data| {
+            if data.contains(&0) {
+                return Err(io::ErrorKind::InvalidData.into());
+            }
+            s.0.extend_from_slice(data);
+            Ok(data.len())
+        }, (Vec::new(), Vec::new())).with_flush(|s| {
+            s.1.push(String::from_utf8(std::mem::take(&mut s.0)).unwrap());
+            Ok(())
+        });
+        writeln!(lines, "one").unwrap();
+        lines.flush().unwrap();
+        assert_eq!(lines.write(b"\0").unwrap_err().kind(), io::ErrorKind::InvalidData);
+        write!(lines, "two").unwrap();
+        lines.flush().unwrap();
+        assert_eq!(lines.state().1, vec!["one\n", "two"]);
+    }
+    #[test]
+    fn test_reader_with_buffering() {
+        let text = IoReader::new(|rest:&mut &[u8], buf| {
+            // at most 2 bytes per read, to exercise the buffering
+            let n = rest.len().min(buf.len()).min(2);
+            buf[..n].copy_from_slice(&rest[..n]);
+            *rest = &rest[n..];
+            Ok(n)
+        }, &b"first\nsecond\n"[..]);
+        let lines:Vec<String> = BufReader::new(text).lines().map(Result::unwrap).collect();
+        assert_eq!(lines, vec!["first", "second"]);
+    }
+}
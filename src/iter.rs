@@ -0,0 +1,153 @@
+//! Iterators driven by namable closures.
+//!
+//! `core::iter::from_fn` and `core::iter::successors` return types that name
+//! their closure, so a struct holding such an iterator cannot spell its own
+//! type. `FromFn` and `Successors` are the same iterators over a namable
+//! closure instead.
+
+use core::fmt;
+use core::iter::FusedIterator;
+
+use crate::stable_fn::StableFnMut;
+
+/// An iterator calling `f` until it returns `None`; see `from_fn`.
+#[derive(Clone, Copy)]
+pub struct FromFn<F> {
+    f: F,
+}
+/// Creates an iterator that yields the outputs of `f` until it returns `None`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::ClosureRefMut;
+/// # use namable_closures::iter::{from_fn,FromFn};
+/// let countdown:FromFn<ClosureRefMut<u32,(),Option<u32>>> = from_fn(closure!(ref mut n=3 => move || {
+///     *n = n.checked_sub(1)?;
+///     Some(*n)
+/// }));
+/// assert_eq!(countdown.collect::<Vec<_>>(), vec![2,1,0]);
+/// ```
+pub fn from_fn<F, T>(f: F) -> FromFn<F>
+where
+    F: StableFnMut<(), Output=Option<T>>
+{
+    FromFn { f }
+}
+impl<F> FromFn<F> {
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
+impl<F, T> Iterator for FromFn<F>
+where
+    F: StableFnMut<(), Output=Option<T>>
+{
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.f.stable_call_mut(())
+    }
+}
+impl<F> fmt::Debug for FromFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FromFn").finish_non_exhaustive()
+    }
+}
+
+/// An iterator where each item is computed from the previous one; see
+/// `successors`.
+#[derive(Clone, Copy)]
+pub struct Successors<F, T> {
+    f: F,
+    next: Option<T>,
+}
+/// Creates an iterator starting at `first` and calling `f` with a clone of
+/// each item to get the next, until `f` returns `None`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::ClosureRef;
+/// # use namable_closures::iter::{successors,Successors};
+/// struct Backoff {
+///     delays: Successors<ClosureRef<u32,(u32,),Option<u32>>, u32>,
+/// }
+/// let backoff = Backoff {
+///     delays: successors(Some(10), closure!(ref limit=1000 => move |d| Some(d * 4).filter(|d| d <= limit))),
+/// };
+/// assert_eq!(backoff.delays.collect::<Vec<_>>(), vec![10,40,160,640]);
+/// ```
+pub fn successors<F, T>(first: Option<T>, f: F) -> Successors<F, T>
+where
+    F: StableFnMut<(T,), Output=Option<T>>,
+    T: Clone
+{
+    Successors { f, next: first }
+}
+impl<F, T> Successors<F, T> {
+    /// The item the next call to `next` will return.
+    pub fn peek(&self) -> Option<&T> {
+        self.next.as_ref()
+    }
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
+impl<F, T> Iterator for Successors<F, T>
+where
+    F: StableFnMut<(T,), Output=Option<T>>,
+    T: Clone
+{
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        let item = self.next.take()?;
+        self.next = self.f.stable_call_mut((item.clone(),));
+        Some(item)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.next.is_some() { (1, None) } else { (0, Some(0)) }
+    }
+}
+impl<F, T> FusedIterator for Successors<F, T>
+where
+    F: StableFnMut<(T,), Output=Option<T>>,
+    T: Clone
+{}
+impl<F, T> fmt::Debug for Successors<F, T>
+where
+    T: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Successors").field("next", &self.next).finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::iter::{from_fn,successors};
+    use crate::{ClosureRef,ClosureRefMut};
+
+    #[test]
+    fn test_successors_is_fused() {
+        let halve:ClosureRef<(),(u32,),Option<u32>> = ClosureRef::new(|_, (n,)| if n > 1 { Some(n / 2) } else { None }, ());
+        let mut it = successors(Some(20), halve);
+        assert_eq!(it.peek(), Some(&20));
+        assert_eq!(it.by_ref().collect::<Vec<_>>(), vec![20,10,5,2,1]);
+        assert_eq!(it.size_hint(), (0, Some(0)));
+        assert_eq!(it.next(), None);
+        assert_eq!(successors(None, halve).count(), 0);
+    }
+    #[test]
+    fn test_from_fn_keeps_state() {
+        let mut fib:ClosureRefMut<(u64,u64),(),Option<u64>> = closure!(ref mut s=(0,1) => move || {
+            let next = s.0.checked_add(s.1)?;
+            *s = (s.1, next);
+            Some(s.0)
+        });
+        assert_eq!(from_fn(&mut fib).take(5).collect::<Vec<_>>(), vec![1,1,2,3,5]);
+        assert_eq!(from_fn(&mut fib).next(), Some(8));
+        assert_eq!(from_fn(fib).take_while(|&n| n < 100).collect::<Vec<_>>(), vec![13,21,34,55,89]);
+    }
+}
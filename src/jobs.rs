@@ -0,0 +1,174 @@
+//! A bounded worker pool for namable jobs.
+//!
+//! All jobs of a kind, say `ClosureOnce<Request, (), Response>`, share one
+//! namable type, so the pool's queue holds them by value: submitting a job does
+//! not box it. Each submission returns a `JobHandle`, a future (and a blocking
+//! wait) for that job's output.
+//!
+//! Only available with the `std` feature.
+
+use std::fmt;
+use std::panic::{self,AssertUnwindSafe};
+use std::sync::mpsc::{self,Receiver,SyncSender,TrySendError};
+use std::sync::{Arc,Mutex,PoisonError};
+use std::thread::{self,JoinHandle};
+
+use crate::remote::{oneshot,RemoteCall,Reply};
+use crate::stable_fn::StableFnOnce;
+
+/// The pending output of a job; see `RemoteCall`.
+pub type JobHandle<Output> = RemoteCall<Output>;
+
+type Queue<J, Output> = Arc<Mutex<Receiver<(J, Reply<Output>)>>>;
+
+/// A fixed set of worker threads running jobs of type `J`.
+///
+/// The queue is bounded: `submit` blocks while it is full, and `try_submit`
+/// hands the job back instead. A job that panics resolves its handle to
+/// `Err(Error::Disconnected)` and does not take its worker down. Dropping the
+/// pool runs the jobs still queued, then joins the workers.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureOnce,JobPool};
+/// let pool:JobPool<ClosureOnce<u64,(),u64>> = JobPool::new(2);
+/// let handles:Vec<_> = (1..=4)
+///     .map(|n| pool.submit(closure!(n=n => move || (1..=n).product())))
+///     .collect();
+/// let results:Vec<_> = handles.into_iter().map(|h| h.wait().unwrap()).collect();
+/// assert_eq!(results, vec![1,2,6,24]);
+/// ```
+pub struct JobPool<J>
+where
+    J: StableFnOnce<()>
+{
+    tx: Option<SyncSender<(J, Reply<J::Output>)>>,
+    workers: Vec<JoinHandle<()>>,
+}
+impl<J> JobPool<J>
+where
+    J: StableFnOnce<()> + Send + 'static,
+    J::Output: Send + 'static
+{
+    /// A pool of `threads` workers whose queue holds as many jobs as there
+    /// are workers.
+    pub fn new(threads: usize) -> Self {
+        Self::with_capacity(threads, threads)
+    }
+    /// A pool of `threads` workers whose queue holds up to `capacity` jobs
+    /// waiting for a worker.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is zero.
+    pub fn with_capacity(threads: usize, capacity: usize) -> Self {
+        assert!(threads > 0, "a JobPool needs at least one thread");
+        let (tx, rx) = mpsc::sync_channel(capacity);
+        let queue:Queue<J, J::Output> = Arc::new(Mutex::new(rx));
+        let workers = (0..threads)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || work(&queue))
+            })
+            .collect();
+        Self { tx: Some(tx), workers }
+    }
+    /// The number of worker threads.
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+    /// Queues `job`, waiting for room in the queue if it is full.
+    pub fn submit(&self, job: J) -> JobHandle<J::Output> {
+        let (reply, handle) = oneshot();
+        if let Some(tx) = &self.tx {
+            // the workers only stop once the pool is dropped
+            let _ = tx.send((job, reply));
+        }
+        handle
+    }
+    /// Queues `job` if there is room, or gives it back.
+    pub fn try_submit(&self, job: J) -> Result<JobHandle<J::Output>, J> {
+        let (reply, handle) = oneshot();
+        match self.tx.as_ref().map(|tx| tx.try_send((job, reply))) {
+            Some(Ok(())) => Ok(handle),
+            Some(Err(TrySendError::Full((job, _)))) | Some(Err(TrySendError::Disconnected((job, _)))) => Err(job),
+            None => unreachable!("the sender lives as long as the pool"),
+        }
+    }
+}
+fn work<J>(queue: &Queue<J, J::Output>)
+where
+    J: StableFnOnce<()>
+{
+    loop {
+        let next = queue.lock().unwrap_or_else(PoisonError::into_inner).recv();
+        let (job, reply) = match next {
+            Ok(next) => next,
+            Err(_) => return,
+        };
+        // on panic the reply is dropped, which closes the handle
+        if let Ok(output) = panic::catch_unwind(AssertUnwindSafe(|| job.stable_call_once(()))) {
+            reply.send(output);
+        }
+    }
+}
+impl<J> Drop for JobPool<J>
+where
+    J: StableFnOnce<()>
+{
+    fn drop(&mut self) {
+        self.tx = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+impl<J> fmt::Debug for JobPool<J>
+where
+    J: StableFnOnce<()>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("JobPool").field("threads", &self.workers.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use crate::{ClosureOnce,Error,JobPool};
+
+    #[test]
+    fn test_panicking_job_keeps_worker() {
+        let pool:JobPool<ClosureOnce<i32,(),i32>> = JobPool::new(1);
+        let bad = pool.submit(closure!(n=0 => move || 10 / n));
+        let good = pool.submit(closure!(n=2 => move || 10 / n));
+        assert_eq!(bad.wait(), Err(Error::Disconnected));
+        assert_eq!(good.wait(), Ok(5));
+        assert_eq!(pool.threads(), 1);
+    }
+    #[test]
+    fn test_try_submit_when_full() {
+        let (release, gate) = mpsc::channel::<()>();
+        let pool:JobPool<ClosureOnce<Option<mpsc::Receiver<()>>,(),bool>> = JobPool::with_capacity(1, 1);
+        // occupies the worker until released
+        let blocked = pool.submit(closure!(gate=Some(gate) => move || gate.is_some_and(|g| g.recv().is_ok())));
+        let mut queued = None;
+        let mut rejected = None;
+        for _ in 0..1000 {
+            match pool.try_submit(closure!(g=None => move || g.is_none())) {
+                Ok(h) if queued.is_none() => queued = Some(h),
+                Ok(_) => {}
+                Err(job) => { rejected = Some(job); break; }
+            }
+        }
+        assert!(rejected.is_some());
+        release.send(()).unwrap();
+        assert_eq!(blocked.wait(), Ok(true));
+        // nothing was queued if the worker had not picked up the first job yet
+        if let Some(h) = queued {
+            assert_eq!(h.wait(), Ok(true));
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Combining two futures into one with a nameable type.
+//!
+//! `async` blocks and `futures::join!` produce anonymous types, which cannot be
+//! written in a `static` or a struct field. `Join2` and `Select2` combine two
+//! futures whose types can be named, such as `RemoteCall`, into a future whose
+//! type can be named as well.
+//!
+//! The crate has no unsafe code, so the futures must be `Unpin`.
+
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context,Poll};
+
+/// One of two values, the output of a `Select2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+impl<L, R> Either<L, R> {
+    pub fn is_left(&self) -> bool {
+        matches!(self, Either::Left(_))
+    }
+    pub fn is_right(&self) -> bool {
+        matches!(self, Either::Right(_))
+    }
+    pub fn left(self) -> Option<L> {
+        match self {
+            Either::Left(l) => Some(l),
+            Either::Right(_) => None,
+        }
+    }
+    pub fn right(self) -> Option<R> {
+        match self {
+            Either::Left(_) => None,
+            Either::Right(r) => Some(r),
+        }
+    }
+}
+
+enum Slot<F: Future> {
+    Pending(F),
+    Done(F::Output),
+    Taken,
+}
+impl<F> Slot<F>
+where
+    F: Future + Unpin
+{
+    // Polls the future if it is still pending; true once its output is ready.
+    fn poll_ready(&mut self, cx: &mut Context) -> bool {
+        if let Slot::Pending(f) = self {
+            match Pin::new(f).poll(cx) {
+                Poll::Ready(output) => *self = Slot::Done(output),
+                Poll::Pending => return false,
+            }
+        }
+        true
+    }
+    fn take(&mut self) -> F::Output {
+        match core::mem::replace(self, Slot::Taken) {
+            Slot::Done(output) => output,
+            _ => panic!("Join2 polled after completion"),
+        }
+    }
+}
+
+/// Waits for both `A` and `B`, resolving to both outputs.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")] {
+/// # use namable_closures::closure;
+/// # use namable_closures::{block_on,ClosureOnce,JobHandle,JobPool,Join2};
+/// # use std::future::{ready,Ready};
+/// type Job = ClosureOnce<u32,(),u32>;
+/// // a type for a field or a static, where `async` blocks cannot go
+/// type Both = Join2<JobHandle<u32>,Ready<u32>>;
+/// let pool:JobPool<Job> = JobPool::new(2);
+/// let both:Both = Join2::new(pool.submit(closure!(n=20 => move || n + 1)), ready(5));
+/// let (job, cached) = block_on(both);
+/// assert_eq!((job.unwrap(), cached), (21, 5));
+/// # }
+/// ```
+pub struct Join2<A: Future, B: Future> {
+    a: Slot<A>,
+    b: Slot<B>,
+}
+// the outputs are never pinned, so they need not be `Unpin`
+impl<A, B> Unpin for Join2<A, B>
+where
+    A: Future + Unpin,
+    B: Future + Unpin
+{}
+impl<A, B> Join2<A, B>
+where
+    A: Future,
+    B: Future
+{
+    pub const fn new(a: A, b: B) -> Self {
+        Self { a: Slot::Pending(a), b: Slot::Pending(b) }
+    }
+}
+impl<A, B> Future for Join2<A, B>
+where
+    A: Future + Unpin,
+    B: Future + Unpin
+{
+    type Output = (A::Output, B::Output);
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        // poll both, so that each registers its waker
+        let a = this.a.poll_ready(cx);
+        let b = this.b.poll_ready(cx);
+        if a && b {
+            Poll::Ready((this.a.take(), this.b.take()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+impl<A, B> fmt::Debug for Join2<A, B>
+where
+    A: Future,
+    B: Future
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Join2")
+            .field("a_done", &!matches!(self.a, Slot::Pending(_)))
+            .field("b_done", &!matches!(self.b, Slot::Pending(_)))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Waits for whichever of `A` and `B` finishes first, resolving to its output.
+///
+/// `A` is polled first, so it wins when both are ready. The other future is
+/// dropped with the `Select2`; pass `&mut b` instead of `b` to keep it.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")] {
+/// # use namable_closures::{block_on,Either,Select2};
+/// # use std::future::{pending,ready,Pending,Ready};
+/// let mut slow:Pending<u8> = pending();
+/// let first:Select2<Ready<&str>,&mut Pending<u8>> = Select2::new(ready("fast"), &mut slow);
+/// assert_eq!(block_on(first), Either::Left("fast"));
+/// # }
+/// ```
+pub struct Select2<A, B> {
+    a: A,
+    b: B,
+}
+impl<A, B> Select2<A, B> {
+    pub const fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+    pub fn into_inner(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+impl<A, B> Future for Select2<A, B>
+where
+    A: Future + Unpin,
+    B: Future + Unpin
+{
+    type Output = Either<A::Output, B::Output>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Poll::Ready(a) = Pin::new(&mut self.a).poll(cx) {
+            return Poll::Ready(Either::Left(a));
+        }
+        Pin::new(&mut self.b).poll(cx).map(Either::Right)
+    }
+}
+impl<A, B> fmt::Debug for Select2<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Select2").finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, feature="std"))]
+mod tests {
+    use std::future::{pending,ready};
+    use std::thread;
+    use crate::{block_on,ClosureRefMut,Either,Join2,RemoteOwner,Select2};
+
+    #[test]
+    fn test_join_and_select() {
+        let add:ClosureRefMut<i32,(i32,),i32> = closure!(ref mut total=0 => move |i| {*total+=i;*total});
+        let (mut owner, remote) = RemoteOwner::new(add);
+        let both = Join2::new(remote.call((2,)), remote.call((3,)));
+        let waiter = thread::spawn(move || block_on(both));
+        let mut served = 0;
+        while served < 2 {
+            served += owner.serve();
+            thread::yield_now();
+        }
+        assert_eq!(waiter.join().unwrap(), (Ok(2), Ok(5)));
+
+        let mut never = pending::<u8>();
+        assert_eq!(block_on(Select2::new(&mut never, ready('b'))), Either::Right('b'));
+        assert_eq!(block_on(Select2::new(ready(1), ready('b'))), Either::Left(1));
+        assert_eq!(Either::<u8, char>::Right('b').right(), Some('b'));
+    }
+}
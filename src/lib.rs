@@ -8,8 +8,11 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+#![cfg_attr(not(any(feature="std", test)), no_std)]
 #![cfg_attr(feature="nightly",feature(fn_traits))]
 #![cfg_attr(feature="nightly",feature(unboxed_closures))]
+#![cfg_attr(feature="nightly",feature(tuple_trait))]
+#![deny(unsafe_code)]
 
 #![doc="
 This crate supports nameable closures, without requiring any language
@@ -121,7 +124,7 @@ variable and the variables in the closure definition header.
 Examples:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureRef;
 # use namable_closures::StableFn;
 # struct Point{x:i32,y:i32}
@@ -198,7 +201,7 @@ let read_data:ClosureOnce<MyStream,(&mut [u8],usize),Result<(),io::Error>>
 The same examples that uses the unstable features:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureRef;
 # struct Point{x:i32,y:i32}
 # impl Point{ fn new(x:i32,y:i32) -> Point {Point{x:x,y:y}} }
@@ -274,7 +277,7 @@ The macro to create closures.
 Examples:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureRef;
 # use namable_closures::StableFn;
 # struct Point{x:i32,y:i32}
@@ -345,12 +348,65 @@ let send_data:ClosureOnce<MyStream,(&[u8],),Result<usize,io::Error>>
 let read_data:ClosureOnce<MyStream,(&mut [u8],usize),Result<(),io::Error>>
     = closure!(mut stream=MyStream::new() => move |buf,len| stream.read_exact_ex(buf, len));
 ```
+
+The type can also be given inline, as `closure!(as Kind<State,(Inputs..),Output>: ...)`.
+The number of arguments of the body is then checked against the `Input` tuple,
+and a mismatch is reported as such rather than as a type error:
+
+```rust
+# use namable_closures::closure;
+# use namable_closures::{ClosureRef,StableFn};
+let add_ten = closure!(as ClosureRef<i32,(i32,),i32>: ref state=10 => move |i| i+*state);
+assert_eq!(add_ten.stable_call((1,)),11);
+```
+
+```compile_fail
+# use namable_closures::closure;
+# use namable_closures::ClosureRef;
+// error: closure! body takes more arguments than the Input of `ClosureRef < i32, (i32,), i32 >`
+let add = closure!(as ClosureRef<i32,(i32,),i32>: ref state=10 => move |a,b| a+b+*state);
+```
+
+Arguments borrowed for any lifetime are written with `for<'r>` before the state,
+giving each argument its type; the body may then return a borrow of them:
+
+```rust
+# use namable_closures::closure;
+# use namable_closures::{ClosureRef,StableFn};
+let first_word:ClosureRef<(),(&str,),&str>
+    = closure!(for<'r> ref _s=() => move |line: &'r str| -> &'r str { line.split(' ').next().unwrap_or(line) });
+assert_eq!(first_word.stable_call((\"hello world\",)),\"hello\");
+```
+
+The body can be given as several blocks, each preceded by a `#[cfg(...)]`.
+The block that is left in is the body, and the closure has the same type
+whichever it is:
+
+```rust
+# use namable_closures::closure;
+# use namable_closures::{ClosureRef,StableFn};
+let separator:ClosureRef<(),(),char> = closure!(ref _s=() => move ||
+    #[cfg(windows)] { '\\\\' }
+    #[cfg(not(windows))] { '/' }
+);
+assert_eq!(separator.stable_call(()),std::path::MAIN_SEPARATOR);
+```
+
+Exactly one of the blocks must be left in:
+
+```compile_fail
+# use namable_closures::closure;
+# use namable_closures::ClosureRef;
+let separator:ClosureRef<(),(),char> = closure!(ref _s=() => move ||
+    #[cfg(any())] { '/' }
+);
+```
 "]
 #[cfg_attr(feature="nightly", doc="
 The same examples that uses unstable features:
 
 ```rust
-# #[macro_use] extern crate namable_closures;
+# use namable_closures::closure;
 # use namable_closures::ClosureRef;
 # struct Point{x:i32,y:i32}
 # impl Point{ fn new(x:i32,y:i32) -> Point {Point{x:x,y:y}} }
@@ -423,64 +479,184 @@ let read_data:ClosureOnce<MyStream,(&mut [u8],usize),Result<(),io::Error>>
 ")]
 #[macro_export]
 macro_rules! closure {
+    (as $($kind:ident)::+<$state_ty:ty, ($($input:ty),* $(,)?), $output:ty>: $($rest:tt)+) => {
+        $crate::closure!(@arity {$($kind)::+<$state_ty, ($($input,)*), $output>} [$($input;)*] [$($rest)+] $($rest)+)
+    };
+    // skip the state up to `=>` and an optional `move`
+    (@arity $target:tt $inputs:tt $rest:tt => move $($tail:tt)*) => {
+        $crate::closure!(@params $target $inputs $rest $($tail)*)
+    };
+    (@arity $target:tt $inputs:tt $rest:tt => $($tail:tt)*) => {
+        $crate::closure!(@params $target $inputs $rest $($tail)*)
+    };
+    (@arity $target:tt $inputs:tt $rest:tt $skip:tt $($tail:tt)*) => {
+        $crate::closure!(@arity $target $inputs $rest $($tail)*)
+    };
+    (@params $target:tt $inputs:tt $rest:tt || $($body:tt)*) => {
+        $crate::closure!(@done $target $inputs $rest)
+    };
+    (@params $target:tt $inputs:tt $rest:tt | $($tail:tt)*) => {
+        $crate::closure!(@count $target $inputs $rest $($tail)*)
+    };
+    // pop one input type for each argument, at its `,` or closing `|`
+    (@count {$target:ty} [] $rest:tt , $($tail:tt)*) => {
+        compile_error!(concat!("closure! body takes more arguments than the Input of `", stringify!($target), "`"))
+    };
+    (@count {$target:ty} [] $rest:tt | $($tail:tt)*) => {
+        compile_error!(concat!("closure! body takes more arguments than the Input of `", stringify!($target), "`"))
+    };
+    (@count $target:tt [$input:ty; $($inputs:ty;)*] $rest:tt , $($tail:tt)*) => {
+        $crate::closure!(@count $target [$($inputs;)*] $rest $($tail)*)
+    };
+    (@count $target:tt [$input:ty; $($inputs:ty;)*] $rest:tt | $($body:tt)*) => {
+        $crate::closure!(@done $target [$($inputs;)*] $rest)
+    };
+    (@count $target:tt $inputs:tt $rest:tt $skip:tt $($tail:tt)*) => {
+        $crate::closure!(@count $target $inputs $rest $($tail)*)
+    };
+    (@done {$target:ty} [] [$($rest:tt)+]) => {{
+        let closure: $target = $crate::closure!($($rest)+);
+        closure
+    }};
+    (@done {$target:ty} [$($inputs:ty;)+] $rest:tt) => {
+        compile_error!(concat!("closure! body takes fewer arguments than the Input of `", stringify!($target), "`"))
+    };
+    (for<$($lt:lifetime),+> $state:ident=$state_val:expr => move |$($arg:tt: $ty:ty),*| $($body:tt)+) => {
+        $crate::closure!(@for [$($lt),+] ClosureOnce (_) ($state) $state_val, ($($ty),*) ($($arg),*) $($body)+)
+    };
+    (for<$($lt:lifetime),+> mut $state:ident=$state_val:expr => move |$($arg:tt: $ty:ty),*| $($body:tt)+) => {
+        $crate::closure!(@for [$($lt),+] ClosureOnce (_) (mut $state) $state_val, ($($ty),*) ($($arg),*) $($body)+)
+    };
+    (for<$($lt:lifetime),+> mut $state:ident=$state_val:expr => |$($arg:tt: $ty:ty),*| $($body:tt)+) => {
+        $crate::closure!(@for [$($lt),+] ClosureMut (&mut _) ($state) $state_val, ($($ty),*) ($($arg),*) $($body)+)
+    };
+    (for<$($lt:lifetime),+> ref mut $state:ident=$state_val:expr => move |$($arg:tt: $ty:ty),*| $($body:tt)+) => {
+        $crate::closure!(@for [$($lt),+] ClosureRefMut (&mut _) ($state) $state_val, ($($ty),*) ($($arg),*) $($body)+)
+    };
+    (for<$($lt:lifetime),+> $state:ident=$state_val:expr => |$($arg:tt: $ty:ty),*| $($body:tt)+) => {
+        $crate::closure!(@for [$($lt),+] Closure (&_) ($state) $state_val, ($($ty),*) ($($arg),*) $($body)+)
+    };
+    (for<$($lt:lifetime),+> ref $state:ident=$state_val:expr => move |$($arg:tt: $ty:ty),*| $($body:tt)+) => {
+        $crate::closure!(@for [$($lt),+] ClosureRef (&_) ($state) $state_val, ($($ty),*) ($($arg),*) $($body)+)
+    };
+    // the state is checked before the body, which is then bound to a
+    // higher-ranked fn pointer
+    (@for [$($lt:lifetime),+] $kind:ident ($($state_ty:tt)+) ($($state:tt)+) $state_val:expr, ($($ty:ty),*) ($($arg:tt),*) -> $output:ty $body:block) => {{
+        let new = |f: for<$($lt),+> fn($($state_ty)+, ($($ty,)*)) -> $output, state| $crate::$kind::new(f, state);
+        new(|$($state)+, ($($arg,)*)| $body, $state_val)
+    }};
+    (@for [$($lt:lifetime),+] $kind:ident ($($state_ty:tt)+) ($($state:tt)+) $state_val:expr, ($($ty:ty),*) ($($arg:tt),*) $body:expr) => {{
+        let new = |f: for<$($lt),+> fn($($state_ty)+, ($($ty,)*)) -> _, state| $crate::$kind::new(f, state);
+        new(|$($state)+, ($($arg,)*)| $body, $state_val)
+    }};
+    // `#[cfg]`-selected bodies become one block whose tail is the block left
+    // in; if none is, the body is a compile error
+    ($state:ident=$state_val:expr => move || $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!($state=$state_val => move || { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    ($state:ident=$state_val:expr => move |$($arg:pat_param),*| $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!($state=$state_val => move |$($arg),*| { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    ($state:ident=$state_val:expr => || $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!($state=$state_val => || { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    ($state:ident=$state_val:expr => |$($arg:pat_param),*| $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!($state=$state_val => |$($arg),*| { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (mut $state:ident=$state_val:expr => move || $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(mut $state=$state_val => move || { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (mut $state:ident=$state_val:expr => move |$($arg:pat_param),*| $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(mut $state=$state_val => move |$($arg),*| { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (mut $state:ident=$state_val:expr => || $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(mut $state=$state_val => || { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (mut $state:ident=$state_val:expr => |$($arg:pat_param),*| $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(mut $state=$state_val => |$($arg),*| { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (ref $state:ident=$state_val:expr => move || $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(ref $state=$state_val => move || { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (ref $state:ident=$state_val:expr => move |$($arg:pat_param),*| $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(ref $state=$state_val => move |$($arg),*| { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (ref $state:ident=$state_val:expr => || $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(ref $state=$state_val => || { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (ref $state:ident=$state_val:expr => |$($arg:pat_param),*| $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(ref $state=$state_val => |$($arg),*| { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (ref mut $state:ident=$state_val:expr => move || $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(ref mut $state=$state_val => move || { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (ref mut $state:ident=$state_val:expr => move |$($arg:pat_param),*| $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(ref mut $state=$state_val => move |$($arg),*| { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (ref mut $state:ident=$state_val:expr => || $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(ref mut $state=$state_val => || { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
+    (ref mut $state:ident=$state_val:expr => |$($arg:pat_param),*| $(#[cfg($c:meta)] $block:block)+) => {
+        $crate::closure!(ref mut $state=$state_val => |$($arg),*| { $(#[cfg($c)] $block)+ #[cfg(not(any($($c),+)))] { compile_error!("no `#[cfg]` branch of the closure! body is selected") } })
+    };
     ($state:ident=$state_val:expr => move || $body:expr) => {
-        ClosureOnce::new(|$state,()| $body, $state_val)
+        $crate::ClosureOnce::new(|$state,()| $body, $state_val)
     };
-    ($state:ident=$state_val:expr => move |$arg:pat| $body:expr) => {
-        ClosureOnce::new(|$state,($arg,)| $body, $state_val)
+    ($state:ident=$state_val:expr => move |$arg:pat_param| $body:expr) => {
+        $crate::ClosureOnce::new(|$state,($arg,)| $body, $state_val)
     };
-    ($state:ident=$state_val:expr => move |$arg1:pat,$($arg2:pat),+| $body:expr) => {
-        ClosureOnce::new(|$state,($arg1,$($arg2),*)| $body, $state_val)
+    ($state:ident=$state_val:expr => move |$arg1:pat_param,$($arg2:pat_param),+| $body:expr) => {
+        $crate::ClosureOnce::new(|$state,($arg1,$($arg2),*)| $body, $state_val)
     };
     (mut $state:ident=$state_val:expr => move || $body:expr) => {
-        ClosureOnce::new(|mut $state,()| $body, $state_val)
+        $crate::ClosureOnce::new(|mut $state,()| $body, $state_val)
     };
-    (mut $state:ident=$state_val:expr => move |$arg:pat| $body:expr) => {
-        ClosureOnce::new(|mut $state,($arg,)| $body, $state_val)
+    (mut $state:ident=$state_val:expr => move |$arg:pat_param| $body:expr) => {
+        $crate::ClosureOnce::new(|mut $state,($arg,)| $body, $state_val)
     };
-    (mut $state:ident=$state_val:expr => move |$arg1:pat,$($arg2:pat),+| $body:expr) => {
-        ClosureOnce::new(|mut $state,($arg1,$($arg2),*)| $body, $state_val)
+    (mut $state:ident=$state_val:expr => move |$arg1:pat_param,$($arg2:pat_param),+| $body:expr) => {
+        $crate::ClosureOnce::new(|mut $state,($arg1,$($arg2),*)| $body, $state_val)
     };
     (mut $state:ident=$state_val:expr => || $body:expr) => {
-        ClosureMut::new(|$state,()| $body, $state_val)
+        $crate::ClosureMut::new(|$state,()| $body, $state_val)
     };
-    (mut $state:ident=$state_val:expr => |$arg:pat| $body:expr) => {
-        ClosureMut::new(|$state,($arg,)| $body, $state_val)
+    (mut $state:ident=$state_val:expr => |$arg:pat_param| $body:expr) => {
+        $crate::ClosureMut::new(|$state,($arg,)| $body, $state_val)
     };
-    (mut $state:ident=$state_val:expr => |$arg1:pat,$($arg2:pat),+| $body:expr) => {
-        ClosureMut::new(|$state,($arg1,$($arg2),*)| $body, $state_val)
+    (mut $state:ident=$state_val:expr => |$arg1:pat_param,$($arg2:pat_param),+| $body:expr) => {
+        $crate::ClosureMut::new(|$state,($arg1,$($arg2),*)| $body, $state_val)
     };
     (ref mut $state:ident=$state_val:expr => move || $body:expr) => {
-        ClosureRefMut::new(|$state,()| $body, $state_val)
+        $crate::ClosureRefMut::new(|$state,()| $body, $state_val)
     };
-    (ref mut $state:ident=$state_val:expr => move |$arg:pat| $body:expr) => {
-        ClosureRefMut::new(|$state,($arg,)| $body, $state_val)
+    (ref mut $state:ident=$state_val:expr => move |$arg:pat_param| $body:expr) => {
+        $crate::ClosureRefMut::new(|$state,($arg,)| $body, $state_val)
     };
-    (ref mut $state:ident=$state_val:expr => move |$arg1:pat,$($arg2:pat),+| $body:expr) => {
-        ClosureRefMut::new(|$state,($arg1,$($arg2),*)| $body, $state_val)
+    (ref mut $state:ident=$state_val:expr => move |$arg1:pat_param,$($arg2:pat_param),+| $body:expr) => {
+        $crate::ClosureRefMut::new(|$state,($arg1,$($arg2),*)| $body, $state_val)
     };
     ($state:ident=$state_val:expr => || $body:expr) => {
-        Closure::new(|$state,()| $body, $state_val)
+        $crate::Closure::new(|$state,()| $body, $state_val)
     };
-    ($state:ident=$state_val:expr => |$arg:pat| $body:expr) => {
-        Closure::new(|$state,($arg,)| $body, $state_val)
+    ($state:ident=$state_val:expr => |$arg:pat_param| $body:expr) => {
+        $crate::Closure::new(|$state,($arg,)| $body, $state_val)
     };
-    ($state:ident=$state_val:expr => |$arg1:pat,$($arg2:pat),+| $body:expr) => {
-        Closure::new(|$state,($arg1,$($arg2),*)| $body, $state_val)
+    ($state:ident=$state_val:expr => |$arg1:pat_param,$($arg2:pat_param),+| $body:expr) => {
+        $crate::Closure::new(|$state,($arg1,$($arg2),*)| $body, $state_val)
     };
     (ref $state:ident=$state_val:expr => move || $body:expr) => {
-        ClosureRef::new(|$state,()| $body, $state_val)
+        $crate::ClosureRef::new(|$state,()| $body, $state_val)
     };
-    (ref $state:ident=$state_val:expr => move |$arg:pat| $body:expr) => {
-        ClosureRef::new(|$state,($arg,)| $body, $state_val)
+    (ref $state:ident=$state_val:expr => move |$arg:pat_param| $body:expr) => {
+        $crate::ClosureRef::new(|$state,($arg,)| $body, $state_val)
     };
-    (ref $state:ident=$state_val:expr => move |$arg1:pat,$($arg2:pat),+| $body:expr) => {
-        ClosureRef::new(|$state,($arg1,$($arg2),*)| $body, $state_val)
+    (ref $state:ident=$state_val:expr => move |$arg1:pat_param,$($arg2:pat_param),+| $body:expr) => {
+        $crate::ClosureRef::new(|$state,($arg1,$($arg2),*)| $body, $state_val)
     };
-    (ref $state:ident=$state_val:expr => |$($arg:pat),*| $body:expr) => {
+    (ref $state:ident=$state_val:expr => |$($arg:pat_param),*| $body:expr) => {
         compile_error!("Use of ref keyword require move keyword for the closure body")
     };
-    (ref mut $state:ident=$state_val:expr => |$($arg:pat),*| $body:expr) => {
+    (ref mut $state:ident=$state_val:expr => |$($arg:pat_param),*| $body:expr) => {
         compile_error!("Use of ref keyword require move keyword for the closure body")
     };
 }
@@ -488,118 +664,485 @@ macro_rules! closure {
 #[macro_export]
 macro_rules! closure_rec {
     ($me:ident.state=$state_val:expr => || $body:expr) => {
-        ClosureOnceRec::new(|$me,()| $body, $state_val)
+        $crate::ClosureOnceRec::new(|$me,()| $body, $state_val)
     };
-    ($me:ident.state=$state_val:expr => |$arg:pat| $body:expr) => {
-        ClosureOnceRec::new(|$me,($arg,)| $body, $state_val)
+    ($me:ident.state=$state_val:expr => |$arg:pat_param| $body:expr) => {
+        $crate::ClosureOnceRec::new(|$me,($arg,)| $body, $state_val)
     };
-    ($me:ident.state=$state_val:expr => |$arg1:pat,$($arg2:pat),+| $body:expr) => {
-        ClosureOnceRec::new(|$me,($arg1,$($arg2),*)| $body, $state_val)
+    ($me:ident.state=$state_val:expr => |$arg1:pat_param,$($arg2:pat_param),+| $body:expr) => {
+        $crate::ClosureOnceRec::new(|$me,($arg1,$($arg2),*)| $body, $state_val)
     };
     (mut $me:ident.state=$state_val:expr => || $body:expr) => {
-        ClosureOnceRec::new(|mut $me,()| $body, $state_val)
+        $crate::ClosureOnceRec::new(|mut $me,()| $body, $state_val)
     };
-    (mut $me:ident.state=$state_val:expr => |$arg:pat| $body:expr) => {
-        ClosureOnceRec::new(|mut $me,($arg,)| $body, $state_val)
+    (mut $me:ident.state=$state_val:expr => |$arg:pat_param| $body:expr) => {
+        $crate::ClosureOnceRec::new(|mut $me,($arg,)| $body, $state_val)
     };
-    (mut $me:ident.state=$state_val:expr => |$arg1:pat,$($arg2:pat),+| $body:expr) => {
-        ClosureOnceRec::new(|mut $me,($arg1,$($arg2),*)| $body, $state_val)
+    (mut $me:ident.state=$state_val:expr => |$arg1:pat_param,$($arg2:pat_param),+| $body:expr) => {
+        $crate::ClosureOnceRec::new(|mut $me,($arg1,$($arg2),*)| $body, $state_val)
     };
     (mut $me:ident.state=$state_val:expr => mut || $body:expr) => {
-        ClosureMutRec::new(|$me,()| $body, $state_val)
+        $crate::ClosureMutRec::new(|$me,()| $body, $state_val)
     };
-    (mut $me:ident.state=$state_val:expr => mut |$arg:pat| $body:expr) => {
-        ClosureMutRec::new(|$me,($arg,)| $body, $state_val)
+    (mut $me:ident.state=$state_val:expr => mut |$arg:pat_param| $body:expr) => {
+        $crate::ClosureMutRec::new(|$me,($arg,)| $body, $state_val)
     };
-    (mut $me:ident.state=$state_val:expr => mut |$arg1:pat,$($arg2:pat),+| $body:expr) => {
-        ClosureMutRec::new(|$me,($arg1,$($arg2),*)| $body, $state_val)
+    (mut $me:ident.state=$state_val:expr => mut |$arg1:pat_param,$($arg2:pat_param),+| $body:expr) => {
+        $crate::ClosureMutRec::new(|$me,($arg1,$($arg2),*)| $body, $state_val)
     };
     ($me:ident.state=$state_val:expr => mut || $body:expr) => {
-        ClosureRecMut::new(|$me,()| $body, $state_val)
+        $crate::ClosureRecMut::new(|$me,()| $body, $state_val)
     };
-    ($me:ident.state=$state_val:expr => mut |$arg:pat| $body:expr) => {
-        ClosureRecMut::new(|$me,($arg,)| $body, $state_val)
+    ($me:ident.state=$state_val:expr => mut |$arg:pat_param| $body:expr) => {
+        $crate::ClosureRecMut::new(|$me,($arg,)| $body, $state_val)
     };
-    ($me:ident.state=$state_val:expr => mut |$arg1:pat,$($arg2:pat),+| $body:expr) => {
-        ClosureRecMut::new(|$me,($arg1,$($arg2),*)| $body, $state_val)
+    ($me:ident.state=$state_val:expr => mut |$arg1:pat_param,$($arg2:pat_param),+| $body:expr) => {
+        $crate::ClosureRecMut::new(|$me,($arg1,$($arg2),*)| $body, $state_val)
     };
     ($me:ident.state=$state_val:expr => ref || $body:expr) => {
-        ClosureRec::new(|$me,()| $body, $state_val)
+        $crate::ClosureRec::new(|$me,()| $body, $state_val)
     };
-    ($me:ident.state=$state_val:expr => ref |$arg:pat| $body:expr) => {
-        ClosureRec::new(|$me,($arg,)| $body, $state_val)
+    ($me:ident.state=$state_val:expr => ref |$arg:pat_param| $body:expr) => {
+        $crate::ClosureRec::new(|$me,($arg,)| $body, $state_val)
     };
-    ($me:ident.state=$state_val:expr => ref |$arg1:pat,$($arg2:pat),+| $body:expr) => {
-        ClosureRec::new(|$me,($arg1,$($arg2),*)| $body, $state_val)
+    ($me:ident.state=$state_val:expr => ref |$arg1:pat_param,$($arg2:pat_param),+| $body:expr) => {
+        $crate::ClosureRec::new(|$me,($arg1,$($arg2),*)| $body, $state_val)
     };
 }
 
 #[macro_export]
 macro_rules! call {
     (ref $c:ident ()) => {
-        $c.ident.stable_call(())
+        { use $crate::StableFn as _; $c.stable_call(()) }
     };
     (ref $c:ident ($arg:expr)) => {
-        $c.stable_call(($arg,))
+        { use $crate::StableFn as _; $c.stable_call(($arg,)) }
     };
     (ref $c:ident ($arg1:expr,$($arg2:expr),+)) => {
-        $c.stable_call(($arg1,$($arg2),*))
+        { use $crate::StableFn as _; $c.stable_call(($arg1,$($arg2),*)) }
     };
     (mut $c:ident ()) => {
-        $c.stable_call_mut(())
+        { use $crate::StableFnMut as _; $c.stable_call_mut(()) }
     };
     (mut $c:ident ($arg:expr)) => {
-        $c.stable_call_mut(($arg,))
+        { use $crate::StableFnMut as _; $c.stable_call_mut(($arg,)) }
     };
     (mut $c:ident ($arg1:expr,$($arg2:expr),+)) => {
-        $c.stable_call_mut(($arg1,$($arg2),*))
+        { use $crate::StableFnMut as _; $c.stable_call_mut(($arg1,$($arg2),*)) }
     };
     ($c:ident ()) => {
-        $c.stable_call_once(())
+        { use $crate::StableFnOnce as _; $c.stable_call_once(()) }
     };
     ($c:ident ($arg:expr)) => {
-        $c.stable_call_once(($arg,))
+        { use $crate::StableFnOnce as _; $c.stable_call_once(($arg,)) }
     };
     ($c:ident ($arg1:expr,$($arg2:expr),+)) => {
-        $c.stable_call_once(($arg1,$($arg2),*))
+        { use $crate::StableFnOnce as _; $c.stable_call_once(($arg1,$($arg2),*)) }
     };
 }
 
 #[macro_export]
 macro_rules! regulate {
     (|| ref $c:ident) => {
-        || $c.stable_call(())
+        || { use $crate::StableFn as _; $c.stable_call(()) }
     };
     (|$arg:ident| ref $c:ident) => {
-        |$arg| $c.stable_call(($arg,))
+        |$arg| { use $crate::StableFn as _; $c.stable_call(($arg,)) }
     };
     (|$arg1:ident,$($arg2:ident),+| ref $c:ident) => {
-        |$arg1,$($arg2),*| $c.stable_call(($arg1,$($arg2),*))
+        |$arg1,$($arg2),*| { use $crate::StableFn as _; $c.stable_call(($arg1,$($arg2),*)) }
     };
     (|| mut $c:ident) => {
-        || $c.stable_call_mut(())
+        || { use $crate::StableFnMut as _; $c.stable_call_mut(()) }
     };
     (|$arg:ident| mut $c:ident) => {
-        |$arg| $c.stable_call_mut(($arg,))
+        |$arg| { use $crate::StableFnMut as _; $c.stable_call_mut(($arg,)) }
     };
     (|$arg1:ident,$($arg2:ident),+| mut $c:ident) => {
-        |$arg1,$($arg2),*| $c:stable_call_mut(($arg1,$($arg2),*))
+        |$arg1,$($arg2),*| { use $crate::StableFnMut as _; $c.stable_call_mut(($arg1,$($arg2),*)) }
     };
     (|| $c:ident) => {
-        || $c.stable_call_once(())
+        || { use $crate::StableFnOnce as _; $c.stable_call_once(()) }
     };
     (|$arg:ident| $c:ident) => {
-        |$arg| $c.stable_call_once(($arg,))
+        |$arg| { use $crate::StableFnOnce as _; $c.stable_call_once(($arg,)) }
     };
     (|$arg1:ident,$($arg2:ident),+| $c:ident) => {
-        |$arg1,$($arg2),*| $c.stable_call_once(($arg1,$($arg2),*))
+        |$arg1,$($arg2),*| { use $crate::StableFnOnce as _; $c.stable_call_once(($arg1,$($arg2),*)) }
+    };
+}
+
+/// Creates a `DisplayWith` from a state expression and a formatting body.
+///
+/// `display_closure!(state=exp => |f| body)` formats `state` (borrowed in the
+/// body) into the formatter `f`.
+#[macro_export]
+macro_rules! display_closure {
+    ($state:ident=$state_val:expr => |$f:ident| $body:expr) => {
+        $crate::DisplayWith::new(|$state, $f| $body, $state_val)
+    };
+}
+
+/// Creates a `DeferGuard` that lives until the end of the enclosing scope.
+///
+/// `defer!(state=exp => body)` runs `body` with `state` (by value) when the
+/// scope is left, including by unwinding.
+#[macro_export]
+macro_rules! defer {
+    ($state:ident=$state_val:expr => $body:expr) => {
+        let _guard = $crate::DeferGuard::new(|$state| $body, $state_val);
+    };
+    (mut $state:ident=$state_val:expr => $body:expr) => {
+        let _guard = $crate::DeferGuard::new(|mut $state| $body, $state_val);
+    };
+}
+
+/// Declares a `static` closure.
+///
+/// `static_closure!(vis static NAME: Type = ...)` takes the same closure syntax
+/// as `closure!` after the `=`; as the constructors are `const fn`, the
+/// closure is built at compile time. With the `registry` feature,
+/// `static_closure!(register vis static NAME: Type = ...)` also submits the
+/// closure to the program-wide `registry` under the name `"NAME"`, and
+/// `static_closure!(hook vis static NAME: Kind<State, Input, Output> = ...)`
+/// registers it so that it can also be resolved by name as a `Hook<Input,
+/// Output>`; the type must then be spelled out with its three parameters.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::static_closure;
+/// # use namable_closures::{ClosureRef,StableFn};
+/// static_closure!(pub static ADD_TEN: ClosureRef<i32,(i32,),i32> = ref n=10 => move |i| i+*n);
+/// assert_eq!(ADD_TEN.stable_call((1,)), 11);
+/// ```
+#[macro_export]
+macro_rules! static_closure {
+    (register $(#[$attr:meta])* $vis:vis static $name:ident : $ty:ty = $($closure:tt)+) => {
+        $crate::static_closure!($(#[$attr])* $vis static $name : $ty = $($closure)+);
+        $crate::inventory::submit! {
+            $crate::registry::StaticClosure::new(stringify!($name), &$name)
+        }
+    };
+    (hook $(#[$attr:meta])* $vis:vis static $name:ident : $($kind:ident)::+ < $state:ty, $input:ty, $output:ty > = $($closure:tt)+) => {
+        $crate::static_closure!($(#[$attr])* $vis static $name : $($kind)::+ <$state, $input, $output> = $($closure)+);
+        const _: () = {
+            fn call(i: $input) -> $output {
+                $crate::StableFn::stable_call(&$name, i)
+            }
+            static HOOK: fn($input) -> $output = call;
+            $crate::inventory::submit! {
+                $crate::registry::StaticClosure::with_hook(stringify!($name), &$name, &HOOK, $crate::SignatureId::of::<$input, $output>)
+            }
+        };
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident : $ty:ty = $($closure:tt)+) => {
+        $(#[$attr])* $vis static $name : $ty = $crate::closure!($($closure)+);
+    };
+}
+
+/// Declares a `static` or `const` array of closures built at compile time.
+///
+/// Each entry is in braces and is either a stateless `|args| body`, which
+/// becomes a `ClosureRef<(), Input, Output>`, or anything `closure!` accepts.
+/// The length is counted from the entries. Since they all go into one array,
+/// an entry whose state or signature differs from the declared element type
+/// is a compile error.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure_table;
+/// # use namable_closures::{ClosureRef,StableFn};
+/// closure_table! {
+///     /// Binary opcodes, indexed by opcode byte.
+///     static BINARY_OPS: [ClosureRef<(),(i64,i64),Option<i64>>] = [
+///         { |a, b| a.checked_add(b) },
+///         { |a, b| a.checked_sub(b) },
+///         { |a, b| a.checked_mul(b) },
+///         { |a, b| a.checked_div(b) },
+///     ];
+/// }
+/// assert_eq!(BINARY_OPS.len(), 4);
+/// assert_eq!(BINARY_OPS[2].stable_call((6, 7)), Some(42));
+/// assert_eq!(BINARY_OPS[3].stable_call((1, 0)), None);
+/// ```
+#[macro_export]
+macro_rules! closure_table {
+    ($(#[$attr:meta])* $vis:vis static $name:ident : [$ty:ty] = [$({$($entry:tt)+}),* $(,)?];) => {
+        $(#[$attr])* $vis static $name : [$ty; $crate::closure_table!(@count $({$($entry)+})*)]
+            = [$($crate::closure_table!(@entry $($entry)+)),*];
+    };
+    ($(#[$attr:meta])* $vis:vis const $name:ident : [$ty:ty] = [$({$($entry:tt)+}),* $(,)?];) => {
+        $(#[$attr])* $vis const $name : [$ty; $crate::closure_table!(@count $({$($entry)+})*)]
+            = [$($crate::closure_table!(@entry $($entry)+)),*];
+    };
+    (@count $($entry:tt)*) => {
+        <[()]>::len(&[$($crate::closure_table!(@unit $entry)),*])
+    };
+    (@unit $entry:tt) => { () };
+    (@entry || $body:expr) => {
+        $crate::ClosureRef::new(|_, ()| $body, ())
+    };
+    (@entry |$($arg:pat_param),+| $body:expr) => {
+        $crate::ClosureRef::new(|_, ($($arg,)+)| $body, ())
+    };
+    (@entry $($closure:tt)+) => {
+        $crate::closure!($($closure)+)
     };
 }
 
+/// Declares an event enum together with a dispatcher routing each variant to
+/// its own callback.
+///
+/// Every variant is written with named fields and followed by the name and
+/// closure type of its slot. The dispatcher gets one public `CallbackSlot`
+/// field per variant, and `handle(event)` destructures the event and calls
+/// the matching slot mutably with the fields as arguments, in declaration
+/// order. `handle` returns `false` if that slot was empty; the callbacks'
+/// outputs are dropped.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::{closure,event_dispatch};
+/// # use namable_closures::{ClosureRef,ClosureRefMut};
+/// event_dispatch! {
+///     #[derive(Debug, Clone, Copy)]
+///     pub enum Input {
+///         Click { x: i32, y: i32 } => on_click: ClosureRefMut<Vec<(i32,i32)>,(i32,i32),()>,
+///         Key { c: char } => on_key: ClosureRef<(),(char,),()>,
+///         Quit {} => on_quit: ClosureRefMut<bool,(),()>,
+///     }
+///     #[derive(Debug)]
+///     pub struct InputHandlers;
+/// }
+/// let mut handlers = InputHandlers::new();
+/// handlers.on_click.set(closure!(ref mut clicks=Vec::new() => move |x,y| clicks.push((x,y))));
+/// assert!(handlers.handle(Input::Click { x: 1, y: 2 }));
+/// assert!(!handlers.handle(Input::Key { c: 'q' }));
+/// ```
+#[macro_export]
+macro_rules! event_dispatch {
+    (
+        $(#[$eattr:meta])* $evis:vis enum $event:ident {
+            $($variant:ident { $($arg:ident : $arg_ty:ty),* $(,)? } => $slot:ident : $slot_ty:ty),* $(,)?
+        }
+        $(#[$dattr:meta])* $dvis:vis struct $dispatcher:ident;
+    ) => {
+        $(#[$eattr])* $evis enum $event {
+            $($variant { $($arg : $arg_ty),* }),*
+        }
+        $(#[$dattr])* $dvis struct $dispatcher {
+            $(pub $slot: $crate::CallbackSlot<$slot_ty>),*
+        }
+        impl ::core::default::Default for $dispatcher {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+        impl $dispatcher {
+            /// A dispatcher with every slot empty.
+            pub fn new() -> Self {
+                Self { $($slot: $crate::CallbackSlot::new()),* }
+            }
+            /// Calls the slot for `event`'s variant, returning `false` if it
+            /// is empty.
+            pub fn handle(&mut self, event: $event) -> bool {
+                match event {
+                    $($event::$variant { $($arg),* } => self.$slot.call_mut_if_set(($($arg,)*)).is_some()),*
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature="alloc")]
+extern crate alloc;
+
+#[cfg(feature="registry")]
+#[doc(hidden)]
+pub use inventory;
+
+#[cfg(doctest)]
+#[doc = include_str!("../README.md")]
+struct ReadmeDoctests;
+
+#[allow(unsafe_code)]
+mod raw;
+
+// The core: closure types, traits and adapters with no dependencies and no
+// allocation. Always available, including with `default-features = false`.
 pub mod closures;
+pub mod once_slot;
 pub mod closure_rec;
+pub mod rec_table;
 pub mod stable_fn;
+pub mod state;
+pub mod state_fn;
+pub mod closure_id;
+pub mod error;
+pub mod into_closure;
+pub mod collection;
+pub mod snapshot;
+pub mod arr_fn;
+pub mod display;
+pub mod defer;
+pub mod std_fns;
+pub mod iter;
+pub mod callback;
+pub mod callback_slot;
+pub mod self_replacing;
+pub mod scratch;
+pub mod context;
+pub mod map_state;
+pub mod signature;
+pub mod any_kind;
+pub mod validated;
+pub mod versioned_cache;
+pub mod stepper;
+pub mod history;
+pub mod dedup;
+pub mod progress;
+pub mod ref_args;
+pub mod api;
+pub mod bind;
+pub mod ab_test;
+pub mod outcome;
+pub mod unwrap;
+pub mod join;
+pub mod prelude;
+
+// Containers and other types that need a heap.
+#[cfg(feature="alloc")]
+pub mod closure_slab;
+#[cfg(feature="alloc")]
+pub mod closure_array;
+#[cfg(feature="alloc")]
+pub mod event;
+#[cfg(feature="alloc")]
+pub mod aggregate;
+#[cfg(feature="alloc")]
+pub mod weak;
+#[cfg(feature="alloc")]
+pub mod timer_wheel;
+#[cfg(feature="alloc")]
+pub mod typed_bus;
+#[cfg(feature="alloc")]
+pub mod strategy;
+
+// Threads, processes, I/O and the rest of `std`.
+#[cfg(feature="std")]
+pub mod closure_map;
+#[cfg(feature="std")]
+pub mod panic_hook;
+#[cfg(feature="std")]
+pub mod timeout;
+#[cfg(feature="std")]
+pub mod remote;
+#[cfg(feature="std")]
+pub mod jobs;
+#[cfg(feature="std")]
+pub mod scope;
+#[cfg(feature="std")]
+pub mod process;
+#[cfg(feature="std")]
+pub mod io;
+#[cfg(feature="std")]
+pub mod shutdown;
+#[cfg(feature="std")]
+pub mod block_on;
+
+// Integrations with other crates, one feature each.
+#[cfg(feature="critical-section")]
+pub mod closure_cell;
+#[cfg(feature="serde")]
+pub mod serde_visitor;
+#[cfg(feature="registry")]
+pub mod registry;
+#[cfg(feature="registry")]
+pub mod hook;
+#[cfg(feature="pyo3")]
+pub mod python;
+#[cfg(feature="nom")]
+pub mod nom_parser;
+#[cfg(feature="futures")]
+pub mod sink;
 
-pub use closures::{Closure,ClosureMut,ClosureOnce,ClosureRef,ClosureRefMut};
+pub use closures::{Closure,ClosureMut,ClosureOnce,ClosureRef,ClosureRefMut,ClosureView};
+pub use once_slot::ClosureOnceSlot;
 pub use stable_fn::{StableFn,StableFnMut,StableFnOnce};
-pub use closure_rec::{ClosureOnceRec,ClosureRecMut,ClosureMutRec,ClosureRec};
\ No newline at end of file
+pub use state::{ClosureState,ClosureStateMut};
+pub use state_fn::GenericStateFn;
+pub use closure_id::ClosureId;
+pub use error::Error;
+pub use into_closure::IntoClosure;
+pub use collection::ClosureCollection;
+pub use snapshot::Snapshot;
+pub use closure_rec::{ClosureOnceRec,ClosureRecMut,ClosureMutRec,ClosureRec,RecMutFn};
+pub use rec_table::{RecFn,RecTable};
+pub use arr_fn::{StableFnArr,StableFnMutArr,StableFnOnceArr};
+pub use display::{DisplayWith,FmtWriter};
+pub use defer::{guard,DeferGuard};
+pub use callback_slot::CallbackSlot;
+pub use self_replacing::{ClosureChain,SelfReplacingClosure};
+pub use scratch::ScratchClosure;
+pub use context::{ContextClosure,WithContext};
+pub use map_state::{MapStateOnce,MapStateRef,MapStateRefMut};
+pub use signature::{Sig,Signature,SignatureId};
+pub use any_kind::AnyClosureKind;
+pub use validated::{Rejected,Validated};
+pub use versioned_cache::VersionedCache;
+pub use stepper::Stepper;
+pub use history::History;
+pub use dedup::Dedup;
+pub use progress::{IgnoreProgress,NoProgress,ProgressSink,WithProgress};
+pub use bind::Bind;
+pub use ab_test::{AbReport,AbTest,ArmStats,Outcome};
+pub use outcome::CallOutcome;
+pub use unwrap::{CallUnwrap,Fallible,NoneError};
+pub use join::{Either,Join2,Select2};
+#[cfg(feature="alloc")]
+pub use closure_slab::{ClosureSlab,SlabKey};
+#[cfg(feature="alloc")]
+pub use closure_array::ClosureArray;
+#[cfg(feature="alloc")]
+pub use event::EventEmitter;
+#[cfg(feature="alloc")]
+pub use aggregate::{Aggregate,CollectAll,FirstSome,Fold};
+#[cfg(feature="alloc")]
+pub use weak::WeakClosure;
+#[cfg(feature="alloc")]
+pub use timer_wheel::TimerWheel;
+#[cfg(feature="alloc")]
+pub use typed_bus::{BusKey,EventHandler,TypedBus};
+#[cfg(feature="alloc")]
+pub use strategy::Strategy;
+#[cfg(feature="std")]
+pub use closure_map::ClosureMap;
+#[cfg(feature="std")]
+pub use timeout::TimedOut;
+#[cfg(feature="std")]
+pub use remote::{RemoteCall,RemoteClosure,RemoteOwner};
+#[cfg(feature="std")]
+pub use jobs::{JobHandle,JobPool};
+#[cfg(feature="std")]
+pub use scope::{scope,Scope};
+#[cfg(feature="std")]
+pub use io::{IoReader,IoWriter};
+#[cfg(feature="std")]
+pub use shutdown::ShutdownHooks;
+#[cfg(feature="std")]
+pub use block_on::block_on;
+#[cfg(feature="critical-section")]
+pub use closure_cell::{IsrNotify,IsrWait,StaticClosureCell};
+#[cfg(feature="serde")]
+pub use serde_visitor::{ClosureSeed,SeqVisitor,StrVisitor};
+#[cfg(feature="registry")]
+pub use hook::Hook;
+#[cfg(feature="pyo3")]
+pub use python::{py_function,PyCallback};
+#[cfg(feature="nom")]
+pub use nom_parser::NomParser;
+#[cfg(feature="futures")]
+pub use sink::SinkFromFn;
@@ -0,0 +1,194 @@
+//! Closures whose function works on a part of their state.
+//!
+//! A function written against a small state type, say a counter, can be reused
+//! inside a larger application struct by storing the struct and a projection
+//! from it to the counter. `ClosureRef::map_state`, `ClosureRefMut::map_state`
+//! and `ClosureOnce::map_state` build such closures, keeping the function and
+//! taking the new state, and hand back the state they replaced; the types they
+//! return can be named like the closures they came from.
+
+use core::fmt;
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// A `ClosureRef` function called on the part of `NewState` that `proj`
+/// borrows.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,MapStateRef,StableFn};
+/// struct App { name: &'static str, version: u32 }
+/// let greet:ClosureRef<&'static str,(),String> = closure!(ref name="" => move || format!("hi {}", name));
+/// let app = App { name: "editor", version: 3 };
+/// let (greet, _):(MapStateRef<App,&'static str,(),String>, _) = greet.map_state(|app| &app.name, app);
+/// assert_eq!(greet.stable_call(()), "hi editor");
+/// assert_eq!(greet.into_inner().version, 3);
+/// ```
+pub struct MapStateRef<NewState, State, Input, Output> {
+    f: fn(&State, Input) -> Output,
+    proj: fn(&NewState) -> &State,
+    t: NewState,
+}
+/// A `ClosureRefMut` function called on the part of `NewState` that `proj`
+/// borrows.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRefMut,MapStateRefMut,StableFnMut};
+/// #[derive(Default)]
+/// struct Stats { hits: u32, misses: u32 }
+/// let count:ClosureRefMut<u32,(),u32> = closure!(ref mut n=0 => move || {*n+=1;*n});
+/// let (mut hit, _):(MapStateRefMut<Stats,u32,(),u32>, _) = count.map_state(|s| &mut s.hits, Stats::default());
+/// hit.stable_call_mut(());
+/// hit.stable_call_mut(());
+/// let (mut miss, _) = count.map_state(|s| &mut s.misses, hit.into_inner());
+/// miss.stable_call_mut(());
+/// let stats = miss.into_inner();
+/// assert_eq!((stats.hits, stats.misses), (2, 1));
+/// ```
+pub struct MapStateRefMut<NewState, State, Input, Output> {
+    f: fn(&mut State, Input) -> Output,
+    proj: fn(&mut NewState) -> &mut State,
+    t: NewState,
+}
+/// A `ClosureOnce` function called on the `State` that `proj` turns
+/// `NewState` into.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureOnce,MapStateOnce,StableFnOnce};
+/// struct Request { body: Vec<u8>, id: u32 }
+/// let len:ClosureOnce<Vec<u8>,(),usize> = closure!(v=Vec::new() => move || v.len());
+/// let req = Request { body: vec![1, 2, 3], id: 7 };
+/// let (body_len, unused):(MapStateOnce<Request,Vec<u8>,(),usize>, _) = len.map_state(|r| r.body, req);
+/// assert!(unused.is_empty());
+/// assert_eq!(body_len.stable_call_once(()), 3);
+/// # }
+/// ```
+pub struct MapStateOnce<NewState, State, Input, Output> {
+    f: fn(State, Input) -> Output,
+    proj: fn(NewState) -> State,
+    t: NewState,
+}
+
+macro_rules! map_state_common {
+    ($name:ident, $f:ty, $proj:ty) => {
+        impl<NewState, State, Input, Output> Copy for $name<NewState, State, Input, Output>
+        where
+            NewState: Copy
+        {}
+        impl<NewState, State, Input, Output> Clone for $name<NewState, State, Input, Output>
+        where
+            NewState: Clone
+        {
+            fn clone(&self) -> Self {
+                Self { f: self.f, proj: self.proj, t: self.t.clone() }
+            }
+        }
+        impl<NewState, State, Input, Output> $name<NewState, State, Input, Output> {
+            pub const fn new(f: $f, proj: $proj, t: NewState) -> Self {
+                Self { f, proj, t }
+            }
+            pub fn into_inner(self) -> NewState {
+                self.t
+            }
+        }
+        impl<NewState, State, Input, Output> fmt::Debug for $name<NewState, State, Input, Output>
+        where
+            NewState: fmt::Debug
+        {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_struct(stringify!($name)).field("state", &self.t).finish_non_exhaustive()
+            }
+        }
+        impl<NewState, State, Input, Output> ClosureState for $name<NewState, State, Input, Output> {
+            type State = NewState;
+            fn state(&self) -> &NewState {
+                &self.t
+            }
+        }
+        impl<NewState, State, Input, Output> ClosureStateMut for $name<NewState, State, Input, Output> {
+            fn state_mut(&mut self) -> &mut NewState {
+                &mut self.t
+            }
+        }
+    };
+}
+map_state_common!(MapStateRef, fn(&State, Input) -> Output, fn(&NewState) -> &State);
+map_state_common!(MapStateRefMut, fn(&mut State, Input) -> Output, fn(&mut NewState) -> &mut State);
+map_state_common!(MapStateOnce, fn(State, Input) -> Output, fn(NewState) -> State);
+
+impl<NewState, State, Input, Output> StableFnOnce<Input> for MapStateRef<NewState, State, Input, Output> {
+    type Output = Output;
+    #[inline]
+    fn stable_call_once(self, i: Input) -> Output {
+        (self.f)((self.proj)(&self.t), i)
+    }
+}
+impl<NewState, State, Input, Output> StableFnMut<Input> for MapStateRef<NewState, State, Input, Output> {
+    #[inline]
+    fn stable_call_mut(&mut self, i: Input) -> Output {
+        (self.f)((self.proj)(&self.t), i)
+    }
+}
+impl<NewState, State, Input, Output> StableFn<Input> for MapStateRef<NewState, State, Input, Output> {
+    #[inline]
+    fn stable_call(&self, i: Input) -> Output {
+        (self.f)((self.proj)(&self.t), i)
+    }
+}
+impl<NewState, State, Input, Output> StableFnOnce<Input> for MapStateRefMut<NewState, State, Input, Output> {
+    type Output = Output;
+    #[inline]
+    fn stable_call_once(mut self, i: Input) -> Output {
+        (self.f)((self.proj)(&mut self.t), i)
+    }
+}
+impl<NewState, State, Input, Output> StableFnMut<Input> for MapStateRefMut<NewState, State, Input, Output> {
+    #[inline]
+    fn stable_call_mut(&mut self, i: Input) -> Output {
+        (self.f)((self.proj)(&mut self.t), i)
+    }
+}
+impl<NewState, State, Input, Output> StableFnOnce<Input> for MapStateOnce<NewState, State, Input, Output> {
+    type Output = Output;
+    #[inline]
+    fn stable_call_once(self, i: Input) -> Output {
+        (self.f)((self.proj)(self.t), i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureOnce,ClosureRef,ClosureRefMut,MapStateRefMut,StableFn,StableFnMut,StableFnOnce};
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    struct Player { score: u32, lives: u8 }
+
+    #[test]
+    fn test_map_state() {
+        let add:ClosureRefMut<u32,(u32,),u32> = closure!(ref mut n=0 => move |i| {*n+=i;*n});
+        let (mut scored, old):(MapStateRefMut<Player,u32,(u32,),u32>, _) = add.map_state(|p| &mut p.score, Player::default());
+        assert_eq!(old, 0);
+        scored.stable_call_mut((10,));
+        let copy = scored;
+        assert_eq!(scored.stable_call_once((5,)), 15);
+        assert_eq!(copy.into_inner(), Player { score: 10, lives: 0 });
+
+        let alive:ClosureRef<u8,(),bool> = closure!(ref n=0 => move || *n > 0);
+        let (alive, _) = alive.map_state(|p: &Player| &p.lives, Player { score: 0, lives: 2 });
+        assert!(alive.stable_call(()));
+        assert_eq!(format!("{:?}", alive), "MapStateRef { state: Player { score: 0, lives: 2 }, .. }");
+
+        let lose:ClosureOnce<u8,(),u8> = closure!(n=0 => move || n - 1);
+        assert_eq!(lose.map_state(|p: Player| p.lives, Player { score: 0, lives: 3 }).0.stable_call_once(()), 2);
+    }
+}
@@ -0,0 +1,85 @@
+//! nom parsers.
+//!
+//! `NomParser` lets a namable closure over `(Input,)` that returns an
+//! `IResult` take part in nom combinator chains. A stateful parser, such as a
+//! lexer that keeps its interner or symbol table in the closure state, stays
+//! namable and unboxed. Wrapping `&mut` the closure keeps its state reachable
+//! after the chain has run.
+//!
+//! Only available with the `nom` feature.
+
+use nom::error::ParseError;
+use nom::{Err,IResult,Mode,OutputMode,PResult,Parser};
+
+use crate::stable_fn::StableFnMut;
+
+/// A namable closure used as a `nom::Parser`.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRefMut,ClosureState,NomParser};
+/// use nom::character::complete::{alpha1,char};
+/// use nom::multi::separated_list1;
+/// use nom::{IResult,Parser};
+///
+/// let mut intern:ClosureRefMut<Vec<String>,(&str,),IResult<&str,usize>> = closure!(ref mut names=vec![] => move |input| {
+///     let (rest, name) = alpha1::<_, nom::error::Error<_>>(input)?;
+///     let id = names.iter().position(|n| n == name).unwrap_or_else(|| {
+///         names.push(name.to_string());
+///         names.len() - 1
+///     });
+///     Ok((rest, id))
+/// });
+/// let ids = separated_list1(char(','), NomParser(&mut intern)).parse("x,y,x");
+/// assert_eq!(ids, Ok(("", vec![0, 1, 0])));
+/// assert_eq!(intern.state(), &["x", "y"]);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct NomParser<F>(pub F);
+
+impl<F, I, O, E> Parser<I> for NomParser<F>
+where
+    F: StableFnMut<(I,), Output=IResult<I, O, E>>,
+    E: ParseError<I>
+{
+    type Output = O;
+    type Error = E;
+    fn process<OM: OutputMode>(&mut self, input: I) -> PResult<OM, I, O, E> {
+        match self.0.stable_call_mut((input,)) {
+            Ok((rest, o)) => Ok((rest, OM::Output::bind(|| o))),
+            Err(Err::Error(e)) => Err(Err::Error(OM::Error::bind(|| e))),
+            Err(Err::Failure(e)) => Err(Err::Failure(e)),
+            Err(Err::Incomplete(needed)) => Err(Err::Incomplete(needed)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::bytes::complete::tag;
+    use nom::character::complete::digit1;
+    use nom::error::{Error,ErrorKind};
+    use nom::sequence::preceded;
+    use nom::{IResult,Parser};
+    use crate::{ClosureRefMut,NomParser};
+
+    type Counter<'a> = ClosureRefMut<u32,(&'a str,),IResult<&'a str,u32>>;
+
+    #[test]
+    fn test_parser_in_chain() {
+        let mut number:Counter = closure!(ref mut calls=0 => move |input| {
+            *calls += 1;
+            let (rest, digits) = digit1(input)?;
+            Ok((rest, digits.parse().unwrap()))
+        });
+        assert_eq!(preceded(tag("#"), NomParser(&mut number)).parse("#42;"), Ok((";", 42)));
+        assert_eq!(NomParser(&mut number).parse("x"), Err(nom::Err::Error(Error::new("x", ErrorKind::Digit))));
+        // the failed `tag` never reaches the closure
+        assert!(preceded(tag("#"), NomParser(&mut number)).parse("42").is_err());
+        assert_eq!(*number.state(), 2);
+    }
+}
@@ -0,0 +1,114 @@
+//! A once-closure that can be called through `&mut`.
+//!
+//! `ClosureOnce` only implements `StableFnMut` when its state is `Copy`, as
+//! each call consumes a copy of it. A state that owns a resource, such as a
+//! socket or a buffer, cannot be copied; `ClosureOnceSlot` keeps it in an
+//! `Option` instead and moves it out on the first call.
+
+use core::fmt;
+
+use crate::error::Error;
+use crate::stable_fn::{StableFnMut,StableFnOnce};
+
+/// A `ClosureOnce` whose state is taken on the first call.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureOnce,ClosureOnceSlot,Error,StableFnMut};
+/// let send:ClosureOnce<Vec<u8>,(u8,),Vec<u8>> = closure!(mut buf=vec![1] => move |b| {buf.push(b); buf});
+/// let mut send = send.into_slot();
+/// assert_eq!(send.stable_call_mut((2,)), [1, 2]);
+/// assert!(send.is_spent());
+/// assert_eq!(send.try_call((3,)), Err(Error::AlreadyCalled));
+/// ```
+pub struct ClosureOnceSlot<State, Input, Output> {
+    f: fn(State, Input) -> Output,
+    t: Option<State>,
+}
+impl<State, Input, Output> Clone for ClosureOnceSlot<State, Input, Output>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<State, Input, Output> fmt::Debug for ClosureOnceSlot<State, Input, Output> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ClosureOnceSlot").field("spent", &self.is_spent()).finish_non_exhaustive()
+    }
+}
+impl<State, Input, Output> ClosureOnceSlot<State, Input, Output> {
+    pub const fn new(f: fn(State, Input) -> Output, t: State) -> Self {
+        Self { f, t: Some(t) }
+    }
+    /// Whether the closure has been called, and its state consumed.
+    pub fn is_spent(&self) -> bool {
+        self.t.is_none()
+    }
+    /// The state, unless the closure has been called.
+    pub fn state(&self) -> Option<&State> {
+        self.t.as_ref()
+    }
+    pub fn state_mut(&mut self) -> Option<&mut State> {
+        self.t.as_mut()
+    }
+    /// Calls the closure, or returns `Error::AlreadyCalled` if it has been
+    /// called before.
+    pub fn try_call(&mut self, args: Input) -> Result<Output, Error> {
+        let t = self.t.take().ok_or(Error::AlreadyCalled)?;
+        Ok((self.f)(t, args))
+    }
+    pub fn into_inner(self) -> Option<State> {
+        self.t
+    }
+}
+impl<State, Input, Output> StableFnOnce<Input> for ClosureOnceSlot<State, Input, Output> {
+    type Output = Output;
+    /// # Panics
+    ///
+    /// Panics if the closure has been called before.
+    fn stable_call_once(mut self, args: Input) -> Output {
+        self.stable_call_mut(args)
+    }
+}
+impl<State, Input, Output> StableFnMut<Input> for ClosureOnceSlot<State, Input, Output> {
+    /// # Panics
+    ///
+    /// Panics if the closure has been called before.
+    fn stable_call_mut(&mut self, args: Input) -> Output {
+        match self.try_call(args) {
+            Ok(output) => output,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureOnce,ClosureOnceSlot,Error,StableFnMut,StableFnOnce};
+
+    #[test]
+    fn test_state_is_taken_once() {
+        let greet:ClosureOnce<String,(&str,),String> = closure!(mut s=String::from("hi") => move |name| {s.push_str(name); s});
+        let mut greet = greet.into_slot();
+        assert_eq!(greet.state().map(String::as_str), Some("hi"));
+        greet.state_mut().unwrap().push(' ');
+        let copy = greet.clone();
+        assert_eq!(greet.try_call(("bob",)), Ok("hi bob".to_string()));
+        assert_eq!(greet.try_call(("bob",)), Err(Error::AlreadyCalled));
+        assert_eq!(format!("{:?}", greet), "ClosureOnceSlot { spent: true, .. }");
+        assert_eq!(greet.into_inner(), None);
+        assert_eq!(copy.stable_call_once(("al",)), "hi al");
+    }
+
+    #[test]
+    #[should_panic(expected = "already called")]
+    fn test_second_call_panics() {
+        let mut drop_it = ClosureOnceSlot::new(|v:Vec<u8>, ()| v.len(), vec![1]);
+        assert_eq!(drop_it.stable_call_mut(()), 1);
+        drop_it.stable_call_mut(());
+    }
+}
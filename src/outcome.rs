@@ -0,0 +1,104 @@
+//! The result of one call made by a container.
+//!
+//! Containers that call many closures at once, such as `EventEmitter::emit_report`
+//! and `ClosureSlab::call_each_mut`, report what happened to each of them with
+//! a `CallOutcome`, so that callers can log or aggregate the results the same
+//! way whichever container made the calls.
+
+use crate::ab_test::Outcome;
+use crate::error::Error;
+
+/// What happened when a container called one of its closures.
+///
+/// More variants may be added, so matches need a wildcard arm.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::{CallOutcome,Error};
+/// let outcomes:[CallOutcome<i32>;3] = [Ok(1).into(), CallOutcome::Skipped, Err(Error::StaleKey).into()];
+/// let completed:i32 = outcomes.iter().filter_map(|o| o.completed()).sum();
+/// assert_eq!(completed, 1);
+/// assert_eq!(outcomes[2].error(), Some(Error::StaleKey));
+/// ```
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CallOutcome<Output> {
+    /// The closure was called and returned.
+    Completed(Output),
+    /// The closure was deliberately not called, for example because it was
+    /// already running.
+    Skipped,
+    /// The closure could not be called.
+    Failed(Error),
+}
+impl<Output> CallOutcome<Output> {
+    pub fn is_completed(&self) -> bool {
+        matches!(self, CallOutcome::Completed(_))
+    }
+    pub fn is_skipped(&self) -> bool {
+        matches!(self, CallOutcome::Skipped)
+    }
+    pub fn is_failed(&self) -> bool {
+        matches!(self, CallOutcome::Failed(_))
+    }
+    /// The output of a completed call.
+    pub fn completed(self) -> Option<Output> {
+        match self {
+            CallOutcome::Completed(o) => Some(o),
+            _ => None,
+        }
+    }
+    /// The error of a failed call.
+    pub fn error(&self) -> Option<Error> {
+        match *self {
+            CallOutcome::Failed(e) => Some(e),
+            _ => None,
+        }
+    }
+    pub fn as_ref(&self) -> CallOutcome<&Output> {
+        match *self {
+            CallOutcome::Completed(ref o) => CallOutcome::Completed(o),
+            CallOutcome::Skipped => CallOutcome::Skipped,
+            CallOutcome::Failed(e) => CallOutcome::Failed(e),
+        }
+    }
+    pub fn map<U, F>(self, f: F) -> CallOutcome<U>
+    where
+        F: FnOnce(Output) -> U
+    {
+        match self {
+            CallOutcome::Completed(o) => CallOutcome::Completed(f(o)),
+            CallOutcome::Skipped => CallOutcome::Skipped,
+            CallOutcome::Failed(e) => CallOutcome::Failed(e),
+        }
+    }
+}
+impl<Output> From<Result<Output, Error>> for CallOutcome<Output> {
+    fn from(r: Result<Output, Error>) -> Self {
+        match r {
+            Ok(o) => CallOutcome::Completed(o),
+            Err(e) => CallOutcome::Failed(e),
+        }
+    }
+}
+impl<Output> Outcome for CallOutcome<Output>
+where
+    Output: Outcome
+{
+    /// A skipped call counts as a success; a failed one, or a completed one
+    /// whose output is a failure, does not.
+    fn is_success(&self) -> bool {
+        match *self {
+            CallOutcome::Completed(ref o) => o.is_success(),
+            CallOutcome::Skipped => true,
+            CallOutcome::Failed(_) => false,
+        }
+    }
+    fn value(&self) -> Option<f64> {
+        match *self {
+            CallOutcome::Completed(ref o) => o.value(),
+            _ => None,
+        }
+    }
+}
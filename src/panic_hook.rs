@@ -0,0 +1,128 @@
+//! Installing a namable closure as the panic hook.
+//!
+//! `std::panic::set_hook` needs a handler that accepts a `&PanicHookInfo` of
+//! any lifetime. A `ClosureRef<State, (&PanicHookInfo,), ()>` fixes that
+//! lifetime in its type, so it cannot be used directly. `PanicHook` is the
+//! equivalent namable closure whose function is higher-ranked over the info
+//! lifetime, and `set_panic_hook` installs it.
+
+use std::fmt;
+use std::panic::{self,PanicHookInfo};
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// A namable panic hook: a state plus a `fn(&State, &PanicHookInfo)`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::panic_hook::{set_panic_hook,PanicHook};
+/// # use std::sync::atomic::{AtomicUsize,Ordering};
+/// static PANICS:AtomicUsize = AtomicUsize::new(0);
+/// let previous = set_panic_hook(PanicHook::new(|count, _info| {
+///     count.fetch_add(1, Ordering::SeqCst);
+/// }, &PANICS));
+/// let _ = std::panic::catch_unwind(|| panic!("counted"));
+/// previous.restore();
+/// assert_eq!(PANICS.load(Ordering::SeqCst), 1);
+/// ```
+pub struct PanicHook<State> {
+    f: fn(&State, &PanicHookInfo),
+    t: State,
+}
+impl<State> Copy for PanicHook<State>
+where
+    State: Copy
+{}
+impl<State> Clone for PanicHook<State>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<State> PanicHook<State> {
+    pub fn new(f: fn(&State, &PanicHookInfo), t: State) -> Self {
+        Self { f, t }
+    }
+    pub fn call(&self, info: &PanicHookInfo) {
+        (self.f)(&self.t, info)
+    }
+}
+impl<'a, 'b, State> StableFnOnce<(&'a PanicHookInfo<'b>,)> for PanicHook<State> {
+    type Output = ();
+    fn stable_call_once(self, (info,): (&'a PanicHookInfo<'b>,)) {
+        self.call(info)
+    }
+}
+impl<'a, 'b, State> StableFnMut<(&'a PanicHookInfo<'b>,)> for PanicHook<State> {
+    fn stable_call_mut(&mut self, (info,): (&'a PanicHookInfo<'b>,)) {
+        self.call(info)
+    }
+}
+impl<'a, 'b, State> StableFn<(&'a PanicHookInfo<'b>,)> for PanicHook<State> {
+    fn stable_call(&self, (info,): (&'a PanicHookInfo<'b>,)) {
+        self.call(info)
+    }
+}
+
+type BoxedHook = Box<dyn Fn(&PanicHookInfo) + Send + Sync + 'static>;
+
+/// The hook that was active before `set_panic_hook` was called.
+pub struct PreviousHook(BoxedHook);
+impl PreviousHook {
+    /// Reinstalls the previous hook, dropping the current one.
+    pub fn restore(self) {
+        panic::set_hook(self.0)
+    }
+    /// Forwards to the previous hook, e.g. to chain to the default output.
+    pub fn call(&self, info: &PanicHookInfo) {
+        (self.0)(info)
+    }
+}
+impl fmt::Debug for PreviousHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("PreviousHook")
+    }
+}
+
+/// Installs `hook` as the process-wide panic hook and returns the hook it
+/// replaced, so it can be restored later.
+pub fn set_panic_hook<State>(hook: PanicHook<State>) -> PreviousHook
+where
+    State: Send + Sync + 'static
+{
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| hook.call(info)));
+    PreviousHook(previous)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self,PanicHookInfo};
+    use std::sync::Mutex;
+    use crate::panic_hook::{set_panic_hook,PanicHook};
+    use crate::StableFn;
+
+    static MESSAGES:Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn assert_higher_ranked<H>(_: &H)
+    where
+        H: for<'a,'b> StableFn<(&'a PanicHookInfo<'b>,), Output=()>
+    {}
+
+    #[test]
+    fn test_set_and_restore() {
+        let hook = PanicHook::new(|prefix, info| {
+            let msg = info.payload().downcast_ref::<&str>().cloned().unwrap_or("");
+            MESSAGES.lock().unwrap().push(format!("{}{}", prefix, msg));
+        }, "hooked: ");
+        assert_higher_ranked(&hook);
+        let previous = set_panic_hook(hook);
+        let r = panic::catch_unwind(|| panic!("panic_hook test"));
+        previous.restore();
+        assert!(r.is_err());
+        assert!(MESSAGES.lock().unwrap().iter().any(|m| m == "hooked: panic_hook test"));
+    }
+}
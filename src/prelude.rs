@@ -0,0 +1,32 @@
+//! The closure types, the traits their methods come from, and the macros.
+//!
+//! Calling a namable closure needs the `StableFn*` trait in scope, and reading
+//! its state needs `ClosureState`. Importing the prelude brings in all of
+//! them at once:
+//!
+//! ```rust
+//! use namable_closures::prelude::*;
+//!
+//! let mut count:ClosureRefMut<u32,(),u32> = closure!(ref mut n=0 => move || {*n+=1;*n});
+//! count.stable_call_mut(());
+//! assert_eq!(count.stable_call_mut(()), 2);
+//! let total:&dyn ClosureState<State=u32> = &count;
+//! assert_eq!(*total.state(), 2);
+//! ```
+//!
+//! Containers and adapters are left out; they are imported from the crate
+//! root as needed.
+
+pub use crate::closures::{Closure,ClosureMut,ClosureOnce,ClosureRef,ClosureRefMut,ClosureView};
+pub use crate::closure_rec::{ClosureMutRec,ClosureOnceRec,ClosureRec,ClosureRecMut};
+pub use crate::once_slot::ClosureOnceSlot;
+pub use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+pub use crate::arr_fn::{StableFnArr,StableFnMutArr,StableFnOnceArr};
+pub use crate::state::{ClosureState,ClosureStateMut};
+pub use crate::into_closure::IntoClosure;
+pub use crate::collection::ClosureCollection;
+pub use crate::signature::Signature;
+pub use crate::unwrap::CallUnwrap;
+pub use crate::api::{Factory,Getter,OnEvent,Setter};
+pub use crate::error::Error;
+pub use crate::{call,closure,closure_rec,closure_table,defer,display_closure,event_dispatch,regulate,static_closure};
@@ -0,0 +1,96 @@
+//! Namable closures as `std::process` hooks.
+//!
+//! `CommandExt::pre_exec` wants a `FnMut() -> io::Result<()> + Send + Sync +
+//! 'static`; `pre_exec_fn` adapts a namable closure to that shape, so a
+//! command builder can keep its hooks as fields of a nameable type.
+//! `spawn_with` runs a closure with the pid of a freshly spawned child.
+//!
+//! Only available with the `std` feature; `pre_exec_fn` is unix-only.
+
+use std::io;
+use std::process::{Child,Command};
+
+use crate::stable_fn::{StableFnMut,StableFnOnce};
+
+/// Adapts `f` for `std::os::unix::process::CommandExt::pre_exec`.
+///
+/// `pre_exec` stays `unsafe`: the hook runs in the child between `fork` and
+/// `exec`, where only async-signal-safe operations are allowed. In particular
+/// the body of `f` must not allocate, take locks (including through
+/// `println!`) or touch anything another thread of the parent might have
+/// held at the time of the fork. Changes the hook makes to its state happen in
+/// the child's copy of memory and are never seen by the parent.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(unix)] {
+/// # use namable_closures::closure;
+/// # use namable_closures::ClosureRef;
+/// # use namable_closures::process::pre_exec_fn;
+/// # use std::os::unix::process::CommandExt;
+/// # use std::process::Command;
+/// # use std::io;
+/// let check:ClosureRef<bool,(),io::Result<()>> = closure!(ref allowed=true => move || {
+///     if *allowed { Ok(()) } else { Err(io::ErrorKind::PermissionDenied.into()) }
+/// });
+/// let mut command = Command::new("true");
+/// // Safety: the hook only reads its state and builds an error without allocating.
+/// unsafe { command.pre_exec(pre_exec_fn(check)) };
+/// assert!(command.status().unwrap().success());
+/// # }
+/// ```
+#[cfg(unix)]
+pub fn pre_exec_fn<F>(f: F) -> impl FnMut() -> io::Result<()> + Send + Sync + 'static
+where
+    F: StableFnMut<(), Output=io::Result<()>> + Send + Sync + 'static
+{
+    let mut f = f;
+    move || f.stable_call_mut(())
+}
+
+/// Spawns `command` and calls `on_spawn` with the child's process id.
+///
+/// `on_spawn` is not called if spawning fails. Pass `&mut hook` to keep a
+/// stateful hook for the next spawn.
+pub fn spawn_with<F>(command: &mut Command, on_spawn: F) -> io::Result<(Child, F::Output)>
+where
+    F: StableFnOnce<(u32,)>
+{
+    let child = command.spawn()?;
+    let output = on_spawn.stable_call_once((child.id(),));
+    Ok((child, output))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+    use crate::process::{pre_exec_fn,spawn_with};
+    use crate::{ClosureRef,ClosureRefMut};
+
+    #[test]
+    #[allow(unsafe_code)]
+    fn test_failing_hook_fails_spawn() {
+        let deny:ClosureRef<i32,(),io::Result<()>> = closure!(ref code=1 => move || Err(io::Error::from_raw_os_error(*code)));
+        let mut command = Command::new("true");
+        // Safety: the hook only builds an io::Error from an os code.
+        unsafe { command.pre_exec(pre_exec_fn(deny)) };
+        assert_eq!(command.spawn().unwrap_err().raw_os_error(), Some(1));
+    }
+    #[test]
+    fn test_spawn_hook_keeps_state() {
+        let mut pids:ClosureRefMut<Vec<u32>,(u32,),usize> = closure!(ref mut seen=Vec::new() => move |pid| {seen.push(pid);seen.len()});
+        let mut command = Command::new("true");
+        for n in 1..=2 {
+            let (mut child, count) = spawn_with(&mut command, &mut pids).unwrap();
+            assert!(child.wait().unwrap().success());
+            assert_eq!(count, n);
+        }
+        assert!(spawn_with(&mut Command::new("/nonexistent"), &mut pids).is_err());
+        let seen = pids.state();
+        assert_eq!(seen.len(), 2);
+        assert_ne!(seen[0], seen[1]);
+    }
+}
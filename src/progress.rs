@@ -0,0 +1,192 @@
+//! A progress channel for long-running closures.
+//!
+//! A job that reports progress takes a sink as an extra first argument,
+//! instead of keeping one in its state: a closure over `(S, A, B)` is a job
+//! over `(A, B)` that reports through `S`. `WithProgress` binds the sink so
+//! that callers see the plain signature, and `IgnoreProgress` goes the other
+//! way, letting a closure that never reports run where a job is expected.
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// Somewhere to report progress values of type `P`.
+///
+/// Implemented by namable closures over `(P,)`, by `NoProgress` and, with the
+/// `std` feature, by `mpsc::Sender<P>`.
+pub trait ProgressSink<P> {
+    fn report(&mut self, progress: P);
+}
+impl<F, P> ProgressSink<P> for F
+where
+    F: StableFnMut<(P,), Output=()>
+{
+    fn report(&mut self, progress: P) {
+        self.stable_call_mut((progress,))
+    }
+}
+#[cfg(feature="std")]
+impl<P> ProgressSink<P> for std::sync::mpsc::Sender<P> {
+    /// Sends the progress, dropping it if the receiver is gone.
+    fn report(&mut self, progress: P) {
+        let _ = self.send(progress);
+    }
+}
+
+/// A sink that discards every report.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NoProgress;
+impl<P> ProgressSink<P> for NoProgress {
+    fn report(&mut self, _: P) {}
+}
+
+/// Calls `F` with a clone of the sink `S` before the other arguments.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")] {
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,ProgressSink,StableFn,WithProgress};
+/// # use std::sync::mpsc::{channel,Sender};
+/// let checksum:ClosureRef<(),(Sender<usize>,&[u8]),u32> = closure!(ref _s=() => move |progress, data| {
+///     let mut progress = progress;
+///     let mut sum = 0u32;
+///     for (i, chunk) in data.chunks(4).enumerate() {
+///         sum = chunk.iter().fold(sum, |s, &b| s.wrapping_add(u32::from(b)));
+///         progress.report((i + 1) * 4);
+///     }
+///     sum
+/// });
+/// let (tx, rx) = channel();
+/// let checksum = WithProgress::new(checksum, tx);
+/// assert_eq!(checksum.stable_call((&[1u8; 10][..],)), 10);
+/// drop(checksum);
+/// assert_eq!(rx.iter().collect::<Vec<_>>(), vec![4, 8, 12]);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct WithProgress<F, S> {
+    f: F,
+    sink: S,
+}
+impl<F, S> WithProgress<F, S> {
+    pub fn new(f: F, sink: S) -> Self {
+        Self { f, sink }
+    }
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+    pub fn into_inner(self) -> (F, S) {
+        (self.f, self.sink)
+    }
+}
+
+/// Calls `F` without the sink passed as the first argument.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,IgnoreProgress,NoProgress,StableFn};
+/// fn run_job<J: StableFn<(NoProgress,u32), Output=u32>>(job: J) -> u32 {
+///     job.stable_call((NoProgress, 20))
+/// }
+/// let quick:ClosureRef<u32,(u32,),u32> = closure!(ref n=1 => move |i| i+*n);
+/// assert_eq!(run_job(IgnoreProgress(quick)), 21);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct IgnoreProgress<F>(pub F);
+
+macro_rules! progress_arity {
+    ($($t:ident $a:ident),*) => {
+        impl<F, S, $($t),*> StableFnOnce<($($t,)*)> for WithProgress<F, S>
+        where
+            F: StableFnOnce<(S, $($t,)*)>
+        {
+            type Output = F::Output;
+            fn stable_call_once(self, ($($a,)*): ($($t,)*)) -> F::Output {
+                self.f.stable_call_once((self.sink, $($a,)*))
+            }
+        }
+        impl<F, S, $($t),*> StableFnMut<($($t,)*)> for WithProgress<F, S>
+        where
+            F: StableFnMut<(S, $($t,)*)>,
+            S: Clone
+        {
+            fn stable_call_mut(&mut self, ($($a,)*): ($($t,)*)) -> F::Output {
+                self.f.stable_call_mut((self.sink.clone(), $($a,)*))
+            }
+        }
+        impl<F, S, $($t),*> StableFn<($($t,)*)> for WithProgress<F, S>
+        where
+            F: StableFn<(S, $($t,)*)>,
+            S: Clone
+        {
+            fn stable_call(&self, ($($a,)*): ($($t,)*)) -> F::Output {
+                self.f.stable_call((self.sink.clone(), $($a,)*))
+            }
+        }
+        impl<F, S, $($t),*> StableFnOnce<(S, $($t,)*)> for IgnoreProgress<F>
+        where
+            F: StableFnOnce<($($t,)*)>
+        {
+            type Output = F::Output;
+            fn stable_call_once(self, (_, $($a,)*): (S, $($t,)*)) -> F::Output {
+                self.0.stable_call_once(($($a,)*))
+            }
+        }
+        impl<F, S, $($t),*> StableFnMut<(S, $($t,)*)> for IgnoreProgress<F>
+        where
+            F: StableFnMut<($($t,)*)>
+        {
+            fn stable_call_mut(&mut self, (_, $($a,)*): (S, $($t,)*)) -> F::Output {
+                self.0.stable_call_mut(($($a,)*))
+            }
+        }
+        impl<F, S, $($t),*> StableFn<(S, $($t,)*)> for IgnoreProgress<F>
+        where
+            F: StableFn<($($t,)*)>
+        {
+            fn stable_call(&self, (_, $($a,)*): (S, $($t,)*)) -> F::Output {
+                self.0.stable_call(($($a,)*))
+            }
+        }
+    };
+}
+progress_arity!();
+progress_arity!(A a);
+progress_arity!(A a, B b);
+progress_arity!(A a, B b, C c);
+progress_arity!(A a, B b, C c, D d);
+progress_arity!(A a, B b, C c, D d, E e);
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use crate::{ClosureRef,ClosureRefMut,IgnoreProgress,NoProgress,ProgressSink,StableFn,StableFnMut,WithProgress};
+
+    type Percent<'a> = ClosureRef<&'a Cell<u8>,(u8,),()>;
+
+    #[test]
+    fn test_sink_is_bound_and_stripped() {
+        let last = Cell::new(0);
+        let percent:Percent = closure!(ref c=&last => move |p| c.set(p));
+        let mut copy:ClosureRefMut<Vec<u8>,(Percent,&[u8]),usize> = closure!(ref mut out=vec![] => move |sink, data| {
+            let mut sink = sink;
+            for (i, &b) in data.iter().enumerate() {
+                out.push(b);
+                sink.report(((i + 1) * 100 / data.len()) as u8);
+            }
+            out.len()
+        });
+        let mut bound = WithProgress::new(&mut copy, percent);
+        assert_eq!(bound.stable_call_mut((&[1,2,3,4][..],)), 4);
+        assert_eq!(last.get(), 100);
+        // a job that never reports, run with a sink it ignores
+        let len:ClosureRef<(),(&[u8],),usize> = closure!(ref _s=() => move |data| data.len());
+        assert_eq!(IgnoreProgress(len).stable_call((percent, &[5][..])), 1);
+        assert_eq!(IgnoreProgress(len).stable_call((NoProgress, &[][..])), 0);
+    }
+}
@@ -0,0 +1,155 @@
+//! Python callbacks.
+//!
+//! `py_function` turns a namable closure into a Python callable: the closure
+//! is moved into a capsule owned by the resulting `builtin_function_or_method`
+//! and dropped with it. `PyCallback` goes the other way, wrapping a Python
+//! callable so that scripted callbacks can sit next to native ones behind
+//! `StableFn`.
+//!
+//! Only available with the `pyo3` feature.
+
+use std::ffi::CStr;
+use std::fmt;
+use std::marker::PhantomData;
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction,PyTuple};
+use pyo3::IntoPyObjectExt;
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// Wraps `f` as a Python function called with positional arguments.
+///
+/// The arguments are extracted into the `Input` tuple, so calling with the
+/// wrong number or types of arguments raises the extraction error. Keyword
+/// arguments raise `TypeError`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use namable_closures::closure;
+/// # use namable_closures::{py_function,ClosureRef};
+/// # use pyo3::prelude::*;
+/// let scale:ClosureRef<i64,(i64,),i64> = closure!(ref k=3 => move |x| x * *k);
+/// Python::with_gil(|py| {
+///     let f = py_function(py, Some(c"scale"), scale).unwrap();
+///     assert_eq!(f.call1((14,)).unwrap().extract::<i64>().unwrap(), 42);
+/// });
+/// ```
+pub fn py_function<'py, F, Input, Output>(py: Python<'py>, name: Option<&'static CStr>, f: F) -> PyResult<Bound<'py, PyCFunction>>
+where
+    F: StableFn<Input, Output=Output> + Send + 'static,
+    Input: for<'a> FromPyObject<'a>,
+    Output: for<'a> IntoPyObject<'a>
+{
+    PyCFunction::new_closure(py, name, None, move |args, kwargs| -> PyResult<PyObject> {
+        if kwargs.is_some_and(|kwargs| !kwargs.is_empty()) {
+            return Err(PyTypeError::new_err("keyword arguments are not supported"));
+        }
+        let py = args.py();
+        f.stable_call(args.extract()?).into_py_any(py)
+    })
+}
+
+/// A Python callable, called with the `Input` tuple as its positional
+/// arguments and its result extracted into `Output`.
+///
+/// Each call acquires the GIL. Errors raised by the callable, or while
+/// converting its result, are returned as `Err`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use namable_closures::{PyCallback,StableFn};
+/// # use pyo3::prelude::*;
+/// let len:PyCallback<(&str,),usize> = Python::with_gil(|py| {
+///     PyCallback::new(py.eval(c"len", None, None).unwrap().unbind())
+/// });
+/// assert_eq!(len.stable_call(("four",)).unwrap(), 4);
+/// ```
+pub struct PyCallback<Input, Output> {
+    f: Py<PyAny>,
+    t: PhantomData<fn(Input) -> Output>,
+}
+impl<Input, Output> PyCallback<Input, Output> {
+    pub fn new(f: Py<PyAny>) -> Self {
+        Self { f, t: PhantomData }
+    }
+    pub fn into_inner(self) -> Py<PyAny> {
+        self.f
+    }
+}
+impl<Input, Output> PyCallback<Input, Output>
+where
+    Input: for<'py> IntoPyObject<'py, Target=PyTuple>,
+    Output: for<'py> FromPyObject<'py>
+{
+    /// Calls the Python callable with `args`.
+    pub fn call(&self, args: Input) -> PyResult<Output> {
+        Python::with_gil(|py| self.f.bind(py).call1(args)?.extract())
+    }
+}
+impl<Input, Output> Clone for PyCallback<Input, Output> {
+    fn clone(&self) -> Self {
+        Python::with_gil(|py| Self::new(self.f.clone_ref(py)))
+    }
+}
+impl<Input, Output> fmt::Debug for PyCallback<Input, Output> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PyCallback").field("f", &self.f).finish()
+    }
+}
+impl<Input, Output> StableFnOnce<Input> for PyCallback<Input, Output>
+where
+    Input: for<'py> IntoPyObject<'py, Target=PyTuple>,
+    Output: for<'py> FromPyObject<'py>
+{
+    type Output = PyResult<Output>;
+    fn stable_call_once(self, args: Input) -> PyResult<Output> {
+        self.call(args)
+    }
+}
+impl<Input, Output> StableFnMut<Input> for PyCallback<Input, Output>
+where
+    Input: for<'py> IntoPyObject<'py, Target=PyTuple>,
+    Output: for<'py> FromPyObject<'py>
+{
+    fn stable_call_mut(&mut self, args: Input) -> PyResult<Output> {
+        self.call(args)
+    }
+}
+impl<Input, Output> StableFn<Input> for PyCallback<Input, Output>
+where
+    Input: for<'py> IntoPyObject<'py, Target=PyTuple>,
+    Output: for<'py> FromPyObject<'py>
+{
+    fn stable_call(&self, args: Input) -> PyResult<Output> {
+        self.call(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::prelude::*;
+    use pyo3::exceptions::{PyTypeError,PyValueError};
+    use crate::{py_function,ClosureRef,PyCallback,StableFn};
+
+    #[test]
+    fn test_round_trip() {
+        pyo3::prepare_freethreaded_python();
+        let join:ClosureRef<String,(String,i32),String> = closure!(ref sep=", ".to_string() => move |s,n| vec![s;n as usize].join(sep));
+        let f = Python::with_gil(|py| py_function(py, Some(c"join"), join).unwrap().into_any().unbind());
+        let back:PyCallback<(&str,i32),String> = PyCallback::new(f);
+        assert_eq!(back.stable_call(("ab", 3)).unwrap(), "ab, ab, ab");
+        let native:ClosureRef<(),(&str,i32),PyResult<String>> = ClosureRef::new(|_, (s, _)| Ok(s.to_uppercase()), ());
+        let table:[&dyn StableFn<(&str,i32),Output=PyResult<String>>;2] = [&back, &native];
+        assert_eq!(table.map(|f| f.stable_call(("ab", 1)).unwrap()), ["ab".to_string(), "AB".to_string()]);
+        Python::with_gil(|py| {
+            let f = back.clone().into_inner();
+            assert!(f.call1(py, ("ab", "x")).unwrap_err().is_instance_of::<PyTypeError>(py));
+            let raise:PyCallback<(&str,),i32> = PyCallback::new(py.eval(c"int", None, None).unwrap().unbind());
+            assert!(raise.stable_call(("x",)).unwrap_err().is_instance_of::<PyValueError>(py));
+        });
+    }
+}
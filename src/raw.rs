@@ -0,0 +1,51 @@
+//! The crate's only `unsafe` code.
+//!
+//! The rest of the crate is compiled with `deny(unsafe_code)`. Everything here
+//! exposes a safe, crate-private interface; keep it small and keep each
+//! `unsafe` block next to the invariant that justifies it. The integration
+//! tests in `tests/soundness.rs` exercise these paths and are meant to be run
+//! under Miri (`cargo +nightly miri test --test soundness`).
+
+use core::mem::ManuallyDrop;
+use core::ops::{Deref,DerefMut};
+
+/// A value that is passed by value to `on_drop` when dropped, unless it is
+/// taken out first with `into_inner`.
+///
+/// This is `Option<T>` plus a hook, without the discriminant.
+pub(crate) struct DropWith<T> {
+    value: ManuallyDrop<T>,
+    on_drop: fn(T),
+}
+impl<T> DropWith<T> {
+    pub(crate) fn new(value: T, on_drop: fn(T)) -> Self {
+        Self { value: ManuallyDrop::new(value), on_drop }
+    }
+    /// Takes the value out without running `on_drop`.
+    pub(crate) fn into_inner(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never dropped, so `Drop::drop` below cannot take
+        // the value a second time, and `this` is not used after this line.
+        unsafe { ManuallyDrop::take(&mut this.value) }
+    }
+}
+impl<T> Drop for DropWith<T> {
+    fn drop(&mut self) {
+        // SAFETY: `value` is only taken here and in `into_inner`, which
+        // prevents this destructor from running, and `self` is not used
+        // after the value is moved out.
+        let value = unsafe { ManuallyDrop::take(&mut self.value) };
+        (self.on_drop)(value)
+    }
+}
+impl<T> Deref for DropWith<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+impl<T> DerefMut for DropWith<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
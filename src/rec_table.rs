@@ -0,0 +1,135 @@
+//! Mutually recursive closures.
+//!
+//! A `ClosureRec` can call itself through the reference it is given. A
+//! `RecTable` does the same for `N` functions sharing one state and one
+//! signature: each is given the table and can call any entry by index. This
+//! is the shape of a recursive descent parser, with one entry per rule.
+
+use core::fmt;
+
+use crate::error::Error;
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// An entry of a `RecTable`, given the table it is called from.
+pub type RecFn<State, Input, Output, const N: usize> = fn(&RecTable<State, Input, Output, N>, Input) -> Output;
+
+/// `N` functions that call each other through the table they are given.
+///
+/// Called as a closure, the table runs its first entry.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::{RecTable,StableFn};
+/// // expr = term ('+' term)*, term = digit | '(' expr ')'
+/// const EXPR:usize = 0;
+/// const TERM:usize = 1;
+/// type Parser<'a> = RecTable<(),(&'a [u8],),Option<(u32,&'a [u8])>,2>;
+/// let parser:Parser = RecTable::new([
+///     |p, (s,)| {
+///         let (mut sum, mut s) = p.call(TERM, (s,))?;
+///         while let [b'+', rest @ ..] = s {
+///             let (n, rest) = p.call(TERM, (rest,))?;
+///             sum += n;
+///             s = rest;
+///         }
+///         Some((sum, s))
+///     },
+///     |p, (s,)| match s {
+///         [d @ b'0'..=b'9', rest @ ..] => Some((u32::from(d - b'0'), rest)),
+///         [b'(', rest @ ..] => match p.call(EXPR, (rest,))? {
+///             (n, [b')', rest @ ..]) => Some((n, rest)),
+///             _ => None,
+///         },
+///         _ => None,
+///     },
+/// ], ());
+/// assert_eq!(parser.stable_call((b"1+(2+3)+4",)), Some((10, &b""[..])));
+/// assert_eq!(parser.stable_call((b"(1+",)), None);
+/// ```
+pub struct RecTable<State, Input, Output, const N: usize> {
+    funcs: [RecFn<State, Input, Output, N>; N],
+    state: State,
+}
+impl<State, Input, Output, const N: usize> Copy for RecTable<State, Input, Output, N>
+where
+    State: Copy
+{}
+impl<State, Input, Output, const N: usize> Clone for RecTable<State, Input, Output, N>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { funcs: self.funcs, state: self.state.clone() }
+    }
+}
+impl<State, Input, Output, const N: usize> fmt::Debug for RecTable<State, Input, Output, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RecTable").field("len", &N).finish_non_exhaustive()
+    }
+}
+impl<State, Input, Output, const N: usize> RecTable<State, Input, Output, N> {
+    pub const fn new(funcs: [RecFn<State, Input, Output, N>; N], state: State) -> Self {
+        Self { funcs, state }
+    }
+    /// Calls entry `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than `N`.
+    pub fn call(&self, index: usize, i: Input) -> Output {
+        (self.funcs[index])(self, i)
+    }
+    /// Calls entry `index`, or returns `Error::OutOfBounds` if there is none.
+    pub fn try_call(&self, index: usize, i: Input) -> Result<Output, Error> {
+        let f = self.funcs.get(index).ok_or(Error::OutOfBounds)?;
+        Ok(f(self, i))
+    }
+}
+impl<State, Input, Output, const N: usize> ClosureState for RecTable<State, Input, Output, N> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.state
+    }
+}
+impl<State, Input, Output, const N: usize> ClosureStateMut for RecTable<State, Input, Output, N> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+}
+impl<State, Input, Output, const N: usize> StableFnOnce<Input> for RecTable<State, Input, Output, N> {
+    type Output = Output;
+    fn stable_call_once(self, i: Input) -> Output {
+        self.call(0, i)
+    }
+}
+impl<State, Input, Output, const N: usize> StableFnMut<Input> for RecTable<State, Input, Output, N> {
+    fn stable_call_mut(&mut self, i: Input) -> Output {
+        self.call(0, i)
+    }
+}
+impl<State, Input, Output, const N: usize> StableFn<Input> for RecTable<State, Input, Output, N> {
+    fn stable_call(&self, i: Input) -> Output {
+        self.call(0, i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureState,Error,RecTable,StableFn};
+
+    #[test]
+    fn test_entries_call_each_other() {
+        // even and odd, counting calls against a limit in the state
+        let parity:RecTable<u32,(u32,),Option<bool>,2> = RecTable::new([
+            |t, (n,)| if n > *t.state() { None } else if n == 0 { Some(true) } else { t.call(1, (n-1,)) },
+            |t, (n,)| if n == 0 { Some(false) } else { t.call(0, (n-1,)) },
+        ], 100);
+        assert_eq!(parity.stable_call((10,)), Some(true));
+        assert_eq!(parity.call(1, (7,)), Some(true));
+        assert_eq!(parity.stable_call((101,)), None);
+        assert_eq!(parity.try_call(2, (0,)), Err(Error::OutOfBounds));
+        assert_eq!(format!("{:?}", parity), "RecTable { len: 2, .. }");
+    }
+}
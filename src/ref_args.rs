@@ -0,0 +1,132 @@
+//! Converting between closures over borrowed and owned arguments.
+//!
+//! Libraries disagree on whether callbacks take their arguments by value or by
+//! reference. `Cloning` makes a closure over `(A, B)` callable with
+//! `(&A, &B)`, cloning the arguments, and `Borrowing` makes a closure over
+//! `(&A, &B)` callable with `(A, B)`, lending it the arguments.
+//!
+//! `Borrowing` lends borrows that only live for the call, so the closure must
+//! accept `(&A, &B)` for every lifetime. A `ClosureRef<State, (&'a A,), O>`
+//! names one lifetime and cannot be used; types with a function higher-ranked
+//! over the borrow, such as `EventHandler`, can.
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// Makes a closure over owned arguments callable with references, by
+/// cloning them.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,StableFn};
+/// # use namable_closures::ref_args::Cloning;
+/// let total:ClosureRef<(),(Vec<u32>,u32),u32> = closure!(ref _s=() => move |v, extra| v.into_iter().sum::<u32>() + extra);
+/// let items = vec![1, 2, 3];
+/// assert_eq!(Cloning(total).stable_call((&items, &4)), 10);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Cloning<F>(pub F);
+
+/// Makes a closure over borrowed arguments callable with owned ones, by
+/// lending them for the call.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use namable_closures::{EventHandler,StableFnMut};
+/// # use namable_closures::ref_args::Borrowing;
+/// let log = EventHandler::new(|lines:&mut Vec<String>, line:&String| lines.push(line.clone()), vec![]);
+/// let mut log = Borrowing(log);
+/// log.stable_call_mut((String::from("started"),));
+/// assert_eq!(log.0.into_inner(), ["started"]);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Borrowing<F>(pub F);
+
+macro_rules! ref_args_arity {
+    ($($t:ident $a:ident),+) => {
+        impl<'a, F, $($t),+> StableFnOnce<($(&'a $t,)+)> for Cloning<F>
+        where
+            F: StableFnOnce<($($t,)+)>,
+            $($t: Clone),+
+        {
+            type Output = F::Output;
+            fn stable_call_once(self, ($($a,)+): ($(&'a $t,)+)) -> F::Output {
+                self.0.stable_call_once(($($a.clone(),)+))
+            }
+        }
+        impl<'a, F, $($t),+> StableFnMut<($(&'a $t,)+)> for Cloning<F>
+        where
+            F: StableFnMut<($($t,)+)>,
+            $($t: Clone),+
+        {
+            fn stable_call_mut(&mut self, ($($a,)+): ($(&'a $t,)+)) -> F::Output {
+                self.0.stable_call_mut(($($a.clone(),)+))
+            }
+        }
+        impl<'a, F, $($t),+> StableFn<($(&'a $t,)+)> for Cloning<F>
+        where
+            F: StableFn<($($t,)+)>,
+            $($t: Clone),+
+        {
+            fn stable_call(&self, ($($a,)+): ($(&'a $t,)+)) -> F::Output {
+                self.0.stable_call(($($a.clone(),)+))
+            }
+        }
+        impl<F, O, $($t),+> StableFnOnce<($($t,)+)> for Borrowing<F>
+        where
+            F: for<'a> StableFnOnce<($(&'a $t,)+), Output=O>
+        {
+            type Output = O;
+            fn stable_call_once(self, ($($a,)+): ($($t,)+)) -> O {
+                self.0.stable_call_once(($(&$a,)+))
+            }
+        }
+        impl<F, O, $($t),+> StableFnMut<($($t,)+)> for Borrowing<F>
+        where
+            F: for<'a> StableFnMut<($(&'a $t,)+), Output=O>
+        {
+            fn stable_call_mut(&mut self, ($($a,)+): ($($t,)+)) -> O {
+                self.0.stable_call_mut(($(&$a,)+))
+            }
+        }
+        impl<F, O, $($t),+> StableFn<($($t,)+)> for Borrowing<F>
+        where
+            F: for<'a> StableFn<($(&'a $t,)+), Output=O>
+        {
+            fn stable_call(&self, ($($a,)+): ($($t,)+)) -> O {
+                self.0.stable_call(($(&$a,)+))
+            }
+        }
+    };
+}
+ref_args_arity!(A a);
+ref_args_arity!(A a, B b);
+ref_args_arity!(A a, B b, C c);
+ref_args_arity!(A a, B b, C c, D d);
+ref_args_arity!(A a, B b, C c, D d, E e);
+ref_args_arity!(A a, B b, C c, D d, E e, G g);
+
+#[cfg(all(test, feature="alloc"))]
+mod tests {
+    use crate::ref_args::{Borrowing,Cloning};
+    use crate::{ClosureRefMut,EventHandler,StableFnMut,StableFnOnce};
+
+    #[test]
+    fn test_round_trip() {
+        let push:ClosureRefMut<Vec<String>,(String,),usize> = closure!(ref mut v=vec![] => move |s| {v.push(s); v.len()});
+        let mut push = Cloning(push);
+        let s = String::from("a");
+        assert_eq!(push.stable_call_mut((&s,)), 1);
+        assert_eq!(push.stable_call_mut((&s,)), 2);
+        let seen = EventHandler::new(|n:&mut usize, s:&String| *n += s.len(), 0);
+        let mut seen = Borrowing(seen);
+        seen.stable_call_mut((String::from("abc"),));
+        assert_eq!(seen.0.into_inner(), 3);
+        // a round trip lends the argument and then clones it
+        assert_eq!(Borrowing(Cloning(push.0)).stable_call_once((s,)), 3);
+    }
+}
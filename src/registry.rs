@@ -0,0 +1,131 @@
+//! A program-wide table of closures declared with `static_closure!`.
+//!
+//! Registration happens at link time (through the `inventory` crate), so a
+//! callback declared in any module of any crate of the program is visible here
+//! without a registration call in `main`. Entries are looked up by name and
+//! closure type, or by name alone as a `Hook` for closures registered with
+//! `static_closure!(hook ...)`.
+//!
+//! Only available with the `registry` feature.
+
+use core::any::Any;
+use core::fmt;
+
+use crate::error::Error;
+use crate::hook::Hook;
+use crate::signature::SignatureId;
+use crate::stable_fn::StableFn;
+
+/// A closure submitted to the registry by `static_closure!(register ...)` or
+/// `static_closure!(hook ...)`.
+pub struct StaticClosure {
+    name: &'static str,
+    closure: &'static (dyn Any + Sync),
+    hook: Option<&'static (dyn Any + Sync)>,
+    signature: Option<fn() -> SignatureId>,
+}
+impl StaticClosure {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str, closure: &'static (dyn Any + Sync)) -> Self {
+        Self { name, closure, hook: None, signature: None }
+    }
+    /// `hook` points to a `fn(Input) -> Output` that calls the closure, and
+    /// `signature` returns the id of `Input -> Output`.
+    #[doc(hidden)]
+    pub const fn with_hook(name: &'static str, closure: &'static (dyn Any + Sync), hook: &'static (dyn Any + Sync), signature: fn() -> SignatureId) -> Self {
+        Self { name, closure, hook: Some(hook), signature: Some(signature) }
+    }
+    /// The name of the `static` the closure was declared as.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+    /// The closure, if it has type `C`.
+    pub fn downcast<C: Any>(&self) -> Option<&'static C> {
+        let closure:&'static dyn Any = self.closure;
+        closure.downcast_ref()
+    }
+    /// The closure as a `Hook`, if it was registered as one with this signature.
+    pub fn hook<Input: 'static, Output: 'static>(&self) -> Option<Hook<Input, Output>> {
+        let hook:&'static dyn Any = self.hook?;
+        hook.downcast_ref().map(|&f| Hook::new(self.name, f))
+    }
+    /// The signature of a closure registered as a hook, so that a host can
+    /// check what it loaded before calling it.
+    pub fn signature(&self) -> Option<SignatureId> {
+        self.signature.map(|signature| signature())
+    }
+    /// Calls a closure registered as a hook.
+    ///
+    /// Fails with `Error::UnknownHook` if the closure was not registered as a
+    /// hook, and with `Error::WrongSignature` if its signature is not
+    /// `Input -> Output`.
+    pub fn try_call<Input: 'static, Output: 'static>(&self, args: Input) -> Result<Output, Error> {
+        if self.hook.is_none() {
+            return Err(Error::UnknownHook);
+        }
+        self.hook().map(|h| h.stable_call(args)).ok_or(Error::WrongSignature)
+    }
+}
+impl fmt::Debug for StaticClosure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("StaticClosure").field(&self.name).finish()
+    }
+}
+
+inventory::collect!(StaticClosure);
+
+/// Iterates over every registered closure, in no particular order.
+pub fn entries() -> impl Iterator<Item=&'static StaticClosure> {
+    inventory::iter::<StaticClosure>.into_iter()
+}
+
+/// Iterates over the registered closures of type `C`, with their names.
+pub fn registered<C: Any>() -> impl Iterator<Item=(&'static str, &'static C)> {
+    entries().filter_map(|e| e.downcast().map(|c| (e.name, c)))
+}
+
+/// Finds the registered closure of type `C` with the given name.
+pub fn lookup<C: Any>(name: &str) -> Option<&'static C> {
+    registered().find(|&(n, _)| n == name).map(|(_, c)| c)
+}
+
+/// Finds the hook with the given name and signature.
+pub fn lookup_hook<Input: 'static, Output: 'static>(name: &str) -> Option<Hook<Input, Output>> {
+    entries().filter(|e| e.name == name).find_map(StaticClosure::hook)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::{entries,lookup,registered};
+    use crate::{ClosureRef,Error,SignatureId,StableFn};
+
+    static_closure!(register static DOUBLE: ClosureRef<i32,(i32,),i32> = ref k=2 => move |i| i**k);
+    static_closure!(register static TRIPLE: ClosureRef<i32,(i32,),i32> = ref k=3 => move |i| i**k);
+    static_closure!(register static SHOUT: ClosureRef<(),(&'static str,),usize> = ref _s=() => move |s| s.len());
+
+    #[test]
+    fn test_lookup_by_name_and_type() {
+        let double = lookup::<ClosureRef<i32,(i32,),i32>>("DOUBLE").unwrap();
+        assert_eq!(double.stable_call((5,)), 10);
+        assert!(lookup::<ClosureRef<i32,(i32,),i32>>("SHOUT").is_none());
+        let mut names:Vec<_> = registered::<ClosureRef<i32,(i32,),i32>>()
+            .map(|(n, c)| (n, c.stable_call((1,))))
+            .collect();
+        names.sort();
+        assert_eq!(names, vec![("DOUBLE",2),("TRIPLE",3)]);
+        assert_eq!(SHOUT.stable_call(("hey",)), 3);
+    }
+
+    static_closure!(hook static HALVE: ClosureRef<(),(i32,),i32> = ref _s=() => move |i| i/2);
+
+    #[test]
+    fn test_checked_calls() {
+        let halve = entries().find(|e| e.name() == "HALVE").unwrap();
+        assert_eq!(halve.signature(), Some(SignatureId::of::<(i32,),i32>()));
+        assert_eq!(halve.try_call::<(i32,),i32>((8,)), Ok(4));
+        assert_eq!(halve.try_call::<(i64,),i64>((8,)), Err(Error::WrongSignature));
+        let double = entries().find(|e| e.name() == "DOUBLE").unwrap();
+        assert_eq!(double.signature(), None);
+        assert_eq!(double.try_call::<(i32,),i32>((8,)), Err(Error::UnknownHook));
+    }
+}
@@ -0,0 +1,238 @@
+//! Calling a thread-affine closure from other threads.
+//!
+//! A closure over GUI state (or anything else that is not `Send`) has to stay on
+//! the thread that owns it. `RemoteOwner::new` splits such a closure into the
+//! owner, which stays on that thread and runs queued calls when its event loop
+//! asks it to, and a `RemoteClosure` handle that can be sent anywhere. Calling
+//! the handle queues the input and returns a `RemoteCall`, a future (and a
+//! blocking wait) for the output.
+//!
+//! Only available with the `std` feature.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{self,Receiver,Sender};
+use std::sync::{Arc,Condvar,Mutex,PoisonError};
+use std::task::{Context,Poll,Waker};
+
+use crate::error::Error;
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+struct Slot<Output> {
+    value: Option<Output>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+type Shared<Output> = Arc<(Mutex<Slot<Output>>, Condvar)>;
+
+// The sending half of a `RemoteCall`. Dropping it unanswered closes the call.
+pub(crate) struct Reply<Output> {
+    shared: Shared<Output>,
+}
+// A `RemoteCall` and the `Reply` that completes it.
+pub(crate) fn oneshot<Output>() -> (Reply<Output>, RemoteCall<Output>) {
+    let shared = Arc::new((Mutex::new(Slot { value: None, waker: None, closed: false }), Condvar::new()));
+    (Reply { shared: shared.clone() }, RemoteCall { shared })
+}
+impl<Output> Reply<Output> {
+    pub(crate) fn send(self, output: Output) {
+        self.shared.0.lock().unwrap_or_else(PoisonError::into_inner).value = Some(output);
+    }
+}
+impl<Output> Drop for Reply<Output> {
+    fn drop(&mut self) {
+        let (lock, ready) = &*self.shared;
+        let mut slot = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        slot.closed = true;
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+        ready.notify_all();
+    }
+}
+
+/// The pending result of a call through a `RemoteClosure`, or of a job
+/// submitted to a `JobPool`.
+///
+/// Resolves to `Err(Error::Disconnected)` if the owner was dropped, or the
+/// closure panicked, before answering.
+pub struct RemoteCall<Output> {
+    shared: Shared<Output>,
+}
+impl<Output> RemoteCall<Output> {
+    /// Blocks the current thread until the owner has answered.
+    pub fn wait(self) -> Result<Output, Error> {
+        let (lock, ready) = &*self.shared;
+        let mut slot = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        while !slot.closed {
+            slot = ready.wait(slot).unwrap_or_else(PoisonError::into_inner);
+        }
+        slot.value.take().ok_or(Error::Disconnected)
+    }
+}
+impl<Output> Future for RemoteCall<Output> {
+    type Output = Result<Output, Error>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut slot = self.shared.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if slot.closed {
+            Poll::Ready(slot.value.take().ok_or(Error::Disconnected))
+        } else {
+            slot.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+impl<Output> fmt::Debug for RemoteCall<Output> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RemoteCall").finish_non_exhaustive()
+    }
+}
+
+/// A handle that queues calls to a closure owned by another thread.
+///
+/// Calling it never runs the closure; it returns a `RemoteCall` that completes
+/// once the owner has served the call.
+pub struct RemoteClosure<Input, Output> {
+    tx: Sender<(Input, Reply<Output>)>,
+}
+impl<Input, Output> Clone for RemoteClosure<Input, Output> {
+    fn clone(&self) -> Self {
+        Self { tx: self.tx.clone() }
+    }
+}
+impl<Input, Output> RemoteClosure<Input, Output> {
+    /// Queues a call with `args`.
+    pub fn call(&self, args: Input) -> RemoteCall<Output> {
+        let (reply, call) = oneshot();
+        // if the owner is gone the reply is dropped here, closing the call
+        let _ = self.tx.send((args, reply));
+        call
+    }
+}
+impl<Input, Output> fmt::Debug for RemoteClosure<Input, Output> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RemoteClosure").finish_non_exhaustive()
+    }
+}
+impl<Input, Output> StableFnOnce<Input> for RemoteClosure<Input, Output> {
+    type Output = RemoteCall<Output>;
+    fn stable_call_once(self, args: Input) -> RemoteCall<Output> {
+        self.call(args)
+    }
+}
+impl<Input, Output> StableFnMut<Input> for RemoteClosure<Input, Output> {
+    fn stable_call_mut(&mut self, args: Input) -> RemoteCall<Output> {
+        self.call(args)
+    }
+}
+impl<Input, Output> StableFn<Input> for RemoteClosure<Input, Output> {
+    fn stable_call(&self, args: Input) -> RemoteCall<Output> {
+        self.call(args)
+    }
+}
+
+/// The owning side of a `RemoteClosure`, which runs the queued calls.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRefMut,RemoteOwner};
+/// # use std::rc::Rc;
+/// // Rc is not Send: the closure has to stay on this thread
+/// let label:ClosureRefMut<Rc<String>,(String,),usize> = closure!(ref mut text=Rc::new(String::new()) => move |s| {
+///     *text = Rc::new(s);
+///     text.len()
+/// });
+/// let (mut owner, remote) = RemoteOwner::new(label);
+/// let worker = std::thread::spawn(move || remote.call(("loaded".to_string(),)).wait());
+/// while owner.serve_blocking() {}
+/// assert_eq!(worker.join().unwrap(), Ok(6));
+/// ```
+pub struct RemoteOwner<F, Input, Output> {
+    f: F,
+    rx: Receiver<(Input, Reply<Output>)>,
+}
+impl<F, Input, Output> RemoteOwner<F, Input, Output>
+where
+    F: StableFnMut<Input, Output=Output>
+{
+    /// Splits `f` into its owner and a first handle.
+    pub fn new(f: F) -> (Self, RemoteClosure<Input, Output>) {
+        let (tx, rx) = mpsc::channel();
+        (Self { f, rx }, RemoteClosure { tx })
+    }
+    /// Runs every call queued so far and returns how many there were.
+    pub fn serve(&mut self) -> usize {
+        let mut served = 0;
+        while let Ok((args, reply)) = self.rx.try_recv() {
+            reply.send(self.f.stable_call_mut(args));
+            served += 1;
+        }
+        served
+    }
+    /// Waits for the next call and runs it.
+    ///
+    /// Returns `false` without waiting once every handle has been dropped and
+    /// the queue is empty.
+    pub fn serve_blocking(&mut self) -> bool {
+        match self.rx.recv() {
+            Ok((args, reply)) => {
+                reply.send(self.f.stable_call_mut(args));
+                true
+            }
+            Err(_) => false,
+        }
+    }
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
+impl<F, Input, Output> fmt::Debug for RemoteOwner<F, Input, Output> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RemoteOwner").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize,Ordering};
+    use std::task::{Context,Poll,Wake,Waker};
+    use crate::{ClosureRefMut,Error,RemoteOwner};
+
+    struct CountWakes(AtomicUsize);
+    impl Wake for CountWakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_future_is_woken_when_served() {
+        let add:ClosureRefMut<i32,(i32,),i32> = closure!(ref mut total=0 => move |i| {*total+=i;*total});
+        let (mut owner, remote) = RemoteOwner::new(add);
+        let wakes = Arc::new(CountWakes(AtomicUsize::new(0)));
+        let waker = Waker::from(wakes.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut first = remote.call((2,));
+        let second = remote.call((3,));
+        assert_eq!(Pin::new(&mut first).poll(&mut cx), Poll::Pending);
+        assert_eq!(owner.serve(), 2);
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+        assert_eq!(Pin::new(&mut first).poll(&mut cx), Poll::Ready(Ok(2)));
+        assert_eq!(second.wait(), Ok(5));
+    }
+    #[test]
+    fn test_dropped_owner_disconnects() {
+        let add:ClosureRefMut<i32,(i32,),i32> = closure!(ref mut total=0 => move |i| {*total+=i;*total});
+        let (owner, remote) = RemoteOwner::new(add);
+        let pending = remote.call((1,));
+        drop(owner);
+        assert_eq!(pending.wait(), Err(Error::Disconnected));
+        assert_eq!(remote.call((1,)).wait(), Err(Error::Disconnected));
+    }
+}
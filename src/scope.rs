@@ -0,0 +1,103 @@
+//! Scoped threads running namable closures.
+//!
+//! `Closure` and `ClosureMut` borrow their state, so they cannot be sent to a
+//! `thread::spawn`ed thread, but they are exactly what a scoped thread can
+//! run: `scope` wraps `std::thread::scope` and its `Scope::spawn` takes any
+//! namable closure that lives as long as the scope, with the borrows checked
+//! by the compiler.
+//!
+//! Only available with the `std` feature.
+
+use std::fmt;
+use std::thread::{self,ScopedJoinHandle};
+
+use crate::stable_fn::StableFnOnce;
+
+/// Spawns namable closures on scoped threads; see `scope`.
+pub struct Scope<'scope, 'env: 'scope> {
+    inner: &'scope thread::Scope<'scope, 'env>,
+}
+impl<'scope, 'env> Copy for Scope<'scope, 'env> {}
+impl<'scope, 'env> Clone for Scope<'scope, 'env> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Runs `f` with no arguments on a new scoped thread.
+    pub fn spawn<F>(&self, f: F) -> ScopedJoinHandle<'scope, F::Output>
+    where
+        F: StableFnOnce<()> + Send + 'scope,
+        F::Output: Send + 'scope
+    {
+        self.inner.spawn(move || f.stable_call_once(()))
+    }
+    /// Runs `f` with `args` on a new scoped thread.
+    pub fn spawn_with<F, Input>(&self, f: F, args: Input) -> ScopedJoinHandle<'scope, F::Output>
+    where
+        F: StableFnOnce<Input> + Send + 'scope,
+        F::Output: Send + 'scope,
+        Input: Send + 'scope
+    {
+        self.inner.spawn(move || f.stable_call_once(args))
+    }
+}
+impl<'scope, 'env> fmt::Debug for Scope<'scope, 'env> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Scope").finish_non_exhaustive()
+    }
+}
+
+/// Creates a scope for spawning namable closures that borrow local state.
+///
+/// All threads spawned in the scope are joined before `scope` returns; see
+/// `std::thread::scope`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{scope,ClosureMut};
+/// let mut left = vec![1,2,3];
+/// let mut right = vec![4,5,6];
+/// scope(|s| {
+///     for half in [&mut left, &mut right] {
+///         let double:ClosureMut<Vec<i32>,(),()> = closure!(mut v=half => || v.iter_mut().for_each(|i| *i*=2));
+///         s.spawn(double);
+///     }
+/// });
+/// assert_eq!((left, right), (vec![2,4,6], vec![8,10,12]));
+/// ```
+pub fn scope<'env, F, T>(f: F) -> T
+where
+    F: for<'scope> FnOnce(Scope<'scope, 'env>) -> T
+{
+    thread::scope(|s| f(Scope { inner: s }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize,Ordering};
+    use crate::{scope,Closure,ClosureMut};
+
+    #[test]
+    fn test_shared_and_exclusive_borrows() {
+        let words = ["alpha", "beta", "gamma"];
+        let total = AtomicUsize::new(0);
+        let shared = (&total, words);
+        let mut log = Vec::new();
+        let longest = scope(|s| {
+            let count:Closure<(&AtomicUsize,[&str;3]),(usize,),usize> = closure!(c=&shared => |i| {
+                c.0.fetch_add(c.1[i].len(), Ordering::SeqCst);
+                c.1[i].len()
+            });
+            let handles:Vec<_> = (0..3).map(|i| s.spawn_with(count, (i,))).collect();
+            let record:ClosureMut<Vec<&str>,(),()> = closure!(mut l=&mut log => || l.push("done"));
+            s.spawn(record).join().unwrap();
+            handles.into_iter().map(|h| h.join().unwrap()).max()
+        });
+        assert_eq!(longest, Some(5));
+        assert_eq!(total.load(Ordering::SeqCst), 14);
+        assert_eq!(log, vec!["done"]);
+    }
+}
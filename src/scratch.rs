@@ -0,0 +1,121 @@
+//! Closures that borrow a shared scratch buffer at call time.
+//!
+//! Callback graphs (audio or DSP chains, for instance) often need large
+//! temporary buffers. Keeping one per closure wastes memory, and passing the
+//! buffer through the input tuple makes every signature awkward. A
+//! `ScratchClosure` keeps its own state as usual and additionally receives a
+//! `&mut Scratch` that the caller shares between all closures of the graph.
+
+use crate::stable_fn::{StableFnMut,StableFnOnce};
+
+/// A closure over `&mut State` that also borrows a caller-provided `&mut Scratch`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::ScratchClosure;
+/// type Stage = ScratchClosure<Vec<f32>,f32,(f32,),f32>;
+/// let mut stages:Vec<Stage> = vec![
+///     ScratchClosure::new(|gain, buf, (x,)| { buf.push(x * *gain); x * *gain }, 2.0),
+///     ScratchClosure::new(|offset, buf, (x,)| { buf.push(x + *offset); x + *offset }, 1.0),
+/// ];
+/// let mut scratch = Vec::with_capacity(16);
+/// let out = stages.iter_mut().fold(3.0, |x, s| s.call_with_scratch(&mut scratch, (x,)));
+/// assert_eq!(out, 7.0);
+/// assert_eq!(scratch, vec![6.0, 7.0]);
+/// ```
+pub struct ScratchClosure<Scratch, State, Input, Output>
+where
+    Scratch: ?Sized
+{
+    f: fn(&mut State, &mut Scratch, Input) -> Output,
+    t: State,
+}
+impl<Scratch, State, Input, Output> Copy for ScratchClosure<Scratch, State, Input, Output>
+where
+    Scratch: ?Sized,
+    State: Copy
+{}
+impl<Scratch, State, Input, Output> Clone for ScratchClosure<Scratch, State, Input, Output>
+where
+    Scratch: ?Sized,
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<Scratch, State, Input, Output> ScratchClosure<Scratch, State, Input, Output>
+where
+    Scratch: ?Sized
+{
+    pub fn new(f: fn(&mut State, &mut Scratch, Input) -> Output, t: State) -> Self {
+        Self { f, t }
+    }
+    /// Calls the closure, lending it `scratch` for the duration of the call.
+    pub fn call_with_scratch(&mut self, scratch: &mut Scratch, i: Input) -> Output {
+        (self.f)(&mut self.t, scratch, i)
+    }
+    /// Binds `scratch` so the closure can be passed where a plain
+    /// `StableFnMut<Input>` is expected.
+    pub fn with_scratch<'s>(&'s mut self, scratch: &'s mut Scratch)
+        -> WithScratch<'s, Scratch, State, Input, Output>
+    {
+        WithScratch { closure: self, scratch }
+    }
+}
+
+/// A `ScratchClosure` together with the scratch buffer it is lent.
+pub struct WithScratch<'s, Scratch, State, Input, Output>
+where
+    Scratch: 's + ?Sized,
+    State: 's,
+    Input: 's,
+    Output: 's
+{
+    closure: &'s mut ScratchClosure<Scratch, State, Input, Output>,
+    scratch: &'s mut Scratch,
+}
+impl<'s, Scratch, State, Input, Output> StableFnOnce<Input> for WithScratch<'s, Scratch, State, Input, Output>
+where
+    Scratch: ?Sized
+{
+    type Output = Output;
+    fn stable_call_once(self, i: Input) -> Output {
+        self.closure.call_with_scratch(self.scratch, i)
+    }
+}
+impl<'s, Scratch, State, Input, Output> StableFnMut<Input> for WithScratch<'s, Scratch, State, Input, Output>
+where
+    Scratch: ?Sized
+{
+    fn stable_call_mut(&mut self, i: Input) -> Output {
+        self.closure.call_with_scratch(self.scratch, i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ScratchClosure,StableFnMut};
+
+    fn drive<F:StableFnMut<(usize,),Output=usize>>(mut f:F) -> usize {
+        f.stable_call_mut((1,)) + f.stable_call_mut((2,))
+    }
+
+    #[test]
+    fn test_shared_scratch() {
+        let mut fill:ScratchClosure<[u8],u8,(usize,),usize> = ScratchClosure::new(|v, buf, (n,)| {
+            for b in buf[..n].iter_mut() { *b = *v; }
+            *v += 1;
+            n
+        }, 7);
+        let mut count:ScratchClosure<[u8],u8,(usize,),usize> = ScratchClosure::new(|v, buf, (n,)| {
+            buf[..n].iter().filter(|b| **b == *v).count()
+        }, 7);
+        let mut scratch = [0u8; 8];
+        assert_eq!(fill.call_with_scratch(&mut scratch, (4,)), 4);
+        assert_eq!(count.call_with_scratch(&mut scratch, (8,)), 4);
+        assert_eq!(drive(fill.with_scratch(&mut scratch)), 3);
+        assert_eq!(scratch[..3], [9,9,7]);
+    }
+}
@@ -0,0 +1,142 @@
+//! Closures that hand over to a successor when called.
+//!
+//! A `SelfReplacingClosure` consumes its state on every call and returns the
+//! closure that should handle the next call, if any. This models handlers that
+//! change behaviour over time, such as a protocol handler that performs a
+//! handshake once and then switches to streaming. `ClosureChain` drives such a
+//! closure by installing each successor as it is returned.
+
+use crate::error::Error;
+use crate::stable_fn::{StableFnMut,StableFnOnce};
+
+/// The result of calling a `SelfReplacingClosure`: its output and its successor.
+pub type Handover<State, Input, Output> = (Output, Option<SelfReplacingClosure<State, Input, Output>>);
+
+/// A once-closure that returns its own successor alongside its output.
+///
+/// The successor shares the state type but may use a different function.
+pub struct SelfReplacingClosure<State, Input, Output> {
+    f: fn(State, Input) -> Handover<State, Input, Output>,
+    t: State,
+}
+impl<State, Input, Output> Copy for SelfReplacingClosure<State, Input, Output>
+where
+    State: Copy
+{}
+impl<State, Input, Output> Clone for SelfReplacingClosure<State, Input, Output>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<State, Input, Output> SelfReplacingClosure<State, Input, Output> {
+    pub fn new(f: fn(State, Input) -> Handover<State, Input, Output>, t: State) -> Self {
+        Self { f, t }
+    }
+}
+impl<State, Input, Output> StableFnOnce<Input> for SelfReplacingClosure<State, Input, Output> {
+    type Output = Handover<State, Input, Output>;
+    fn stable_call_once(self, i: Input) -> Self::Output {
+        let Self { f, t } = self;
+        f(t, i)
+    }
+}
+
+/// Drives a `SelfReplacingClosure`, replacing it with its successor after each call.
+///
+/// Once a call returns no successor, the chain is finished and further calls
+/// return `None`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::{ClosureChain,SelfReplacingClosure,StableFnMut};
+/// type Handler = SelfReplacingClosure<u32,(&'static str,),String>;
+/// fn stream(seq:u32, (data,):(&'static str,)) -> (String, Option<Handler>) {
+///     (format!("#{} {}", seq, data), Some(Handler::new(stream, seq+1)))
+/// }
+/// fn handshake(seq:u32, (hello,):(&'static str,)) -> (String, Option<Handler>) {
+///     (format!("welcome {}", hello), Some(Handler::new(stream, seq)))
+/// }
+/// let mut chain = ClosureChain::new(Handler::new(handshake, 0));
+/// assert_eq!(chain.stable_call_mut(("client",)), Some("welcome client".to_string()));
+/// assert_eq!(chain.stable_call_mut(("a",)), Some("#0 a".to_string()));
+/// assert_eq!(chain.stable_call_mut(("b",)), Some("#1 b".to_string()));
+/// ```
+pub struct ClosureChain<State, Input, Output> {
+    current: Option<SelfReplacingClosure<State, Input, Output>>,
+}
+impl<State, Input, Output> Clone for ClosureChain<State, Input, Output>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { current: self.current.clone() }
+    }
+}
+impl<State, Input, Output> ClosureChain<State, Input, Output> {
+    pub fn new(first: SelfReplacingClosure<State, Input, Output>) -> Self {
+        Self { current: Some(first) }
+    }
+    /// Returns `true` once a call has returned no successor.
+    pub fn is_finished(&self) -> bool {
+        self.current.is_none()
+    }
+    /// The closure that will handle the next call.
+    pub fn current(&self) -> Option<&SelfReplacingClosure<State, Input, Output>> {
+        self.current.as_ref()
+    }
+    /// Calls the current closure and installs its successor.
+    pub fn call(&mut self, i: Input) -> Option<Output> {
+        let (output, next) = self.current.take()?.stable_call_once(i);
+        self.current = next;
+        Some(output)
+    }
+    /// Like `call`, but reports a finished chain as `Error::Finished`.
+    pub fn try_call(&mut self, i: Input) -> Result<Output, Error> {
+        self.call(i).ok_or(Error::Finished)
+    }
+}
+impl<State, Input, Output> StableFnOnce<Input> for ClosureChain<State, Input, Output> {
+    type Output = Option<Output>;
+    fn stable_call_once(mut self, i: Input) -> Option<Output> {
+        self.call(i)
+    }
+}
+impl<State, Input, Output> StableFnMut<Input> for ClosureChain<State, Input, Output> {
+    fn stable_call_mut(&mut self, i: Input) -> Option<Output> {
+        self.call(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureChain,Error,SelfReplacingClosure,StableFnMut,StableFnOnce};
+
+    type Countdown = SelfReplacingClosure<u32,(),u32>;
+
+    fn countdown(n:u32, _:()) -> (u32, Option<Countdown>) {
+        if n == 0 { (0, None) } else { (n, Some(Countdown::new(countdown, n-1))) }
+    }
+
+    #[test]
+    fn test_successor() {
+        let c = Countdown::new(countdown, 2);
+        let (out, next) = c.stable_call_once(());
+        assert_eq!(out, 2);
+        let (out, next) = next.unwrap().stable_call_once(());
+        assert_eq!(out, 1);
+        assert!(next.is_some());
+    }
+    #[test]
+    fn test_chain_finishes() {
+        let mut chain = ClosureChain::new(Countdown::new(countdown, 2));
+        let outputs:Vec<_> = (0..5).map(|_| chain.stable_call_mut(())).collect();
+        assert_eq!(outputs, vec![Some(2),Some(1),Some(0),None,None]);
+        assert!(chain.is_finished());
+        assert!(chain.current().is_none());
+        assert_eq!(chain.try_call(()), Err(Error::Finished));
+    }
+}
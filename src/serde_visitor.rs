@@ -0,0 +1,229 @@
+//! Closures as `serde` visitors and seeds.
+//!
+//! Stateful deserialization (interning strings, resolving ids against a table)
+//! normally needs a one-off `Visitor` or `DeserializeSeed` struct. The types
+//! here are that struct once and for all: the context is the closure state,
+//! the behaviour is the function.
+//!
+//! Only available with the `serde` feature.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{Deserialize,DeserializeSeed,Deserializer,Error,SeqAccess,Visitor};
+
+use crate::stable_fn::StableFnOnce;
+
+/// A `DeserializeSeed` that deserializes a `T` and passes it to a closure.
+///
+/// The seed's value is the closure's output.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,ClosureSeed};
+/// use serde::de::{DeserializeSeed,IntoDeserializer,value::Error};
+/// let scale:ClosureRef<i32,(i32,),i32> = closure!(ref k=10 => move |i| i**k);
+/// let d = IntoDeserializer::<Error>::into_deserializer(4);
+/// assert_eq!(ClosureSeed::new(scale).deserialize(d), Ok(40));
+/// ```
+pub struct ClosureSeed<F, T> {
+    f: F,
+    _t: PhantomData<fn() -> T>,
+}
+impl<F, T> ClosureSeed<F, T> {
+    pub fn new(f: F) -> Self {
+        Self { f, _t: PhantomData }
+    }
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
+impl<'de, F, T> DeserializeSeed<'de> for ClosureSeed<F, T>
+where
+    T: Deserialize<'de>,
+    F: StableFnOnce<(T,)>
+{
+    type Value = F::Output;
+    fn deserialize<D>(self, d: D) -> Result<F::Output, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        T::deserialize(d).map(|t| self.f.stable_call_once((t,)))
+    }
+}
+
+/// A `Visitor` for strings, calling `f(state, s)` on the visited string.
+///
+/// Borrowed, transient and owned strings are all passed as `&str`, which is
+/// why the function takes the string at any lifetime instead of being one of
+/// the closure structs. It is also a `DeserializeSeed` that asks for a string.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::StrVisitor;
+/// use serde::de::{DeserializeSeed,IntoDeserializer,value::Error};
+/// let mut names = vec!["a".to_string()];
+/// let intern = StrVisitor::new(|names:&mut Vec<String>, s| {
+///     match names.iter().position(|n| n == s) {
+///         Some(i) => i,
+///         None => { names.push(s.to_string()); names.len() - 1 }
+///     }
+/// }, &mut names);
+/// let d = IntoDeserializer::<Error>::into_deserializer("b");
+/// assert_eq!(intern.deserialize(d), Ok(1));
+/// assert_eq!(names, ["a", "b"]);
+/// ```
+pub struct StrVisitor<State, Output> {
+    f: fn(State, &str) -> Output,
+    t: State,
+}
+impl<State, Output> StrVisitor<State, Output> {
+    pub fn new(f: fn(State, &str) -> Output, t: State) -> Self {
+        Self { f, t }
+    }
+}
+impl<State, Output> Copy for StrVisitor<State, Output>
+where
+    State: Copy
+{}
+impl<State, Output> Clone for StrVisitor<State, Output>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<'de, State, Output> Visitor<'de> for StrVisitor<State, Output> {
+    type Value = Output;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string")
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Output, E>
+    where
+        E: Error
+    {
+        Ok((self.f)(self.t, v))
+    }
+}
+impl<'de, State, Output> DeserializeSeed<'de> for StrVisitor<State, Output> {
+    type Value = Output;
+    fn deserialize<D>(self, d: D) -> Result<Output, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        d.deserialize_str(self)
+    }
+}
+
+/// A `Visitor` for sequences, folding every element into the state with
+/// `f(&mut state, element)`.
+///
+/// The visitor's value is the final state. It is also a `DeserializeSeed` that
+/// asks for a sequence.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")] {
+/// # use namable_closures::SeqVisitor;
+/// use serde::de::{DeserializeSeed,IntoDeserializer,value::Error};
+/// let sum = SeqVisitor::new(|acc:&mut (u32,u32), i:u32| { acc.0 += 1; acc.1 += i; }, (0,0));
+/// let d = IntoDeserializer::<Error>::into_deserializer(vec![1u32,2,3]);
+/// assert_eq!(sum.deserialize(d), Ok((3,6)));
+/// # }
+/// ```
+pub struct SeqVisitor<State, Element> {
+    f: fn(&mut State, Element),
+    t: State,
+}
+impl<State, Element> SeqVisitor<State, Element> {
+    pub fn new(f: fn(&mut State, Element), t: State) -> Self {
+        Self { f, t }
+    }
+}
+impl<State, Element> Copy for SeqVisitor<State, Element>
+where
+    State: Copy
+{}
+impl<State, Element> Clone for SeqVisitor<State, Element>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<'de, State, Element> Visitor<'de> for SeqVisitor<State, Element>
+where
+    Element: Deserialize<'de>
+{
+    type Value = State;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+    fn visit_seq<A>(mut self, mut seq: A) -> Result<State, A::Error>
+    where
+        A: SeqAccess<'de>
+    {
+        while let Some(e) = seq.next_element()? {
+            (self.f)(&mut self.t, e);
+        }
+        Ok(self.t)
+    }
+}
+impl<'de, State, Element> DeserializeSeed<'de> for SeqVisitor<State, Element>
+where
+    Element: Deserialize<'de>
+{
+    type Value = State;
+    fn deserialize<D>(self, d: D) -> Result<State, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        d.deserialize_seq(self)
+    }
+}
+
+#[cfg(all(test, feature="std"))]
+mod tests {
+    use std::collections::HashMap;
+    use serde::de::{DeserializeSeed,IntoDeserializer};
+    use serde::de::value::Error;
+    use crate::serde_visitor::{ClosureSeed,SeqVisitor,StrVisitor};
+    use crate::ClosureMut;
+
+    #[test]
+    fn test_seed_with_mutable_closure() {
+        let mut total = 0u32;
+        for i in 3u8..5 {
+            let add:ClosureMut<u32,(u8,),u32> = closure!(mut n=&mut total => |i| {*n+=i as u32;*n});
+            let d = IntoDeserializer::<Error>::into_deserializer(i);
+            assert_eq!(ClosureSeed::new(add).deserialize(d).map(|n| n > 0), Ok(true));
+        }
+        assert_eq!(total, 7);
+    }
+    #[test]
+    fn test_seq_of_strings_through_interner() {
+        let mut table:HashMap<String,usize> = HashMap::new();
+        {
+            let ids = SeqVisitor::new(|acc:&mut (&mut HashMap<String,usize>,Vec<usize>), s:String| {
+                let next = acc.0.len();
+                let id = *acc.0.entry(s).or_insert(next);
+                acc.1.push(id);
+            }, (&mut table, Vec::new()));
+            let d = IntoDeserializer::<Error>::into_deserializer(vec!["x","y","x"]);
+            assert_eq!(ids.deserialize(d).map(|t| t.1), Ok(vec![0,1,0]));
+        }
+        assert_eq!(table.len(), 2);
+    }
+    #[test]
+    fn test_str_visitor_rejects_numbers() {
+        let v = StrVisitor::new(|_:(), s| s.len(), ());
+        assert!(v.deserialize(IntoDeserializer::<Error>::into_deserializer(1u8)).is_err());
+        assert_eq!(v.deserialize(IntoDeserializer::<Error>::into_deserializer("abc")), Ok(3));
+    }
+}
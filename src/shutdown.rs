@@ -0,0 +1,151 @@
+//! Running finalizers at shutdown.
+//!
+//! `ShutdownHooks` collects once-closures of any type (flush the log, close
+//! the database, remove the PID file) and runs each of them once, latest
+//! first, when `run` is called. A hook can be given a time limit, so that one
+//! that hangs does not stop the others.
+//!
+//! Only available with the `std` feature.
+
+use std::fmt;
+use std::panic::{self,AssertUnwindSafe};
+use std::sync::{Mutex,PoisonError};
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::stable_fn::StableFnOnce;
+use crate::timeout::TimedOut;
+
+type Finalizer = Box<dyn FnOnce() -> Result<(), Error> + Send>;
+
+/// Finalizers run once, in reverse order of registration.
+///
+/// Hooks are added and run through a shared reference, so a `static` or an
+/// `Arc<ShutdownHooks>` can be reached from a signal-handling thread and from
+/// `main` alike. `run` takes the hooks out before calling them, so a second
+/// `run`, or one racing with it, does nothing more; hooks added afterwards
+/// wait for the next `run`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureOnce,ShutdownHooks};
+/// # use std::sync::mpsc::{channel,Sender};
+/// # use std::time::Duration;
+/// let (log, closed) = channel();
+/// let hooks = ShutdownHooks::new();
+/// let flush:ClosureOnce<Sender<&str>,(),()> = closure!(log=log.clone() => move || log.send("flushed").unwrap());
+/// let close:ClosureOnce<Sender<&str>,(),()> = closure!(log=log => move || log.send("closed").unwrap());
+/// hooks.add(flush);
+/// hooks.add_with_timeout(close, Duration::from_secs(1));
+/// assert_eq!(hooks.run(), Ok(()));
+/// assert_eq!(hooks.run(), Ok(()));
+/// assert_eq!(closed.iter().collect::<Vec<_>>(), ["closed", "flushed"]);
+/// ```
+pub struct ShutdownHooks {
+    hooks: Mutex<Vec<Finalizer>>,
+}
+impl Default for ShutdownHooks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl ShutdownHooks {
+    pub const fn new() -> Self {
+        Self { hooks: Mutex::new(Vec::new()) }
+    }
+    /// The number of hooks waiting to run.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Adds a hook, to run before the hooks already added.
+    pub fn add<C>(&self, hook: C)
+    where
+        C: StableFnOnce<(), Output=()> + Send + 'static
+    {
+        self.lock().push(Box::new(move || {
+            hook.stable_call_once(());
+            Ok(())
+        }));
+    }
+    /// Adds a hook that is given up on after `limit`, as with `TimedOut`.
+    pub fn add_with_timeout<C>(&self, hook: C, limit: Duration)
+    where
+        C: StableFnOnce<(), Output=()> + Send + 'static
+    {
+        self.lock().push(Box::new(move || TimedOut::new(hook, limit).stable_call_once(())));
+    }
+    /// Runs every hook added since the last `run`, latest first.
+    ///
+    /// Returns `Err(Error::Timeout)` if a hook timed out. The hooks after it
+    /// still run, and so do the hooks after one that panics; the first panic
+    /// is resumed once they are all done.
+    pub fn run(&self) -> Result<(), Error> {
+        let hooks = std::mem::take(&mut *self.lock());
+        let mut result = Ok(());
+        let mut panicked = None;
+        for hook in hooks.into_iter().rev() {
+            match panic::catch_unwind(AssertUnwindSafe(hook)) {
+                Ok(Ok(())) => {},
+                Ok(Err(e)) => result = result.and(Err(e)),
+                Err(payload) => {
+                    panicked.get_or_insert(payload);
+                },
+            }
+        }
+        if let Some(payload) = panicked {
+            panic::resume_unwind(payload);
+        }
+        result
+    }
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Finalizer>> {
+        self.hooks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+impl fmt::Debug for ShutdownHooks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ShutdownHooks").field("len", &self.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+    use std::sync::mpsc::{self,Receiver,Sender};
+    use std::time::Duration;
+    use crate::{ClosureOnce,Error,ShutdownHooks};
+
+    type Hook = ClosureOnce<(Sender<u32>,u32),(),()>;
+
+    fn hook(tx: &Sender<u32>, id: u32) -> Hook {
+        closure!(s=(tx.clone(),id) => move || s.0.send(s.1).unwrap())
+    }
+
+    #[test]
+    fn test_order_timeout_and_panic() {
+        let (tx, rx) = mpsc::channel();
+        let hooks = ShutdownHooks::new();
+        hooks.add(hook(&tx, 1));
+        let (_keep, hang) = mpsc::channel::<()>();
+        let hung:ClosureOnce<Receiver<()>,(),()> = closure!(rx=hang => move || { let _ = rx.recv(); });
+        hooks.add_with_timeout(hung, Duration::from_millis(20));
+        let boom:ClosureOnce<(),(),()> = closure!(_s=() => move || panic!("hook failed"));
+        hooks.add(boom);
+        hooks.add(hook(&tx, 2));
+        assert_eq!(hooks.len(), 4);
+        assert!(panic::catch_unwind(|| hooks.run()).is_err());
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), [2, 1]);
+        assert!(hooks.is_empty());
+        // a hook added after a run waits for the next one
+        hooks.add(hook(&tx, 3));
+        let (_keep, hang) = mpsc::channel::<()>();
+        hooks.add_with_timeout(closure!(rx=hang => move || { let _ = rx.recv(); }), Duration::from_millis(20));
+        assert_eq!(hooks.run(), Err(Error::Timeout));
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), [3]);
+        assert_eq!(hooks.run(), Ok(()));
+    }
+}
@@ -0,0 +1,156 @@
+//! Zero-cost markers for closure signatures.
+//!
+//! Generic containers usually want "any closure taking `Input` and returning
+//! `Output`", whichever of the closure structs (or adapters) it is. Bounding by
+//! `StableFn<Input, Output=Output>` excludes the mutating and consuming kinds,
+//! so `Signature<Input, Output>` is provided as the common bound: every
+//! `StableFnOnce` implementer has it, and the `Sig` marker lets the signature be
+//! passed around as a value. `SignatureId` is its run-time counterpart, for
+//! checking the signature of a type-erased closure before calling it.
+
+use core::any::{type_name,TypeId};
+use core::fmt;
+use core::hash::{Hash,Hasher};
+use core::marker::PhantomData;
+
+use crate::stable_fn::StableFnOnce;
+
+/// A zero-sized value standing for the signature `Input -> Output`.
+pub struct Sig<Input, Output>(PhantomData<fn(Input) -> Output>);
+impl<Input, Output> Sig<Input, Output> {
+    pub fn new() -> Self {
+        Sig(PhantomData)
+    }
+}
+impl<Input, Output> Default for Sig<Input, Output> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<Input, Output> Copy for Sig<Input, Output> {}
+impl<Input, Output> Clone for Sig<Input, Output> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<Input, Output> PartialEq for Sig<Input, Output> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+impl<Input, Output> Eq for Sig<Input, Output> {}
+impl<Input, Output> fmt::Debug for Sig<Input, Output> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Sig<{} -> {}>", ::core::any::type_name::<Input>(), ::core::any::type_name::<Output>())
+    }
+}
+impl<Input: 'static, Output: 'static> Sig<Input, Output> {
+    /// The run-time identity of the signature.
+    pub fn id(self) -> SignatureId {
+        SignatureId::of::<Input, Output>()
+    }
+}
+
+/// The `TypeId`s of a signature's input and output.
+///
+/// Compares equal for equal types; the type names are only for display.
+#[derive(Clone, Copy)]
+pub struct SignatureId {
+    input: TypeId,
+    output: TypeId,
+    names: (&'static str, &'static str),
+}
+impl SignatureId {
+    pub fn of<Input: 'static, Output: 'static>() -> Self {
+        Self {
+            input: TypeId::of::<Input>(),
+            output: TypeId::of::<Output>(),
+            names: (type_name::<Input>(), type_name::<Output>()),
+        }
+    }
+    pub fn input(&self) -> TypeId {
+        self.input
+    }
+    pub fn output(&self) -> TypeId {
+        self.output
+    }
+    /// Returns `true` if this is the signature `Input -> Output`.
+    pub fn is<Input: 'static, Output: 'static>(&self) -> bool {
+        *self == Self::of::<Input, Output>()
+    }
+}
+impl PartialEq for SignatureId {
+    fn eq(&self, other: &Self) -> bool {
+        (self.input, self.output) == (other.input, other.output)
+    }
+}
+impl Eq for SignatureId {}
+impl Hash for SignatureId {
+    fn hash<H: Hasher>(&self, h: &mut H) {
+        (self.input, self.output).hash(h)
+    }
+}
+impl fmt::Debug for SignatureId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SignatureId<{} -> {}>", self.names.0, self.names.1)
+    }
+}
+
+/// Implemented by every closure callable with `Input` and returning `Output`,
+/// whatever its ownership flavour.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureOnce,ClosureRef,Signature,StableFnOnce};
+/// struct Handlers<C: Signature<(i32,),i32>> { list: Vec<C> }
+/// impl<C: Signature<(i32,),i32>> Handlers<C> {
+///     fn run(self, i:i32) -> Vec<i32> {
+///         self.list.into_iter().map(|c| c.stable_call_once((i,))).collect()
+///     }
+/// }
+/// let by_ref:ClosureRef<i32,(i32,),i32> = closure!(ref n=1 => move |i| i+*n);
+/// let by_val:ClosureOnce<i32,(i32,),i32> = closure!(n=2 => move |i| i*n);
+/// assert_eq!(Handlers { list: vec![by_ref] }.run(5), vec![6]);
+/// assert_eq!(Handlers { list: vec![by_val] }.run(5), vec![10]);
+/// ```
+pub trait Signature<Input, Output>: StableFnOnce<Input, Output=Output> {
+    /// The signature marker of this closure.
+    fn signature(&self) -> Sig<Input, Output> {
+        Sig::new()
+    }
+}
+impl<F, Input> Signature<Input, F::Output> for F
+where
+    F: StableFnOnce<Input>
+{}
+
+#[cfg(test)]
+mod tests {
+    use crate::signature::{Sig,Signature,SignatureId};
+    use crate::{Closure,ClosureMut,ClosureOnce,ClosureRec,ClosureRef,ClosureRefMut};
+
+    fn sig_of<C: Signature<(u8,),u8>>(c:&C) -> Sig<(u8,),u8> {
+        c.signature()
+    }
+
+    #[test]
+    fn test_all_kinds_have_signature() {
+        let mut n = 1u8;
+        let a:Closure<u8,(u8,),u8> = Closure::new(|s, (i,)| i+*s, &1);
+        let b:ClosureRef<u8,(u8,),u8> = closure!(ref s=1 => move |i| i+*s);
+        let c:ClosureRefMut<u8,(u8,),u8> = closure!(ref mut s=1 => move |i| {*s+=i;*s});
+        let e:ClosureOnce<u8,(u8,),u8> = closure!(s=1 => move |i| i+s);
+        let f:ClosureRec<(),(u8,),u8> = ClosureRec::new(|_, (i,)| i, ());
+        assert_eq!(sig_of(&a), sig_of(&b));
+        sig_of(&c);
+        sig_of(&e);
+        sig_of(&f);
+        let d:ClosureMut<u8,(u8,),u8> = closure!(mut s=&mut n => |i| {*s+=i;*s});
+        assert_eq!(format!("{:?}", sig_of(&d)), "Sig<(u8,) -> u8>");
+        assert!(sig_of(&d).id().is::<(u8,),u8>());
+        assert_ne!(sig_of(&d).id(), SignatureId::of::<(u8,),u16>());
+        assert_eq!(format!("{:?}", sig_of(&d).id()), "SignatureId<(u8,) -> u8>");
+    }
+}
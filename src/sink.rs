@@ -0,0 +1,138 @@
+//! futures sinks.
+//!
+//! `SinkFromFn` is a `futures_sink::Sink` made of three function pointers over
+//! a state, so that a sink for allocation-free IO glue has a type that can be
+//! named, where `futures::sink::unfold` would give an anonymous one.
+//!
+//! Only available with the `futures` feature.
+
+use core::fmt;
+use core::pin::Pin;
+use core::task::{Context,Poll};
+
+use futures_sink::Sink;
+
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// A `Sink<Item>` whose readiness, send and flush are functions over `&mut
+/// State`.
+///
+/// Closing the sink flushes it.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")] {
+/// # use namable_closures::{block_on,ClosureState,SinkFromFn};
+/// # use futures_sink::Sink;
+/// # use std::future::poll_fn;
+/// # use std::pin::Pin;
+/// # use std::task::Poll;
+/// // a buffer of at most 2 lines, flushed to the output
+/// type Lines = SinkFromFn<(Vec<String>,Vec<String>),String,()>;
+/// let mut lines:Lines = SinkFromFn::new(
+///     |(buf, out), _| { if buf.len() == 2 { out.append(buf) } Poll::Ready(Ok(())) },
+///     |(buf, _), line| { buf.push(line); Ok(()) },
+///     |(buf, out), _| { out.append(buf); Poll::Ready(Ok(())) },
+///     (vec![], vec![]),
+/// );
+/// for line in ["a", "b", "c"] {
+///     block_on(poll_fn(|cx| Pin::new(&mut lines).poll_ready(cx))).unwrap();
+///     Pin::new(&mut lines).start_send(line.to_string()).unwrap();
+/// }
+/// assert_eq!(lines.state().1, ["a", "b"]);
+/// block_on(poll_fn(|cx| Pin::new(&mut lines).poll_close(cx))).unwrap();
+/// assert_eq!(lines.into_inner().1, ["a", "b", "c"]);
+/// # }
+/// ```
+pub struct SinkFromFn<State, Item, E> {
+    ready: fn(&mut State, &mut Context) -> Poll<Result<(), E>>,
+    send: fn(&mut State, Item) -> Result<(), E>,
+    flush: fn(&mut State, &mut Context) -> Poll<Result<(), E>>,
+    t: State,
+}
+// the state is never pinned, so it need not be `Unpin`
+impl<State, Item, E> Unpin for SinkFromFn<State, Item, E> {}
+impl<State, Item, E> SinkFromFn<State, Item, E> {
+    pub const fn new(
+        ready: fn(&mut State, &mut Context) -> Poll<Result<(), E>>,
+        send: fn(&mut State, Item) -> Result<(), E>,
+        flush: fn(&mut State, &mut Context) -> Poll<Result<(), E>>,
+        t: State,
+    ) -> Self {
+        Self { ready, send, flush, t }
+    }
+    pub fn into_inner(self) -> State {
+        self.t
+    }
+}
+impl<State, Item, E> fmt::Debug for SinkFromFn<State, Item, E>
+where
+    State: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SinkFromFn").field("state", &self.t).finish_non_exhaustive()
+    }
+}
+impl<State, Item, E> ClosureState for SinkFromFn<State, Item, E> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.t
+    }
+}
+impl<State, Item, E> ClosureStateMut for SinkFromFn<State, Item, E> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+}
+impl<State, Item, E> Sink<Item> for SinkFromFn<State, Item, E> {
+    type Error = E;
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), E>> {
+        let this = self.get_mut();
+        (this.ready)(&mut this.t, cx)
+    }
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), E> {
+        let this = self.get_mut();
+        (this.send)(&mut this.t, item)
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), E>> {
+        let this = self.get_mut();
+        (this.flush)(&mut this.t, cx)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), E>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[cfg(all(test, feature="std"))]
+mod tests {
+    use std::future::poll_fn;
+    use std::pin::Pin;
+    use std::task::Poll;
+    use futures_sink::Sink;
+    use crate::{block_on,ClosureState,SinkFromFn};
+
+    // accepts items until closed, then fails
+    type Gate = SinkFromFn<(bool,u32),u32,&'static str>;
+
+    fn send(sink: &mut Gate, i: u32) -> Result<(), &'static str> {
+        block_on(poll_fn(|cx| Pin::new(&mut *sink).poll_ready(cx)))?;
+        Pin::new(sink).start_send(i)
+    }
+
+    #[test]
+    fn test_sink_from_fn() {
+        let mut gate:Gate = SinkFromFn::new(
+            |(closed, _), _| Poll::Ready(if *closed { Err("closed") } else { Ok(()) }),
+            |(_, total), i| { *total += i; Ok(()) },
+            |(closed, _), _| { *closed = true; Poll::Ready(Ok(())) },
+            (false, 0),
+        );
+        assert_eq!(send(&mut gate, 3), Ok(()));
+        assert_eq!(send(&mut gate, 4), Ok(()));
+        assert_eq!(block_on(poll_fn(|cx| Pin::new(&mut gate).poll_close(cx))), Ok(()));
+        assert_eq!(send(&mut gate, 5), Err("closed"));
+        assert_eq!(gate.state(), &(true, 7));
+        assert_eq!(format!("{:?}", gate), "SinkFromFn { state: (true, 7), .. }");
+    }
+}
@@ -0,0 +1,88 @@
+//! State checkpoints for the owning closure types.
+//!
+//! A closure that owns its state (a reducer, an accumulator) can hand out a
+//! copy of that state as a `Snapshot` and later be rolled back to it, which is
+//! all an undo stack needs.
+
+#[cfg(feature="std")]
+use std::time::{Duration,Instant};
+
+use crate::closures::{ClosureOnce,ClosureRef,ClosureRefMut};
+use crate::closure_rec::{ClosureMutRec,ClosureOnceRec,ClosureRec};
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// A copy of a closure's state, taken by `snapshot()`.
+///
+/// With the `std` feature the snapshot also records when it was taken.
+#[derive(Clone, Debug)]
+pub struct Snapshot<State> {
+    state: State,
+    #[cfg(feature="std")]
+    taken_at: Instant,
+}
+impl<State> Snapshot<State> {
+    pub fn new(state: State) -> Self {
+        Self {
+            state,
+            #[cfg(feature="std")]
+            taken_at: Instant::now(),
+        }
+    }
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+    pub fn into_state(self) -> State {
+        self.state
+    }
+    #[cfg(feature="std")]
+    pub fn taken_at(&self) -> Instant {
+        self.taken_at
+    }
+    /// The time since the snapshot was taken.
+    #[cfg(feature="std")]
+    pub fn age(&self) -> Duration {
+        self.taken_at.elapsed()
+    }
+}
+
+macro_rules! snapshots {
+    ($($closure:ident),*) => {$(
+        impl<State, Input, Output> $closure<State, Input, Output>
+        where
+            State: Clone
+        {
+            /// Copies the current state into a `Snapshot`.
+            pub fn snapshot(&self) -> Snapshot<State> {
+                Snapshot::new(self.state().clone())
+            }
+            /// Replaces the state with the one saved in `snapshot`.
+            pub fn restore(&mut self, snapshot: Snapshot<State>) {
+                *self.state_mut() = snapshot.into_state();
+            }
+        }
+    )*};
+}
+snapshots!(ClosureRef, ClosureRefMut, ClosureOnce, ClosureRec, ClosureMutRec, ClosureOnceRec);
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureRefMut,StableFnMut};
+
+    #[test]
+    fn test_undo_redo() {
+        let mut push:ClosureRefMut<Vec<char>,(char,),usize> = closure!(ref mut doc=Vec::new() => move |c| {doc.push(c);doc.len()});
+        let mut undo = Vec::new();
+        for c in "abc".chars() {
+            undo.push(push.snapshot());
+            push.stable_call_mut((c,));
+        }
+        let redo = push.snapshot();
+        push.restore(undo.pop().unwrap());
+        push.restore(undo.pop().unwrap());
+        assert_eq!(push.stable_call_mut(('x',)), 2);
+        #[cfg(feature="std")]
+        assert!(redo.taken_at() <= push.snapshot().taken_at());
+        push.restore(redo);
+        assert_eq!(push.stable_call_mut(('d',)), 4);
+    }
+}
@@ -12,6 +12,98 @@ pub trait StableFn<Input>:StableFnMut<Input> {
     fn stable_call(&self,args:Input) -> Self::Output;
 }
 
+impl<Input, F> StableFnOnce<Input> for &F
+where
+    F: StableFn<Input> + ?Sized
+{
+    type Output = F::Output;
+    #[inline]
+    fn stable_call_once(self, args:Input) -> F::Output {
+        (*self).stable_call(args)
+    }
+}
+impl<Input, F> StableFnMut<Input> for &F
+where
+    F: StableFn<Input> + ?Sized
+{
+    #[inline]
+    fn stable_call_mut(&mut self, args:Input) -> F::Output {
+        (**self).stable_call(args)
+    }
+}
+impl<Input, F> StableFn<Input> for &F
+where
+    F: StableFn<Input> + ?Sized
+{
+    #[inline]
+    fn stable_call(&self, args:Input) -> F::Output {
+        (**self).stable_call(args)
+    }
+}
+impl<Input, F> StableFnOnce<Input> for &mut F
+where
+    F: StableFnMut<Input> + ?Sized
+{
+    type Output = F::Output;
+    #[inline]
+    fn stable_call_once(self, args:Input) -> F::Output {
+        (*self).stable_call_mut(args)
+    }
+}
+impl<Input, F> StableFnMut<Input> for &mut F
+where
+    F: StableFnMut<Input> + ?Sized
+{
+    #[inline]
+    fn stable_call_mut(&mut self, args:Input) -> F::Output {
+        (**self).stable_call_mut(args)
+    }
+}
+
+/// Calls `f` with a tuple of arguments.
+///
+/// Pass `&c` or `&mut c` to call a closure by reference or mutably without
+/// consuming it; trait objects work the same way.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,StableFn};
+/// # use namable_closures::stable_fn::{apply,apply2};
+/// let add:ClosureRef<i32,(i32,i32),i32> = closure!(ref s=1 => move |a,b| a+b+*s);
+/// assert_eq!(apply(&add, (1,2)), 4);
+/// let dyn_add:&dyn StableFn<(i32,i32),Output=i32> = &add;
+/// assert_eq!(apply2(dyn_add, 1, 2), 4);
+/// ```
+pub fn apply<F, Input>(f: F, args: Input) -> F::Output
+where
+    F: StableFnOnce<Input>
+{
+    f.stable_call_once(args)
+}
+
+macro_rules! apply_n {
+    ($($name:ident($($a:ident: $t:ident),*);)*) => {$(
+        /// Calls `f` with the arguments in order; see `apply`.
+        pub fn $name<F, $($t),*>(f: F, $($a: $t),*) -> F::Output
+        where
+            F: StableFnOnce<($($t,)*)>
+        {
+            f.stable_call_once(($($a,)*))
+        }
+    )*};
+}
+apply_n! {
+    apply0();
+    apply1(a: A);
+    apply2(a: A, b: B);
+    apply3(a: A, b: B, c: C);
+    apply4(a: A, b: B, c: C, d: D);
+    apply5(a: A, b: B, c: C, d: D, e: E);
+    apply6(a: A, b: B, c: C, d: D, e: E, g: G);
+}
+
 
 pub fn as_cloning_stable_fn<Input,Output>(f: impl StableFnOnce<Input,Output=Output> + Clone)
     -> impl StableFn<Input,Output=Output>
@@ -22,6 +114,7 @@ pub fn as_cloning_stable_fn<Input,Output>(f: impl StableFnOnce<Input,Output=Outp
         T: StableFnOnce<Input,Output=Output>
     {
         type Output = Output;
+        #[inline]
         fn stable_call_once(self, args:Input) -> Output {
             let Wrapper(t) = self;
             t.stable_call_once(args)
@@ -31,6 +124,7 @@ pub fn as_cloning_stable_fn<Input,Output>(f: impl StableFnOnce<Input,Output=Outp
     where
         T: StableFnOnce<Input,Output=Output> + Clone
     {
+        #[inline]
         fn stable_call_mut(&mut self, args:Input) -> Output {
             let Wrapper(t) = self;
             t.clone().stable_call_once(args)
@@ -40,6 +134,7 @@ pub fn as_cloning_stable_fn<Input,Output>(f: impl StableFnOnce<Input,Output=Outp
     where
         T: StableFnOnce<Input,Output=Output> + Clone
     {
+        #[inline]
         fn stable_call(&self, args:Input) -> Output {
             let Wrapper(t) = self;
             t.clone().stable_call_once(args)
@@ -50,13 +145,17 @@ pub fn as_cloning_stable_fn<Input,Output>(f: impl StableFnOnce<Input,Output=Outp
 #[cfg(feature="nightly")]
 pub fn as_cloning_fn<Input,Output>(f: impl FnOnce<Input,Output=Output> + Clone)
     -> impl Fn<Input,Output=Output>
+where
+    Input: core::marker::Tuple
 {
     struct Wrapper<T>(T);
     impl<Input,Output,T> FnOnce<Input> for Wrapper<T>
     where
+        Input: core::marker::Tuple,
         T: FnOnce<Input,Output=Output>
     {
         type Output = Output;
+        #[inline]
         extern "rust-call" fn call_once(self, args:Input) -> Output {
             let Wrapper(t) = self;
             t.call_once(args)
@@ -64,21 +163,44 @@ pub fn as_cloning_fn<Input,Output>(f: impl FnOnce<Input,Output=Output> + Clone)
     }
     impl<Input,Output,T> FnMut<Input> for Wrapper<T>
     where
+        Input: core::marker::Tuple,
         T: FnOnce<Input,Output=Output> + Clone
     {
+        #[inline]
         extern "rust-call" fn call_mut(&mut self, args:Input) -> Output {
             let Wrapper(t) = self;
             t.clone().call_once(args)
         }
     }
-    impl<Input,Output,T> StableFn<Input> for Wrapper<T>
+    impl<Input,Output,T> Fn<Input> for Wrapper<T>
     where
-        T: StableFnOnce<Input,Output=Output> + Clone
+        Input: core::marker::Tuple,
+        T: FnOnce<Input,Output=Output> + Clone
     {
+        #[inline]
         extern "rust-call" fn call(&self, args:Input) -> Output {
             let Wrapper(t) = self;
             t.clone().call_once(args)
         }
     }
     Wrapper(f)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stable_fn::{apply,apply0,apply1,apply3,StableFnMut};
+    use crate::{ClosureOnce,ClosureRefMut};
+
+    #[test]
+    fn test_apply_by_value_and_by_reference() {
+        let mut count:ClosureRefMut<i32,(),i32> = closure!(ref mut n=0 => move || {*n+=1;*n});
+        assert_eq!(apply0(&mut count), 1);
+        let dyn_count:&mut dyn StableFnMut<(),Output=i32> = &mut count;
+        assert_eq!(apply(dyn_count, ()), 2);
+        assert_eq!(apply0(count), 3);
+        let sum3:ClosureOnce<Vec<i32>,(i32,i32,i32),i32> = closure!(v=vec![] => move |a,b,c| v.into_iter().chain([a,b,c]).sum());
+        assert_eq!(apply3(sum3.clone(), 1, 2, 3), 6);
+        let mut len:ClosureRefMut<(),(&str,),usize> = closure!(ref mut _s=() => move |s| s.len());
+        assert_eq!(apply1(&mut len, "four"), 4);
+    }
+}
@@ -0,0 +1,18 @@
+//! Access to the state stored in a closure.
+//!
+//! Every closure struct keeps its state in a private field. These traits give
+//! generic code (containers in particular) a way to inspect or update that
+//! state without knowing which closure struct it holds.
+
+/// A closure whose state can be read.
+pub trait ClosureState {
+    type State: ?Sized;
+    fn state(&self) -> &Self::State;
+}
+
+/// A closure whose state can be modified in place.
+///
+/// Not implemented by `Closure`, which only borrows its state immutably.
+pub trait ClosureStateMut: ClosureState {
+    fn state_mut(&mut self) -> &mut Self::State;
+}
@@ -0,0 +1,79 @@
+//! One closure body for both the borrowing and the owning closure.
+//!
+//! `closure!(s=&x => ...)` and `closure!(ref s=x => move ...)` each take their
+//! own copy of the body. A type implementing `GenericStateFn` holds the body
+//! once, and `Closure::from_generic` and `ClosureRef::from_generic` build
+//! either closure from it.
+
+use core::borrow::Borrow;
+
+use crate::closures::{Closure,ClosureRef};
+
+/// A closure body that only reads its state, however the state is held.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::{Closure,ClosureRef,GenericStateFn,StableFn};
+/// # use std::borrow::Borrow;
+/// struct Greet;
+/// impl GenericStateFn<String, (&'static str,)> for Greet {
+///     type Output = String;
+///     fn call(name: impl Borrow<String>, (greeting,): (&'static str,)) -> String {
+///         format!("{}, {}!", greeting, name.borrow())
+///     }
+/// }
+/// let name = String::from("world");
+/// let borrowed:Closure<String,(&str,),String> = Closure::from_generic::<Greet>(&name);
+/// let owned:ClosureRef<String,(&str,),String> = ClosureRef::from_generic::<Greet>(name.clone());
+/// assert_eq!(borrowed.stable_call(("Hello",)), "Hello, world!");
+/// assert_eq!(owned.stable_call(("Hi",)), "Hi, world!");
+/// ```
+pub trait GenericStateFn<State, Input> {
+    type Output;
+    fn call(state: impl Borrow<State>, input: Input) -> Self::Output;
+}
+
+impl<'a, State, Input, Output> Closure<'a, State, Input, Output> {
+    /// Borrows `t` as the state of the body `G`.
+    pub fn from_generic<G>(t: &'a State) -> Self
+    where
+        G: GenericStateFn<State, Input, Output=Output>
+    {
+        Closure::new(|t, i| G::call(t, i), t)
+    }
+}
+impl<State, Input, Output> ClosureRef<State, Input, Output> {
+    /// Owns `t` as the state of the body `G`.
+    pub fn from_generic<G>(t: State) -> Self
+    where
+        G: GenericStateFn<State, Input, Output=Output>
+    {
+        ClosureRef::new(|t, i| G::call(t, i), t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::borrow::Borrow;
+    use crate::{Closure,ClosureRef,GenericStateFn,StableFn};
+
+    struct Count;
+    impl GenericStateFn<[i32;3], (i32,)> for Count {
+        type Output = usize;
+        fn call(state: impl Borrow<[i32;3]>, (x,): (i32,)) -> usize {
+            state.borrow().iter().filter(|&&i| i == x).count()
+        }
+    }
+
+    #[test]
+    fn test_same_body_borrowed_and_owned() {
+        let base = [1, 2, 1];
+        let borrowed:Closure<[i32;3],(i32,),usize> = Closure::from_generic::<Count>(&base);
+        let owned:ClosureRef<[i32;3],(i32,),usize> = ClosureRef::from_generic::<Count>(base);
+        assert_eq!(borrowed.stable_call((1,)), 2);
+        assert_eq!(owned.stable_call((2,)), 1);
+        // the body can also be called directly with the state by value
+        assert_eq!(Count::call(base, (3,)), 0);
+    }
+}
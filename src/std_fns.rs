@@ -0,0 +1,85 @@
+//! Ready-made namable closures for common operators.
+//!
+//! Each function returns a `ClosureRef` with `()` state, so the closures are
+//! zero-sized besides their function pointer and can be built in `const`
+//! context:
+//!
+//! ```rust
+//! # use namable_closures::{ClosureRef,StableFn};
+//! use namable_closures::std_fns;
+//! const SUM:ClosureRef<(),(i32,i32),i32> = std_fns::add();
+//! assert_eq!(SUM.stable_call((2,3)), 5);
+//! assert_eq!(std_fns::max().stable_call(("a","b")), "b");
+//! ```
+
+use core::cmp::{self,Ordering};
+use core::ops::{Add,Mul,Not};
+
+use crate::closures::ClosureRef;
+
+/// `a + b`.
+pub const fn add<T>() -> ClosureRef<(), (T, T), T::Output>
+where
+    T: Add
+{
+    ClosureRef::new(|_, (a, b)| a + b, ())
+}
+/// `a * b`.
+pub const fn mul<T>() -> ClosureRef<(), (T, T), T::Output>
+where
+    T: Mul
+{
+    ClosureRef::new(|_, (a, b)| a * b, ())
+}
+/// `cmp::min(a, b)`.
+pub const fn min<T>() -> ClosureRef<(), (T, T), T>
+where
+    T: Ord
+{
+    ClosureRef::new(|_, (a, b)| cmp::min(a, b), ())
+}
+/// `cmp::max(a, b)`.
+pub const fn max<T>() -> ClosureRef<(), (T, T), T>
+where
+    T: Ord
+{
+    ClosureRef::new(|_, (a, b)| cmp::max(a, b), ())
+}
+/// `a.cmp(&b)`; use `T = &U` to compare by reference.
+pub const fn cmp<T>() -> ClosureRef<(), (T, T), Ordering>
+where
+    T: Ord
+{
+    ClosureRef::new(|_, (a, b)| a.cmp(&b), ())
+}
+/// `!a`.
+pub const fn not<T>() -> ClosureRef<(), (T,), T::Output>
+where
+    T: Not
+{
+    ClosureRef::new(|_, (a,)| !a, ())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use std::mem;
+    use crate::std_fns;
+    use crate::{ClosureRef,StableFn};
+
+    #[test]
+    fn test_operators() {
+        let sum:ClosureRef<(),(i32,i32),i32> = std_fns::add();
+        assert_eq!(mem::size_of_val(&sum), mem::size_of::<fn()>());
+        assert_eq!((1..=4).map(|i| (i, i)).map(|p| sum.stable_call(p)).collect::<Vec<_>>(), vec![2,4,6,8]);
+        assert_eq!(std_fns::mul().stable_call((3u8, 4u8)), 12);
+        assert_eq!(std_fns::min().stable_call((3, -4)), -4);
+        assert!(!std_fns::not().stable_call((true,)));
+        assert_eq!(std_fns::not().stable_call((0b1010u8,)), 0b1111_0101);
+        let alphabetical = std_fns::cmp();
+        let mut words = vec!["pear", "fig", "apple"];
+        words.sort_by(|a, b| alphabetical.stable_call((*a, *b)));
+        assert_eq!(words, vec!["apple", "fig", "pear"]);
+        assert_eq!(std_fns::cmp().stable_call((&2, &1)), Ordering::Greater);
+    }
+}
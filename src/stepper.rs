@@ -0,0 +1,145 @@
+//! Running long tasks a few steps at a time.
+//!
+//! Incremental work (loading, garbage collection, path finding) is a closure
+//! that does one step per call and returns `ControlFlow::Continue(())` until it
+//! is done, then `ControlFlow::Break(done)`. `Stepper` drives such a closure
+//! from a frame loop, either for a fixed number of steps or, with the `std`
+//! feature, for a time budget.
+
+use core::ops::ControlFlow;
+#[cfg(feature="std")]
+use std::time::{Duration,Instant};
+
+use crate::stable_fn::StableFnMut;
+
+/// Drives a closure returning `ControlFlow<Done>` until it breaks.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRefMut,Stepper};
+/// # use std::ops::ControlFlow;
+/// let sum:ClosureRefMut<(u32,u32),(),ControlFlow<u32>> = closure!(ref mut s=(0,0) => move || {
+///     if s.0 == 10 { return ControlFlow::Break(s.1); }
+///     s.0 += 1;
+///     s.1 += s.0;
+///     ControlFlow::Continue(())
+/// });
+/// let mut task = Stepper::new(sum);
+/// assert_eq!(task.tick(4), None);
+/// assert_eq!(task.tick(4), None);
+/// assert_eq!(task.tick(4), Some(55));
+/// assert!(task.is_finished());
+/// assert_eq!(task.steps(), 11);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Stepper<F> {
+    f: F,
+    steps: u64,
+    finished: bool,
+}
+impl<F> Stepper<F> {
+    pub fn new(f: F) -> Self {
+        Self { f, steps: 0, finished: false }
+    }
+    /// Returns `true` once the closure has returned `Break`.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+    /// The number of times the closure has been called.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+    /// Calls the closure once, unless it has already finished.
+    pub fn step<Done>(&mut self) -> Option<ControlFlow<Done>>
+    where
+        F: StableFnMut<(), Output=ControlFlow<Done>>
+    {
+        if self.finished {
+            return None;
+        }
+        self.steps += 1;
+        let flow = self.f.stable_call_mut(());
+        self.finished = flow.is_break();
+        Some(flow)
+    }
+    /// Calls the closure up to `max_steps` times, returning its result if it
+    /// finishes during this tick.
+    pub fn tick<Done>(&mut self, max_steps: usize) -> Option<Done>
+    where
+        F: StableFnMut<(), Output=ControlFlow<Done>>
+    {
+        for _ in 0..max_steps {
+            if let ControlFlow::Break(done) = self.step()? {
+                return Some(done);
+            }
+        }
+        None
+    }
+    /// Calls the closure until it finishes or `budget` has elapsed.
+    ///
+    /// The clock is checked between steps, so a tick overruns the budget by at
+    /// most one step. At least one step is taken.
+    #[cfg(feature="std")]
+    pub fn tick_for<Done>(&mut self, budget: Duration) -> Option<Done>
+    where
+        F: StableFnMut<(), Output=ControlFlow<Done>>
+    {
+        let start = Instant::now();
+        loop {
+            if let ControlFlow::Break(done) = self.step()? {
+                return Some(done);
+            }
+            if start.elapsed() >= budget {
+                return None;
+            }
+        }
+    }
+    /// Calls the closure until it finishes, or returns `None` if it already
+    /// had.
+    pub fn run<Done>(&mut self) -> Option<Done>
+    where
+        F: StableFnMut<(), Output=ControlFlow<Done>>
+    {
+        loop {
+            if let ControlFlow::Break(done) = self.step()? {
+                return Some(done);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+    use crate::{ClosureRefMut,Stepper};
+
+    fn countdown(from: u32) -> ClosureRefMut<u32,(),ControlFlow<&'static str>> {
+        closure!(ref mut n=from => move || {
+            if *n == 0 { ControlFlow::Break("liftoff") } else { *n -= 1; ControlFlow::Continue(()) }
+        })
+    }
+
+    #[test]
+    fn test_finished_stepper_is_not_called_again() {
+        let mut task = Stepper::new(countdown(2));
+        assert_eq!(task.run(), Some("liftoff"));
+        assert_eq!(task.steps(), 3);
+        assert_eq!(task.step(), None);
+        assert_eq!(task.tick(10), None);
+        assert_eq!(task.steps(), 3);
+    }
+    #[cfg(feature="std")]
+    #[test]
+    fn test_time_budget() {
+        let mut task = Stepper::new(countdown(u32::MAX));
+        assert_eq!(task.tick_for(std::time::Duration::ZERO), None);
+        assert_eq!(task.steps(), 1);
+        let mut task = Stepper::new(countdown(3));
+        assert_eq!(task.tick_for(std::time::Duration::from_secs(60)), Some("liftoff"));
+    }
+}
@@ -0,0 +1,158 @@
+//! Algorithms selectable at run time.
+//!
+//! A compression level or a path finding heuristic is one of several closures
+//! of the same namable type, chosen by a key from configuration. `Strategy`
+//! keeps the alternatives under their keys and calls the selected one; the
+//! selection, and the alternatives themselves, can be changed while it is in
+//! use.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::error::Error;
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// Closures of type `C` under keys of type `K`, one of which is selected.
+///
+/// There is always a selected alternative: the first one until `select` picks
+/// another.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,StableFn,Strategy};
+/// type Heuristic = ClosureRef<u32,((i32,i32),(i32,i32)),u32>;
+/// let manhattan:Heuristic = closure!(ref w=1 => move |a, b| *w * (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)));
+/// let chebyshev:Heuristic = closure!(ref w=1 => move |a, b| *w * a.0.abs_diff(b.0).max(a.1.abs_diff(b.1)));
+/// let mut h = Strategy::new("manhattan", manhattan).with("chebyshev", chebyshev);
+/// assert_eq!(h.stable_call(((0,0),(3,4))), 7);
+/// h.select(&"chebyshev").unwrap();
+/// assert_eq!(h.stable_call(((0,0),(3,4))), 4);
+/// assert!(h.select(&"dijkstra").is_err());
+/// assert_eq!(*h.selected(), "chebyshev");
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Strategy<K, C> {
+    alternatives: Vec<(K, C)>,
+    selected: usize,
+}
+impl<K, C> Strategy<K, C> {
+    /// A strategy with one alternative, which is selected.
+    pub fn new(key: K, c: C) -> Self {
+        Self { alternatives: alloc::vec![(key, c)], selected: 0 }
+    }
+    /// The key of the selected alternative.
+    pub fn selected(&self) -> &K {
+        &self.alternatives[self.selected].0
+    }
+    /// The selected alternative.
+    pub fn current(&self) -> &C {
+        &self.alternatives[self.selected].1
+    }
+    pub fn current_mut(&mut self) -> &mut C {
+        &mut self.alternatives[self.selected].1
+    }
+    /// The keys of every alternative, in the order they were added.
+    pub fn keys(&self) -> impl Iterator<Item=&K> {
+        self.alternatives.iter().map(|(k, _)| k)
+    }
+}
+impl<K, C> Strategy<K, C>
+where
+    K: PartialEq
+{
+    /// Adds an alternative, replacing the one under the same key.
+    pub fn with(mut self, key: K, c: C) -> Self {
+        self.insert(key, c);
+        self
+    }
+    /// Adds an alternative, returning the one it replaced under the same key.
+    /// Replacing the selected alternative swaps the running algorithm.
+    pub fn insert(&mut self, key: K, c: C) -> Option<C> {
+        match self.alternatives.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, old)) => Some(core::mem::replace(old, c)),
+            None => {
+                self.alternatives.push((key, c));
+                None
+            }
+        }
+    }
+    pub fn get(&self, key: &K) -> Option<&C> {
+        self.alternatives.iter().find(|(k, _)| k == key).map(|(_, c)| c)
+    }
+    /// Selects the alternative under `key`, or reports `Error::MissingKey`
+    /// and keeps the current selection.
+    pub fn select(&mut self, key: &K) -> Result<(), Error> {
+        self.selected = self.alternatives.iter().position(|(k, _)| k == key).ok_or(Error::MissingKey)?;
+        Ok(())
+    }
+}
+impl<K, C> fmt::Debug for Strategy<K, C>
+where
+    K: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Strategy")
+            .field("selected", self.selected())
+            .field("keys", &self.alternatives.iter().map(|(k, _)| k).collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+impl<K, C, Input> StableFnOnce<Input> for Strategy<K, C>
+where
+    C: StableFnOnce<Input>
+{
+    type Output = C::Output;
+    fn stable_call_once(mut self, args: Input) -> C::Output {
+        self.alternatives.swap_remove(self.selected).1.stable_call_once(args)
+    }
+}
+impl<K, C, Input> StableFnMut<Input> for Strategy<K, C>
+where
+    C: StableFnMut<Input>
+{
+    fn stable_call_mut(&mut self, args: Input) -> C::Output {
+        self.current_mut().stable_call_mut(args)
+    }
+}
+impl<K, C, Input> StableFn<Input> for Strategy<K, C>
+where
+    C: StableFn<Input>
+{
+    fn stable_call(&self, args: Input) -> C::Output {
+        self.current().stable_call(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClosureRefMut,Error,StableFnMut,StableFnOnce,Strategy};
+
+    #[derive(Debug, PartialEq)]
+    enum Level { Fast, Best }
+
+    type Compress = ClosureRefMut<u32,(&'static [u8],),usize>;
+
+    #[test]
+    fn test_select_and_swap() {
+        let fast:Compress = closure!(ref mut runs=0 => move |data| {*runs+=1; data.len()});
+        let best:Compress = closure!(ref mut runs=0 => move |data| {*runs+=1; data.len()/2});
+        let mut level = Strategy::new(Level::Fast, fast).with(Level::Best, best);
+        assert_eq!(level.stable_call_mut((b"abcd",)), 4);
+        assert_eq!(level.select(&Level::Best), Ok(()));
+        assert_eq!(level.stable_call_mut((b"abcd",)), 2);
+        assert_eq!(*level.get(&Level::Fast).unwrap().state(), 1);
+        // swapping the selected alternative keeps it selected
+        let none:Compress = closure!(ref mut _runs=0 => move |_data| 0);
+        assert_eq!(level.insert(Level::Best, none).map(|c| *c.state()), Some(1));
+        assert_eq!(level.stable_call_mut((b"abcd",)), 0);
+        assert_eq!(format!("{:?}", level), "Strategy { selected: Best, keys: [Fast, Best], .. }");
+        assert_eq!(level.stable_call_once((b"ab",)), 0);
+        let mut only = Strategy::new(Level::Fast, fast);
+        assert_eq!(only.select(&Level::Best), Err(Error::MissingKey));
+        assert_eq!(only.keys().collect::<Vec<_>>(), [&Level::Fast]);
+    }
+}
@@ -0,0 +1,131 @@
+//! Calling a closure with a time limit.
+//!
+//! `TimedOut` runs its closure on a helper thread and stops waiting once the
+//! configured `Duration` has passed. A closure that hangs cannot be stopped
+//! from outside, so the helper thread is then left running in the background
+//! and its result, if it ever comes, is dropped. This keeps a plugin host
+//! responsive, but is not a way to cancel work.
+//!
+//! Only available with the `std` feature.
+
+use std::panic;
+use std::sync::mpsc::{self,RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// Calls `F` on a helper thread, giving up with `Error::Timeout` after `limit`.
+///
+/// The closure, its input and its output are moved across threads, so they
+/// must be `Send + 'static`. `TimedOut` can only be called by reference when
+/// `F` is `StableFn + Clone`: every call runs on a clone, which behaves like
+/// the original because a `StableFn` does not mutate its state. A panic in the
+/// closure is resumed on the calling thread.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,Error,StableFn,TimedOut};
+/// # use std::time::Duration;
+/// let sleep:ClosureRef<(),(u64,),u64> = closure!(ref _s=() => move |ms| {
+///     std::thread::sleep(Duration::from_millis(ms));
+///     ms
+/// });
+/// let sleep = TimedOut::new(sleep, Duration::from_millis(500));
+/// assert_eq!(sleep.stable_call((1,)), Ok(1));
+/// assert_eq!(sleep.stable_call((5_000,)), Err(Error::Timeout));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct TimedOut<F> {
+    f: F,
+    limit: Duration,
+}
+impl<F> TimedOut<F> {
+    pub fn new(f: F, limit: Duration) -> Self {
+        Self { f, limit }
+    }
+    pub fn limit(&self) -> Duration {
+        self.limit
+    }
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
+fn call_with_limit<F, Input>(f: F, args: Input, limit: Duration) -> Result<F::Output, Error>
+where
+    F: StableFnOnce<Input> + Send + 'static,
+    F::Output: Send + 'static,
+    Input: Send + 'static
+{
+    let (tx, rx) = mpsc::sync_channel(1);
+    let helper = thread::spawn(move || {
+        // the receiver is gone if the call timed out
+        let _ = tx.send(f.stable_call_once(args));
+    });
+    match rx.recv_timeout(limit) {
+        Ok(output) => Ok(output),
+        Err(RecvTimeoutError::Timeout) => Err(Error::Timeout),
+        Err(RecvTimeoutError::Disconnected) => match helper.join() {
+            Err(payload) => panic::resume_unwind(payload),
+            Ok(()) => unreachable!("helper thread exited without sending"),
+        },
+    }
+}
+impl<F, Input> StableFnOnce<Input> for TimedOut<F>
+where
+    F: StableFnOnce<Input> + Send + 'static,
+    F::Output: Send + 'static,
+    Input: Send + 'static
+{
+    type Output = Result<F::Output, Error>;
+    fn stable_call_once(self, args: Input) -> Self::Output {
+        call_with_limit(self.f, args, self.limit)
+    }
+}
+impl<F, Input> StableFnMut<Input> for TimedOut<F>
+where
+    F: StableFn<Input> + Clone + Send + 'static,
+    F::Output: Send + 'static,
+    Input: Send + 'static
+{
+    fn stable_call_mut(&mut self, args: Input) -> Self::Output {
+        call_with_limit(self.f.clone(), args, self.limit)
+    }
+}
+impl<F, Input> StableFn<Input> for TimedOut<F>
+where
+    F: StableFn<Input> + Clone + Send + 'static,
+    F::Output: Send + 'static,
+    Input: Send + 'static
+{
+    fn stable_call(&self, args: Input) -> Self::Output {
+        call_with_limit(self.f.clone(), args, self.limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use crate::{ClosureOnce,Error,StableFnOnce,TimedOut};
+
+    #[test]
+    fn test_hung_closure_times_out() {
+        let (tx, rx) = mpsc::channel::<()>();
+        let wait:ClosureOnce<mpsc::Receiver<()>,(),bool> = closure!(rx=rx => move || rx.recv().is_ok());
+        let timed = TimedOut::new(wait, Duration::from_millis(20));
+        assert_eq!(timed.limit(), Duration::from_millis(20));
+        assert_eq!(timed.stable_call_once(()), Err(Error::Timeout));
+        // release the abandoned helper thread
+        drop(tx);
+    }
+    #[test]
+    #[should_panic(expected = "inner failure")]
+    fn test_panic_is_resumed() {
+        let fail:ClosureOnce<(),(),()> = closure!(_s=() => move || panic!("inner failure"));
+        let _ = TimedOut::new(fail, Duration::from_secs(10)).stable_call_once(());
+    }
+}
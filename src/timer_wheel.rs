@@ -0,0 +1,142 @@
+//! Soft timers over namable callbacks.
+//!
+//! A `TimerWheel` keeps callbacks of one namable type ordered by deadline.
+//! The deadline type only needs `PartialOrd`, so it can be a
+//! `std::time::Instant`, a tick counter or a frame number, and a firmware or
+//! game loop calls `tick(now)` to run everything that is due.
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::stable_fn::StableFnOnce;
+
+/// Callbacks of type `C` scheduled at deadlines of type `D`.
+///
+/// Callbacks with equal deadlines run in the order they were scheduled.
+/// Deadlines are expected to be totally ordered, except that a deadline not
+/// comparable with itself (a NaN) is accepted and is never due.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureOnce,TimerWheel};
+/// # use std::cell::RefCell;
+/// let fired = RefCell::new(Vec::new());
+/// let mut timers:TimerWheel<u32,ClosureOnce<(&RefCell<Vec<&str>>,&str),(),()>> = TimerWheel::new();
+/// timers.schedule(30, closure!(s=(&fired,"blink") => move || s.0.borrow_mut().push(s.1)));
+/// timers.schedule(10, closure!(s=(&fired,"debounce") => move || s.0.borrow_mut().push(s.1)));
+/// assert_eq!(timers.tick(&5), 0);
+/// assert_eq!(timers.tick(&30), 2);
+/// assert_eq!(*fired.borrow(), ["debounce", "blink"]);
+/// ```
+pub struct TimerWheel<D, C> {
+    // Latest deadline first, so that due entries are popped from the end.
+    entries: Vec<(D, C)>,
+}
+impl<D, C> Default for TimerWheel<D, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<D, C> TimerWheel<D, C> {
+    pub fn new() -> Self {
+        Self { entries: Vec::new() }
+    }
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { entries: Vec::with_capacity(capacity) }
+    }
+    /// The number of callbacks waiting.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Removes every callback without running it.
+    pub fn clear(&mut self) {
+        self.entries.clear()
+    }
+    /// The earliest deadline, for example to decide how long to sleep.
+    pub fn next_deadline(&self) -> Option<&D> {
+        self.entries.last().map(|(d, _)| d)
+    }
+}
+impl<D, C> TimerWheel<D, C>
+where
+    D: PartialOrd
+{
+    /// Schedules `c` to run once `deadline` is reached.
+    pub fn schedule(&mut self, deadline: D, c: C) {
+        let index = if deadline.partial_cmp(&deadline).is_none() {
+            0
+        } else {
+            // incomparable entries sit at the front with the later ones
+            self.entries.partition_point(|(d, _)| !matches!(d.partial_cmp(&deadline), Some(Ordering::Less | Ordering::Equal)))
+        };
+        self.entries.insert(index, (deadline, c));
+    }
+    /// Removes the earliest callback if its deadline is at or before `now`.
+    ///
+    /// Use this instead of `tick` to reschedule or inspect callbacks as they
+    /// become due.
+    pub fn pop_due(&mut self, now: &D) -> Option<(D, C)> {
+        match self.entries.last() {
+            Some((d, _)) if d <= now => self.entries.pop(),
+            _ => None,
+        }
+    }
+    /// Runs every callback whose deadline is at or before `now`, earliest
+    /// first, and returns how many ran.
+    pub fn tick(&mut self, now: &D) -> usize
+    where
+        C: StableFnOnce<()>
+    {
+        let mut fired = 0;
+        while let Some((_, c)) = self.pop_due(now) {
+            c.stable_call_once(());
+            fired += 1;
+        }
+        fired
+    }
+}
+impl<D, C> fmt::Debug for TimerWheel<D, C>
+where
+    D: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TimerWheel")
+            .field("len", &self.len())
+            .field("next_deadline", &self.next_deadline())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use crate::{ClosureOnce,StableFnOnce,TimerWheel};
+
+    type Log<'a> = &'a RefCell<Vec<u32>>;
+
+    #[test]
+    fn test_order_and_reschedule() {
+        let log = RefCell::new(Vec::new());
+        let mut timers:TimerWheel<f64,ClosureOnce<(Log,u32),(),()>> = TimerWheel::new();
+        for (deadline, id) in [(2.0, 1), (1.0, 2), (2.0, 3), (f64::NAN, 4), (0.5, 5)] {
+            timers.schedule(deadline, closure!(s=(&log,id) => move || s.0.borrow_mut().push(s.1)));
+        }
+        assert_eq!(timers.next_deadline(), Some(&0.5));
+        assert_eq!(timers.tick(&1.0), 2);
+        // a periodic timer: run it and put it back one period later
+        let (deadline, c) = timers.pop_due(&2.0).unwrap();
+        c.stable_call_once(());
+        timers.schedule(deadline + 1.0, closure!(s=(&log,10) => move || s.0.borrow_mut().push(s.1)));
+        assert_eq!(timers.tick(&3.0), 2);
+        assert_eq!(*log.borrow(), vec![5,2,1,3,10]);
+        // the NaN deadline is never due
+        assert_eq!(timers.tick(&f64::INFINITY), 0);
+        assert_eq!(timers.len(), 1);
+    }
+}
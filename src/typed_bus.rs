@@ -0,0 +1,254 @@
+//! An event bus directed by the type of the event.
+//!
+//! An `EventEmitter` dispatches one event to one closure type. A `TypedBus`
+//! keeps an emitter per event type and per handler type, found through their
+//! `TypeId`s, so publishers and subscribers only have to agree on the event
+//! types. Handlers of the same type are still stored without boxing.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::any::{Any,TypeId};
+use core::fmt;
+use core::marker::PhantomData;
+
+use crate::closure_slab::SlabKey;
+use crate::event::EventEmitter;
+use crate::stable_fn::{StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// A handler for events of type `E`: a state plus a `fn(&mut State, &E)`.
+///
+/// A `ClosureRefMut<State, (&E,), ()>` fixes the lifetime of the event borrow
+/// in its type, so it cannot handle the borrows a `TypedBus` publishes.
+/// `EventHandler` is the equivalent namable closure, with a function that is
+/// higher-ranked over the borrow.
+pub struct EventHandler<State, E> {
+    f: fn(&mut State, &E),
+    t: State,
+}
+impl<State, E> Copy for EventHandler<State, E>
+where
+    State: Copy
+{}
+impl<State, E> Clone for EventHandler<State, E>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<State, E> EventHandler<State, E> {
+    pub fn new(f: fn(&mut State, &E), t: State) -> Self {
+        Self { f, t }
+    }
+    pub fn into_inner(self) -> State {
+        self.t
+    }
+}
+impl<State, E> fmt::Debug for EventHandler<State, E>
+where
+    State: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventHandler").field("state", &self.t).finish_non_exhaustive()
+    }
+}
+impl<State, E> ClosureState for EventHandler<State, E> {
+    type State = State;
+    fn state(&self) -> &State {
+        &self.t
+    }
+}
+impl<State, E> ClosureStateMut for EventHandler<State, E> {
+    fn state_mut(&mut self) -> &mut State {
+        &mut self.t
+    }
+}
+impl<'a, State, E> StableFnOnce<(&'a E,)> for EventHandler<State, E> {
+    type Output = ();
+    fn stable_call_once(mut self, (e,): (&'a E,)) {
+        (self.f)(&mut self.t, e)
+    }
+}
+impl<'a, State, E> StableFnMut<(&'a E,)> for EventHandler<State, E> {
+    fn stable_call_mut(&mut self, (e,): (&'a E,)) {
+        (self.f)(&mut self.t, e)
+    }
+}
+
+/// Identifies a handler subscribed to a `TypedBus`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BusKey {
+    event: TypeId,
+    handler: TypeId,
+    key: SlabKey,
+}
+
+/// An event bus with handlers registered per event type.
+///
+/// Any `'static` type can be an event. A handler for `E` is any `'static`
+/// namable closure callable with `(&E,)` for every lifetime of the borrow,
+/// such as an `EventHandler<State, E>`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::{EventHandler,TypedBus};
+/// # use std::cell::Cell;
+/// # use std::rc::Rc;
+/// struct Clicked { x: i32 }
+/// struct Closed;
+/// let clicks = Rc::new(Cell::new(0));
+/// let mut bus = TypedBus::new();
+/// bus.subscribe(EventHandler::new(|n:&mut Rc<Cell<i32>>, e:&Clicked| n.set(n.get()+e.x), clicks.clone()));
+/// let closed = bus.subscribe(EventHandler::new(|_:&mut (), _:&Closed| panic!("unsubscribed"), ()));
+/// bus.unsubscribe(closed);
+/// bus.publish(&Clicked { x: 3 });
+/// bus.publish(&Closed);
+/// bus.publish(&"nobody listens to strings");
+/// assert_eq!(clicks.get(), 3);
+/// ```
+#[derive(Default)]
+pub struct TypedBus {
+    // every emitter for one event type, one per handler type
+    buckets: BTreeMap<TypeId, Vec<Box<dyn Bucket>>>,
+}
+impl TypedBus {
+    pub fn new() -> Self {
+        Self { buckets: BTreeMap::new() }
+    }
+    /// The number of subscribed handlers, for all event types.
+    pub fn len(&self) -> usize {
+        self.buckets.values().flatten().map(|b| b.len()).sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// The number of handlers subscribed to events of type `E`.
+    pub fn subscribers<E>(&self) -> usize
+    where
+        E: 'static
+    {
+        self.buckets.get(&TypeId::of::<E>()).map_or(0, |b| b.iter().map(|b| b.len()).sum())
+    }
+    /// Adds a handler for events of type `E` and returns the key to
+    /// unsubscribe it.
+    pub fn subscribe<E, C>(&mut self, c: C) -> BusKey
+    where
+        E: 'static,
+        C: for<'a> StableFnMut<(&'a E,), Output=()> + 'static
+    {
+        let buckets = self.buckets.entry(TypeId::of::<E>()).or_default();
+        let key = if let Some(b) = buckets.iter().find_map(|b| b.as_any().downcast_ref::<TypedBucket<E, C>>()) {
+            b.0.subscribe(c)
+        } else {
+            let b = TypedBucket::<E, C>(EventEmitter::new(), PhantomData);
+            let key = b.0.subscribe(c);
+            buckets.push(Box::new(b));
+            key
+        };
+        BusKey { event: TypeId::of::<E>(), handler: TypeId::of::<C>(), key }
+    }
+    /// Removes a handler. Returns `false` if `key` was not subscribed.
+    pub fn unsubscribe(&mut self, key: BusKey) -> bool {
+        self.buckets.get(&key.event).into_iter().flatten()
+            .find(|b| b.handler() == key.handler)
+            .is_some_and(|b| b.unsubscribe(key.key))
+    }
+    /// Calls every handler subscribed to events of type `E`.
+    ///
+    /// Handlers of the same type are called in the order they subscribed, as
+    /// by `EventEmitter::emit`, and they may publish re-entrantly.
+    pub fn publish<E>(&self, event: &E)
+    where
+        E: 'static
+    {
+        for bucket in self.buckets.get(&TypeId::of::<E>()).into_iter().flatten() {
+            bucket.publish(event);
+        }
+    }
+}
+impl fmt::Debug for TypedBus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TypedBus")
+            .field("event_types", &self.buckets.len())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+// The emitter for one event type and one handler type, with both types erased.
+trait Bucket {
+    fn as_any(&self) -> &dyn Any;
+    fn handler(&self) -> TypeId;
+    fn len(&self) -> usize;
+    fn unsubscribe(&self, key: SlabKey) -> bool;
+    fn publish(&self, event: &dyn Any);
+}
+struct TypedBucket<E, C>(EventEmitter<C>, PhantomData<fn(&E)>);
+impl<E, C> Bucket for TypedBucket<E, C>
+where
+    E: 'static,
+    C: for<'a> StableFnMut<(&'a E,), Output=()> + 'static
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn handler(&self) -> TypeId {
+        TypeId::of::<C>()
+    }
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+    fn unsubscribe(&self, key: SlabKey) -> bool {
+        self.0.unsubscribe(key)
+    }
+    fn publish(&self, event: &dyn Any) {
+        if let Some(event) = event.downcast_ref::<E>() {
+            self.0.emit((event,))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::{EventHandler,StableFnMut,StableFnOnce,TypedBus};
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    // a handler type of its own, to share the bucket list with EventHandler
+    struct Upper(Log);
+    impl<'a> StableFnOnce<(&'a String,)> for Upper {
+        type Output = ();
+        fn stable_call_once(mut self, e: (&'a String,)) {
+            self.stable_call_mut(e)
+        }
+    }
+    impl<'a> StableFnMut<(&'a String,)> for Upper {
+        fn stable_call_mut(&mut self, (s,): (&'a String,)) {
+            self.0.borrow_mut().push(s.to_uppercase())
+        }
+    }
+
+    #[test]
+    fn test_buckets_per_event_and_handler_type() {
+        let log:Log = Rc::default();
+        let mut bus = TypedBus::new();
+        let lower = bus.subscribe(EventHandler::new(|l:&mut Log, s:&String| l.borrow_mut().push(s.clone()), log.clone()));
+        bus.subscribe(Upper(log.clone()));
+        bus.subscribe(EventHandler::new(|l:&mut Log, n:&u32| l.borrow_mut().push(n.to_string()), log.clone()));
+        assert_eq!(bus.subscribers::<String>(), 2);
+        assert_eq!(bus.len(), 3);
+        bus.publish(&String::from("a"));
+        bus.publish(&7u32);
+        bus.publish(&7u64);
+        assert!(bus.unsubscribe(lower));
+        assert!(!bus.unsubscribe(lower));
+        bus.publish(&String::from("b"));
+        assert_eq!(*log.borrow(), ["a", "A", "7", "B"]);
+    }
+}
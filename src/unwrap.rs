@@ -0,0 +1,113 @@
+//! Calling a closure and unwrapping its output in one step.
+//!
+//! Once-callbacks often return an `Option` or a `Result` that the caller only
+//! wants to unwrap. `CallUnwrap` does both, and its panic message names the
+//! closure's type as well as the error.
+
+use core::any;
+use core::fmt;
+
+use crate::stable_fn::StableFnOnce;
+
+/// An output holding either a value or an error: `Option<T>` or
+/// `Result<T, E>`.
+pub trait Fallible {
+    type Value;
+    type Error;
+    fn into_result(self) -> Result<Self::Value, Self::Error>;
+}
+impl<T> Fallible for Option<T> {
+    type Value = T;
+    type Error = NoneError;
+    fn into_result(self) -> Result<T, NoneError> {
+        self.ok_or(NoneError)
+    }
+}
+impl<T, E> Fallible for Result<T, E> {
+    type Value = T;
+    type Error = E;
+    fn into_result(self) -> Result<T, E> {
+        self
+    }
+}
+
+/// The error of an `Option` output that was `None`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NoneError;
+impl fmt::Debug for NoneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("None")
+    }
+}
+
+/// Calls a closure returning an `Option` or a `Result` and unwraps the output.
+///
+/// Implemented for every `StableFnOnce` with such an output. Pass `&c` or
+/// `&mut c` to keep the closure.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{CallUnwrap,ClosureOnce,ClosureRef};
+/// let parse:ClosureRef<(),(&str,),Result<i32,std::num::ParseIntError>> = closure!(ref _s=() => move |s| s.parse());
+/// assert_eq!((&parse).call_expect(("42",), "config value"), 42);
+/// assert_eq!((&parse).call_ok_or(("x",), "not a number"), Err("not a number"));
+/// let take:ClosureOnce<Vec<u8>,(),Option<u8>> = closure!(mut v=vec![1, 2] => move || v.pop());
+/// assert_eq!(take.call_expect((), "empty buffer"), 2);
+/// ```
+pub trait CallUnwrap<Input>: StableFnOnce<Input>
+where
+    Self::Output: Fallible
+{
+    /// Calls the closure, replacing a `None` or `Err` output with `Err(err)`.
+    fn call_ok_or<E>(self, args: Input, err: E) -> Result<<Self::Output as Fallible>::Value, E>
+    where
+        Self: Sized
+    {
+        self.stable_call_once(args).into_result().map_err(|_| err)
+    }
+    /// Calls the closure and unwraps the output.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the output is `None` or `Err`, with `msg`, the closure's
+    /// type and the error.
+    #[track_caller]
+    fn call_expect(self, args: Input, msg: &str) -> <Self::Output as Fallible>::Value
+    where
+        Self: Sized,
+        <Self::Output as Fallible>::Error: fmt::Debug
+    {
+        match self.stable_call_once(args).into_result() {
+            Ok(v) => v,
+            Err(e) => panic!("{} ({}): {:?}", msg, any::type_name::<Self>(), e),
+        }
+    }
+}
+impl<F, Input> CallUnwrap<Input> for F
+where
+    F: StableFnOnce<Input>,
+    F::Output: Fallible
+{}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CallUnwrap,ClosureOnce,ClosureRefMut};
+
+    #[test]
+    fn test_call_ok_or_and_expect() {
+        let mut next:ClosureRefMut<u32,(),Option<u32>> = closure!(ref mut n=0 => move || {*n+=1; (*n < 3).then_some(*n)});
+        assert_eq!((&mut next).call_expect((), "first"), 1);
+        assert_eq!((&mut next).call_ok_or((), "done"), Ok(2));
+        assert_eq!((&mut next).call_ok_or((), "done"), Err("done"));
+        let fail:ClosureOnce<(),(),Result<(),&str>> = closure!(_s=() => move || Err("disk full"));
+        let panic = std::panic::catch_unwind(|| fail.call_expect((), "saving")).unwrap_err();
+        let msg = panic.downcast_ref::<String>().unwrap();
+        assert!(msg.starts_with("saving (namable_closures::closures::ClosureOnce<"), "{}", msg);
+        assert!(msg.ends_with("): \"disk full\""), "{}", msg);
+        let none:ClosureOnce<(),(),Option<u8>> = closure!(_s=() => move || None);
+        let panic = std::panic::catch_unwind(|| none.call_expect((), "reading")).unwrap_err();
+        assert!(panic.downcast_ref::<String>().unwrap().ends_with("): None"));
+    }
+}
@@ -0,0 +1,170 @@
+//! Input validation in front of a namable closure.
+//!
+//! `Validated` pairs a closure with a validator over its input. Calls whose
+//! input the validator rejects return `Err(Rejected(input))` without reaching
+//! the closure, so boundary checks are written once, named, and reused instead
+//! of being repeated at the top of every callback body.
+//!
+//! A validator only looks at the input, so it receives `&Input` at whatever
+//! lifetime the call has. A `ClosureRef<State, (&Input,), bool>` fixes that
+//! lifetime in its type and cannot be used; `Predicate` is the equivalent
+//! namable closure whose function is higher-ranked over it.
+
+use core::fmt;
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// A check on the input of a closure.
+pub trait Validate<Input> {
+    fn validate(&self, input: &Input) -> bool;
+}
+impl<Input> Validate<Input> for fn(&Input) -> bool {
+    fn validate(&self, input: &Input) -> bool {
+        self(input)
+    }
+}
+
+/// A namable predicate: a state plus a `fn(&State, &Input) -> bool`.
+pub struct Predicate<State, Input> {
+    f: fn(&State, &Input) -> bool,
+    t: State,
+}
+impl<State, Input> Copy for Predicate<State, Input>
+where
+    State: Copy
+{}
+impl<State, Input> Clone for Predicate<State, Input>
+where
+    State: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<State, Input> Predicate<State, Input> {
+    pub const fn new(f: fn(&State, &Input) -> bool, t: State) -> Self {
+        Self { f, t }
+    }
+}
+impl<State, Input> Validate<Input> for Predicate<State, Input> {
+    fn validate(&self, input: &Input) -> bool {
+        (self.f)(&self.t, input)
+    }
+}
+
+/// The input of a call refused by a validator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rejected<Input>(pub Input);
+impl<Input> Rejected<Input> {
+    pub fn into_inner(self) -> Input {
+        self.0
+    }
+}
+impl<Input> fmt::Display for Rejected<Input> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("input rejected by validator")
+    }
+}
+impl<Input> core::error::Error for Rejected<Input>
+where
+    Input: fmt::Debug
+{}
+
+/// Calls `F` only with inputs that `V` accepts.
+///
+/// The output is `Result<F::Output, Rejected<Input>>`.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRefMut,StableFnMut};
+/// # use namable_closures::validated::{Predicate,Rejected,Validated};
+/// let in_range = Predicate::new(|max, &(i,):&(usize,)| i < *max, 3);
+/// let set:ClosureRefMut<[u8;3],(usize,),u8> = closure!(ref mut buf=[0;3] => move |i| {buf[i]+=1;buf[i]});
+/// let mut set = Validated::new(set, in_range);
+/// assert_eq!(set.stable_call_mut((2,)), Ok(1));
+/// assert_eq!(set.stable_call_mut((3,)), Err(Rejected((3,))));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Validated<F, V> {
+    f: F,
+    v: V,
+}
+impl<F, V> Validated<F, V> {
+    pub fn new(f: F, v: V) -> Self {
+        Self { f, v }
+    }
+    pub fn into_inner(self) -> (F, V) {
+        (self.f, self.v)
+    }
+}
+impl<F, V, Input> StableFnOnce<Input> for Validated<F, V>
+where
+    F: StableFnOnce<Input>,
+    V: Validate<Input>
+{
+    type Output = Result<F::Output, Rejected<Input>>;
+    fn stable_call_once(self, args: Input) -> Self::Output {
+        if self.v.validate(&args) {
+            Ok(self.f.stable_call_once(args))
+        } else {
+            Err(Rejected(args))
+        }
+    }
+}
+impl<F, V, Input> StableFnMut<Input> for Validated<F, V>
+where
+    F: StableFnMut<Input>,
+    V: Validate<Input>
+{
+    fn stable_call_mut(&mut self, args: Input) -> Self::Output {
+        if self.v.validate(&args) {
+            Ok(self.f.stable_call_mut(args))
+        } else {
+            Err(Rejected(args))
+        }
+    }
+}
+impl<F, V, Input> StableFn<Input> for Validated<F, V>
+where
+    F: StableFn<Input>,
+    V: Validate<Input>
+{
+    fn stable_call(&self, args: Input) -> Self::Output {
+        if self.v.validate(&args) {
+            Ok(self.f.stable_call(args))
+        } else {
+            Err(Rejected(args))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validated::{Predicate,Rejected,Validate,Validated};
+    use crate::{ClosureOnce,ClosureRef,StableFn,StableFnOnce};
+
+    fn non_empty((s,):&(String,)) -> bool {
+        !s.is_empty()
+    }
+
+    #[test]
+    fn test_rejected_input_is_returned() {
+        let greet:ClosureOnce<&str,(String,),String> = closure!(g="hello" => move |name| format!("{} {}", g, name));
+        let v = non_empty as fn(&(String,)) -> bool;
+        assert_eq!(Validated::new(greet, v).stable_call_once(("".to_string(),)), Err(Rejected(("".to_string(),))));
+        assert_eq!(Validated::new(greet, v).stable_call_once(("bob".to_string(),)), Ok("hello bob".to_string()));
+    }
+    #[test]
+    fn test_shared_predicate() {
+        let even = Predicate::new(|m, &(i,):&(i32,)| i % *m == 0, 2);
+        assert!(even.validate(&(4,)));
+        let half:ClosureRef<i32,(i32,),i32> = closure!(ref d=2 => move |i| i / *d);
+        let third:ClosureRef<i32,(i32,),i32> = closure!(ref d=3 => move |i| i / *d);
+        let (half, third) = (Validated::new(half, even), Validated::new(third, even));
+        assert_eq!(half.stable_call((6,)), Ok(3));
+        assert_eq!(third.stable_call((6,)), Ok(2));
+        assert_eq!(third.stable_call((9,)).map_err(Rejected::into_inner), Err((9,)));
+    }
+}
@@ -0,0 +1,141 @@
+//! Memoizing the output of a closure until its state changes.
+//!
+//! A derived value (a "selector" in UI terms) is a closure over some state
+//! that takes no arguments. `VersionedCache` keeps its last output together
+//! with the version of the state it was computed from, and calls the closure
+//! again only after the version has been bumped, either explicitly with
+//! `invalidate` or implicitly by mutating the state through `state_mut`.
+
+use core::fmt;
+
+use crate::stable_fn::{StableFnMut,StableFnOnce};
+use crate::state::{ClosureState,ClosureStateMut};
+
+/// Caches the output of `F` per state version.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::closure;
+/// # use namable_closures::{ClosureRef,ClosureStateMut,VersionedCache};
+/// let total:ClosureRef<Vec<u32>,(),u32> = closure!(ref items=vec![1,2] => move || items.iter().sum());
+/// let mut total = VersionedCache::new(total);
+/// assert_eq!(*total.get(), 3);
+/// assert_eq!(total.peek(), Some(&3));
+/// total.state_mut().push(3);
+/// assert_eq!(total.peek(), None);
+/// assert_eq!(*total.get(), 6);
+/// ```
+pub struct VersionedCache<F>
+where
+    F: StableFnOnce<()>
+{
+    f: F,
+    version: u64,
+    cached: Option<(u64, F::Output)>,
+}
+impl<F> VersionedCache<F>
+where
+    F: StableFnOnce<()>
+{
+    pub fn new(f: F) -> Self {
+        Self { f, version: 0, cached: None }
+    }
+    /// The current state version, bumped by `invalidate` and `state_mut`.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+    /// Marks the cached output as stale.
+    pub fn invalidate(&mut self) {
+        self.version = self.version.wrapping_add(1);
+    }
+    /// The cached output, if it was computed from the current state version.
+    pub fn peek(&self) -> Option<&F::Output> {
+        match self.cached {
+            Some((version, ref output)) if version == self.version => Some(output),
+            _ => None,
+        }
+    }
+    /// The output for the current state version, calling the closure if the
+    /// cached one is stale.
+    pub fn get(&mut self) -> &F::Output
+    where
+        F: StableFnMut<()>
+    {
+        if self.peek().is_none() {
+            self.cached = Some((self.version, self.f.stable_call_mut(())));
+        }
+        match self.cached {
+            Some((_, ref output)) => output,
+            None => unreachable!(),
+        }
+    }
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
+impl<F> Clone for VersionedCache<F>
+where
+    F: StableFnOnce<()> + Clone,
+    F::Output: Clone
+{
+    fn clone(&self) -> Self {
+        Self { f: self.f.clone(), version: self.version, cached: self.cached.clone() }
+    }
+}
+impl<F> fmt::Debug for VersionedCache<F>
+where
+    F: StableFnOnce<()>,
+    F::Output: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VersionedCache")
+            .field("version", &self.version)
+            .field("cached", &self.peek())
+            .finish()
+    }
+}
+impl<F> ClosureState for VersionedCache<F>
+where
+    F: StableFnOnce<()> + ClosureState
+{
+    type State = F::State;
+    fn state(&self) -> &F::State {
+        self.f.state()
+    }
+}
+impl<F> ClosureStateMut for VersionedCache<F>
+where
+    F: StableFnOnce<()> + ClosureStateMut
+{
+    /// Borrows the state mutably and invalidates the cached output.
+    fn state_mut(&mut self) -> &mut F::State {
+        self.invalidate();
+        self.f.state_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use crate::{ClosureRef,VersionedCache};
+
+    #[test]
+    fn test_recomputes_only_when_stale() {
+        let calls = Cell::new(0);
+        let len:ClosureRef<(&Cell<i32>,&str),(),usize> = closure!(ref s=(&calls,"abc") => move || {
+            s.0.set(s.0.get()+1);
+            s.1.len()
+        });
+        let mut len = VersionedCache::new(len);
+        assert_eq!(len.peek(), None);
+        assert_eq!(*len.get(), 3);
+        assert_eq!(*len.get(), 3);
+        assert_eq!(calls.get(), 1);
+        len.invalidate();
+        assert_eq!(len.version(), 1);
+        assert_eq!(*len.get(), 3);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(format!("{:?}", len), "VersionedCache { version: 1, cached: Some(3) }");
+    }
+}
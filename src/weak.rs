@@ -0,0 +1,77 @@
+//! Closures that do not keep their state alive.
+//!
+//! A handler subscribed on behalf of some object should not outlive it, but a
+//! `ClosureRef` holding an `Rc` to the object keeps it alive for as long as the
+//! subscription exists. `WeakClosure` holds a `Weak` instead: once every `Rc`
+//! is dropped the closure does nothing, and `EventEmitter::sweep` removes it.
+
+use alloc::rc::{Rc,Weak};
+use core::fmt;
+
+use crate::stable_fn::{StableFn,StableFnMut,StableFnOnce};
+
+/// A namable closure over state owned by someone else, through a `Weak`.
+///
+/// Calling it returns `None` once the state has been dropped.
+///
+/// # Example
+///
+/// ```rust
+/// # use namable_closures::{StableFn,WeakClosure};
+/// # use std::cell::Cell;
+/// # use std::rc::Rc;
+/// let clicks = Rc::new(Cell::new(0));
+/// let on_click = WeakClosure::new(|n:&Cell<i32>, ()| n.set(n.get()+1), &clicks);
+/// assert_eq!(on_click.stable_call(()), Some(()));
+/// assert_eq!(clicks.get(), 1);
+/// drop(clicks);
+/// assert!(!on_click.is_alive());
+/// assert_eq!(on_click.stable_call(()), None);
+/// ```
+pub struct WeakClosure<State, Input, Output> {
+    f: fn(&State, Input) -> Output,
+    t: Weak<State>,
+}
+impl<State, Input, Output> Clone for WeakClosure<State, Input, Output> {
+    fn clone(&self) -> Self {
+        Self { f: self.f, t: self.t.clone() }
+    }
+}
+impl<State, Input, Output> WeakClosure<State, Input, Output> {
+    pub fn new(f: fn(&State, Input) -> Output, owner: &Rc<State>) -> Self {
+        Self { f, t: Rc::downgrade(owner) }
+    }
+    /// Returns `false` once the state has been dropped.
+    pub fn is_alive(&self) -> bool {
+        self.t.strong_count() > 0
+    }
+    /// The state, if it is still alive.
+    pub fn upgrade(&self) -> Option<Rc<State>> {
+        self.t.upgrade()
+    }
+    /// Calls the function if the state is still alive.
+    pub fn call(&self, args: Input) -> Option<Output> {
+        self.t.upgrade().map(|t| (self.f)(&t, args))
+    }
+}
+impl<State, Input, Output> fmt::Debug for WeakClosure<State, Input, Output> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WeakClosure").field("alive", &self.is_alive()).finish()
+    }
+}
+impl<State, Input, Output> StableFnOnce<Input> for WeakClosure<State, Input, Output> {
+    type Output = Option<Output>;
+    fn stable_call_once(self, args: Input) -> Option<Output> {
+        self.call(args)
+    }
+}
+impl<State, Input, Output> StableFnMut<Input> for WeakClosure<State, Input, Output> {
+    fn stable_call_mut(&mut self, args: Input) -> Option<Output> {
+        self.call(args)
+    }
+}
+impl<State, Input, Output> StableFn<Input> for WeakClosure<State, Input, Output> {
+    fn stable_call(&self, args: Input) -> Option<Output> {
+        self.call(args)
+    }
+}
@@ -0,0 +1,92 @@
+//! Checks that calling a namable closure costs no more than calling its
+//! function pointer.
+//!
+//! A probe crate depending on this one is built in release mode with
+//! `--emit=llvm-ir`, and the bodies of its exported functions are searched for
+//! calls. Like the feature matrix, this runs cargo and is ignored by default:
+//!
+//! ```text
+//! cargo test --test codegen -- --ignored
+//! ```
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const PROBE:&str = r#"
+use namable_closures::{closure,ClosureRef,ClosureRefMut,StableFn,StableFnMut};
+
+/// A closure whose function is known: the call should disappear.
+#[no_mangle]
+pub fn probe_known(i: i32) -> i32 {
+    let add:ClosureRef<(),(i32,),i32> = closure!(ref _s=() => move |i| i+1);
+    add.stable_call((i,))
+}
+
+/// A closure built elsewhere: only the call through its function remains.
+#[no_mangle]
+pub fn probe_opaque(add: &ClosureRef<(),(i32,),i32>, i: i32) -> i32 {
+    add.stable_call((i,))
+}
+
+/// The same through the blanket impl for `&mut`.
+#[no_mangle]
+pub fn probe_opaque_mut(count: &mut ClosureRefMut<u32,(),u32>) -> u32 {
+    fn call<F: StableFnMut<()>>(mut f: F) -> F::Output {
+        f.stable_call_mut(())
+    }
+    call(count)
+}
+"#;
+
+/// Builds the probe crate and returns its LLVM IR.
+fn probe_ir() -> String {
+    let dir:PathBuf = [env!("CARGO_TARGET_TMPDIR"), "codegen"].iter().collect();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("Cargo.toml"), format!(
+        "[package]\nname = \"probe\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+         [dependencies]\nnamable_closures = {{ path = {:?}, default-features = false }}\n\n\
+         [workspace]\n",
+        env!("CARGO_MANIFEST_DIR")
+    )).unwrap();
+    fs::write(dir.join("src/lib.rs"), PROBE).unwrap();
+    let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .current_dir(&dir)
+        .env("CARGO_TARGET_DIR", dir.join("target"))
+        .args(["rustc", "--quiet", "--release", "--lib", "--", "--emit=llvm-ir"])
+        .status()
+        .expect("cargo runs");
+    assert!(status.success(), "the probe crate does not build");
+    let deps = dir.join("target/release/deps");
+    let ll = fs::read_dir(&deps).unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|e| e == "ll"))
+        .expect("cargo emits LLVM IR");
+    fs::read_to_string(ll).unwrap()
+}
+
+/// The calls in the body of `function`, leaving out LLVM intrinsics.
+fn calls<'a>(ir: &'a str, function: &str) -> Vec<&'a str> {
+    let start = ir.find(&format!("@{}(", function)).unwrap_or_else(|| panic!("{} is emitted", function));
+    let body = &ir[start..];
+    body[..body.find("\n}").unwrap()]
+        .lines()
+        .filter(|l| l.contains(" call ") || l.contains(" invoke "))
+        .filter(|l| !l.contains("@llvm."))
+        .collect()
+}
+
+#[test]
+#[ignore]
+fn calls_compile_to_direct_calls() {
+    let ir = probe_ir();
+    let known = calls(&ir, "probe_known");
+    assert!(known.is_empty(), "probe_known still calls: {:#?}", known);
+    for function in ["probe_opaque", "probe_opaque_mut"] {
+        let opaque = calls(&ir, function);
+        // one call, through the function pointer rather than to a named shim
+        assert_eq!(opaque.len(), 1, "{} calls: {:#?}", function, opaque);
+        assert!(!opaque[0].contains(" @"), "{} calls a shim: {}", function, opaque[0]);
+    }
+}
@@ -0,0 +1,75 @@
+//! Builds the crate under every combination of its optional features.
+//!
+//! Each feature should pull in only what it names, so a module that quietly
+//! relies on another feature's items breaks one of these builds. Both tests
+//! run cargo once per combination and are ignored by default:
+//!
+//! ```text
+//! cargo test --test feature_matrix -- --ignored
+//! ```
+//!
+//! `pyo3` needs a Python interpreter and `embedded-tests` only enables a
+//! test, so neither is part of the matrix. On a nightly toolchain `nightly`
+//! is added to it.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+const FEATURES:&[&str] = &["alloc", "std", "serde", "registry", "critical-section", "log", "nom", "futures"];
+
+fn cargo() -> Command {
+    let mut cmd = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+    // a target directory of its own, so that the outer build stays unlocked
+    // and each combination only rebuilds this crate
+    let target:PathBuf = [env!("CARGO_TARGET_TMPDIR"), "feature-matrix"].iter().collect();
+    cmd.current_dir(env!("CARGO_MANIFEST_DIR")).env("CARGO_TARGET_DIR", target);
+    cmd
+}
+
+fn matrix_features() -> Vec<&'static str> {
+    let mut features = FEATURES.to_vec();
+    let version = cargo().arg("--version").output().expect("cargo runs");
+    if String::from_utf8_lossy(&version.stdout).contains("nightly") {
+        features.push("nightly");
+    }
+    features
+}
+
+/// Runs `cargo <args> --no-default-features --features <features>`, with
+/// warnings denied, and returns whether it succeeded.
+fn run(args:&[&str], features:&[&str]) -> bool {
+    let features = features.join(",");
+    eprintln!("cargo {} --no-default-features --features '{}'", args.join(" "), features);
+    cargo().args(args)
+        .args(["--quiet", "--no-default-features", "--features", &features])
+        .env("RUSTFLAGS", "-D warnings")
+        .status()
+        .expect("cargo runs")
+        .success()
+}
+
+#[test]
+#[ignore]
+fn every_feature_combination_builds() {
+    let features = matrix_features();
+    let failed:Vec<String> = (0..1usize << features.len())
+        .map(|mask| features.iter().enumerate()
+            .filter(|(i, _)| mask & 1 << i != 0)
+            .map(|(_, f)| *f)
+            .collect::<Vec<_>>())
+        .filter(|set| !run(&["check", "--all-targets"], set))
+        .map(|set| format!("[{}]", set.join(",")))
+        .collect();
+    assert!(failed.is_empty(), "feature sets that do not build: {}", failed.join(" "));
+}
+
+#[test]
+#[ignore]
+fn each_feature_alone_passes_the_tests() {
+    let features = matrix_features();
+    let failed:Vec<&str> = [""].into_iter().chain(features)
+        .filter(|f| !run(&["test", "--lib", "--tests"], &[f]))
+        .collect();
+    assert!(failed.is_empty(), "features whose tests fail on their own: {:?}", failed);
+}
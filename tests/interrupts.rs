@@ -0,0 +1,110 @@
+//! Dispatching from simulated interrupt context.
+//!
+//! A second thread plays the interrupt handler: it may only reach the
+//! closures through `critical_section` statics and must not allocate. The
+//! global allocator counts allocations made while the current thread is
+//! flagged as being in the interrupt, so any hidden `Vec` or `Box` on the
+//! dispatch path fails the test.
+//!
+//! ```text
+//! cargo test --features embedded-tests --test interrupts
+//! ```
+#![cfg(feature="embedded-tests")]
+
+use std::alloc::{GlobalAlloc,Layout,System};
+use std::cell::{Cell,RefCell};
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::thread;
+
+use critical_section::Mutex;
+use namable_closures::{block_on,closure,ClosureArray,ClosureRefMut,IsrNotify,StableFnMut,StaticClosureCell};
+
+struct CountingAlloc;
+
+static INTERRUPT_ALLOCS:AtomicUsize = AtomicUsize::new(0);
+thread_local! {
+    static IN_INTERRUPT:Cell<bool> = const { Cell::new(false) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if IN_INTERRUPT.with(Cell::get) {
+            INTERRUPT_ALLOCS.fetch_add(1, Ordering::SeqCst);
+        }
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC:CountingAlloc = CountingAlloc;
+
+/// Runs `isr` on another thread with allocation counting switched on.
+fn interrupt(isr: fn()) {
+    thread::spawn(move || {
+        IN_INTERRUPT.with(|i| i.set(true));
+        isr();
+        IN_INTERRUPT.with(|i| i.set(false));
+    }).join().unwrap();
+}
+
+type Handler = ClosureRefMut<u32,(u8,),u32>;
+
+static ON_TICK:StaticClosureCell<Handler> = StaticClosureCell::new();
+static VECTORS:Mutex<RefCell<Option<ClosureArray<Handler>>>> = Mutex::new(RefCell::new(None));
+static RX_DONE:IsrNotify<u8> = IsrNotify::new();
+
+fn tick_isr() {
+    for n in 0..100 {
+        ON_TICK.call_mut_if_set((n,));
+    }
+}
+fn dispatch_isr() {
+    critical_section::with(|cs| {
+        if let Some(vectors) = VECTORS.borrow_ref_mut(cs).as_mut() {
+            for irq in 0..4u8 {
+                let _ = vectors.try_call_mut(usize::from(irq % 2), (irq,));
+            }
+            for handler in vectors.iter_mut() {
+                handler.stable_call_mut((0,));
+            }
+        }
+    });
+}
+
+fn rx_isr() {
+    for _ in 0..3 {
+        RX_DONE.on_interrupt();
+    }
+}
+
+#[test]
+fn interrupt_dispatch_does_not_allocate() {
+    // setup runs in thread mode and may allocate
+    ON_TICK.set(closure!(ref mut count=0 => move |n| {*count+=u32::from(n);*count}));
+    let mut vectors = ClosureArray::with_capacity(2);
+    vectors.push(closure!(ref mut hits=0 => move |_irq| {*hits+=1;*hits}));
+    vectors.push(closure!(ref mut last=0 => move |irq| {*last=u32::from(irq);*last}));
+    critical_section::with(|cs| VECTORS.borrow_ref_mut(cs).replace(vectors));
+
+    interrupt(tick_isr);
+    interrupt(dispatch_isr);
+    assert_eq!(INTERRUPT_ALLOCS.load(Ordering::SeqCst), 0);
+
+    assert_eq!(ON_TICK.call_mut_if_set((0,)), Some(4950));
+    let vectors = critical_section::with(|cs| VECTORS.borrow_ref_mut(cs).take()).unwrap();
+    let states:Vec<u32> = vectors.iter().map(|c| *c.state()).collect();
+    assert_eq!(states, vec![3,0]);
+}
+
+#[test]
+fn waking_a_task_from_an_interrupt_does_not_allocate() {
+    RX_DONE.handler().set(closure!(ref mut bytes=0 => move || {*bytes+=1; *bytes == 3}));
+    let task = thread::spawn(|| block_on(RX_DONE.wait()));
+    interrupt(rx_isr);
+    task.join().unwrap();
+    assert_eq!(INTERRUPT_ALLOCS.load(Ordering::SeqCst), 0);
+    assert_eq!(RX_DONE.with_state(|bytes| *bytes), Some(3));
+}
@@ -0,0 +1,119 @@
+//! The exported macros must work through paths alone, with no other imports.
+
+#[test]
+fn closure_macros_need_no_imports() {
+    let add:namable_closures::ClosureRef<i32,(i32,),i32>
+        = namable_closures::closure!(ref n=1 => move |i| i+*n);
+    assert_eq!(namable_closures::call!(ref add (1)), 2);
+    let mut count:namable_closures::ClosureRefMut<i32,(),i32>
+        = namable_closures::closure!(ref mut n=0 => move || {*n+=1;*n});
+    assert_eq!(namable_closures::call!(mut count ()), 1);
+    let mut f = namable_closures::regulate!(|| mut count);
+    assert_eq!(f(), 2);
+    let fac:namable_closures::ClosureRec<(),(u32,),u32>
+        = namable_closures::closure_rec!(me.state=() => ref |i| if i == 0 { 1 } else { i * namable_closures::call!(ref me (i-1)) });
+    assert_eq!(namable_closures::call!(fac (4)), 24);
+}
+
+#[test]
+fn closure_with_inline_type_needs_no_imports() {
+    let add = namable_closures::closure!(as namable_closures::ClosureRef<i32,(i32,(i32,i32)),i32>: ref n=1 => move |i, (j, _)| i+j+*n);
+    assert_eq!(namable_closures::call!(ref add (1, (2, 3))), 4);
+    let mut total = 0;
+    {
+        let mut count = namable_closures::closure!(as namable_closures::ClosureMut<i32,(),()>: mut t=&mut total => || *t+=1);
+        namable_closures::call!(mut count ());
+    }
+    assert_eq!(total, 1);
+}
+
+#[test]
+fn higher_ranked_closure_needs_no_imports() {
+    let longer = namable_closures::closure!(for<'r> ref _s=() => move |a: &'r str, b: &'r str| -> &'r str { if b.len() > a.len() { b } else { a } });
+    assert_eq!(namable_closures::call!(ref longer ("ab", "abc")), "abc");
+    let mut seen = Vec::new();
+    {
+        let mut push = namable_closures::closure!(for<'r> mut seen=&mut seen => |(k, v): (&'r str, &'r u8)| seen.push(format!("{}={}", k, v)));
+        namable_closures::call!(mut push (("a", &1)));
+    }
+    assert_eq!(seen, ["a=1"]);
+}
+
+#[test]
+fn helper_macros_need_no_imports() {
+    let d:namable_closures::DisplayWith<i32> = namable_closures::display_closure!(n=3 => |f| write!(f, "{}!", n));
+    assert_eq!(d.to_string(), "3!");
+    let hits = std::cell::Cell::new(0);
+    {
+        namable_closures::defer!(h=&hits => h.set(h.get()+1));
+    }
+    assert_eq!(hits.get(), 1);
+}
+
+namable_closures::static_closure!(static DOUBLE: namable_closures::ClosureRef<i32,(i32,),i32> = ref k=2 => move |i| i**k);
+
+#[test]
+fn static_closure_needs_no_imports() {
+    assert_eq!(namable_closures::call!(ref DOUBLE (4)), 8);
+}
+
+namable_closures::event_dispatch! {
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Resize { w: u32, h: u32 } => on_resize: namable_closures::ClosureRefMut<u32,(u32,u32),u32>,
+        Text { s: String, } => on_text: namable_closures::ClosureRefMut<String,(String,),()>,
+        Close {} => on_close: namable_closures::ClosureRefMut<bool,(),()>,
+    }
+    struct Handlers;
+}
+
+#[test]
+fn event_dispatch_needs_no_imports() {
+    let mut handlers = Handlers::default();
+    assert!(!handlers.handle(Event::Close {}));
+    handlers.on_resize.set(namable_closures::closure!(ref mut area=0 => move |w,h| {*area+=w*h;*area}));
+    handlers.on_text.set(namable_closures::closure!(ref mut all=String::new() => move |s| all.push_str(&s)));
+    handlers.on_close.set(namable_closures::closure!(ref mut closed=false => move || *closed=true));
+    assert!(handlers.handle(Event::Resize { w: 2, h: 3 }));
+    assert!(handlers.handle(Event::Resize { w: 1, h: 4 }));
+    assert!(handlers.handle(Event::Text { s: "hi".to_string() }));
+    assert!(handlers.handle(Event::Close {}));
+    assert_eq!(handlers.on_resize.call_mut_if_set((0, 0)), Some(10));
+    assert_eq!(handlers.on_close.clear().map(|c| *namable_closures::ClosureState::state(&c)), Some(true));
+    assert!(!handlers.handle(Event::Close {}));
+}
+
+namable_closures::closure_table! {
+    const GREETINGS: [namable_closures::ClosureRef<&'static str,(&'static str,),String>] = [
+        { ref p="hello" => move |name| format!("{}, {}", p, name) },
+        { ref p="bye" => move |name| format!("{}, {}", p, name) },
+    ];
+}
+namable_closures::closure_table! {
+    pub(crate) static NULLARY: [namable_closures::ClosureRef<(),(),u8>] = [{ || 1 }, { || 2 }];
+}
+
+#[test]
+fn closure_table_needs_no_imports() {
+    let said:Vec<String> = GREETINGS.iter().map(|g| namable_closures::StableFn::stable_call(g, ("bob",))).collect();
+    assert_eq!(said, ["hello, bob", "bye, bob"]);
+    assert_eq!(NULLARY.iter().map(|n| namable_closures::call!(ref n ())).sum::<u8>(), 3);
+}
+
+#[test]
+fn closure_bodies_can_be_cfg_selected() {
+    let pick:namable_closures::ClosureRef<i32,(i32,i32),i32> = namable_closures::closure!(ref n=1 => move |a, b|
+        #[cfg(test)] { a*b + *n }
+        #[cfg(not(test))] { a+b + *n }
+    );
+    assert_eq!(namable_closures::call!(ref pick (3,4)), 13);
+    let mut total = 0;
+    {
+        let mut add:namable_closures::ClosureMut<i32,(),()> = namable_closures::closure!(mut t=&mut total => ||
+            #[cfg(any())] { *t -= 1 }
+            #[cfg(all())] { *t += 1 }
+        );
+        namable_closures::call!(mut add ());
+    }
+    assert_eq!(total, 1);
+}
@@ -0,0 +1,180 @@
+//! Ownership and drop behaviour of every closure type.
+//!
+//! These tests avoid threads and timing so that they can run under Miri:
+//!
+//! ```text
+//! cargo +nightly miri test --test soundness
+//! ```
+//!
+//! Each state type counts its drops, so double drops and leaks show up as
+//! assertion failures even without Miri.
+
+use std::cell::Cell;
+#[cfg(feature="alloc")]
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use namable_closures::*;
+use namable_closures::arr_fn::{Arrayed,Tupled};
+
+/// A state that records how many times it has been dropped.
+#[derive(Clone)]
+struct Tracked {
+    value: i32,
+    drops: Rc<Cell<usize>>,
+}
+impl Tracked {
+    fn new(value: i32, drops: &Rc<Cell<usize>>) -> Self {
+        Tracked { value, drops: drops.clone() }
+    }
+}
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+#[test]
+fn owned_states_drop_once() {
+    let drops = Rc::new(Cell::new(0));
+    {
+        let r:ClosureRef<Tracked,(i32,),i32>
+            = closure!(ref t=Tracked::new(1, &drops) => move |i| i+t.value);
+        let mut rm:ClosureRefMut<Tracked,(i32,),i32>
+            = closure!(ref mut t=Tracked::new(2, &drops) => move |i| {t.value+=i;t.value});
+        let o:ClosureOnce<Tracked,(i32,),i32>
+            = closure!(t=Tracked::new(3, &drops) => move |i| i+t.value);
+        assert_eq!(r.stable_call((1,)), 2);
+        assert_eq!(rm.stable_call_mut((1,)), 3);
+        assert_eq!(rm.clone().stable_call_mut((1,)), 4);
+        assert_eq!(drops.get(), 1);
+        assert_eq!(o.stable_call_once((1,)), 4);
+        assert_eq!(drops.get(), 2);
+    }
+    assert_eq!(drops.get(), 4);
+}
+
+#[test]
+fn borrowed_states_are_not_dropped() {
+    let drops = Rc::new(Cell::new(0));
+    let mut t = Tracked::new(10, &drops);
+    {
+        let c:Closure<Tracked,(i32,),i32> = closure!(t=&t => |i| i+t.value);
+        let copy = c;
+        assert_eq!(c.stable_call((1,)), copy.stable_call((1,)));
+        assert_eq!(copy.stable_call_once((2,)), 12);
+    }
+    {
+        let mut m:ClosureMut<Tracked,(i32,),i32> = closure!(mut t=&mut t => |i| {t.value+=i;t.value});
+        assert_eq!(m.stable_call_mut((5,)), 15);
+        assert_eq!(m.stable_call_once((5,)), 20);
+    }
+    assert_eq!(drops.get(), 0);
+    assert_eq!(t.value, 20);
+}
+
+#[test]
+fn recursive_closures() {
+    let fac:ClosureRec<(),(u64,),u64> = ClosureRec::new(|me, (i,)| {
+        if i == 0 { 1 } else { i * me.stable_call((i-1,)) }
+    }, ());
+    assert_eq!(fac.stable_call((10,)), 3628800);
+    let once:ClosureOnceRec<u32,(u32,),u32> = ClosureOnceRec::new(|me, (i,)| {
+        if i == 0 { 0 } else { 1 + me.stable_call_once((i-1,)) }
+    }, 0);
+    assert_eq!(once.stable_call_once((5,)), 5);
+}
+
+#[cfg(feature="alloc")]
+#[test]
+fn slab_keys_do_not_alias() {
+    let drops = Rc::new(Cell::new(0));
+    let mut slab:ClosureSlab<ClosureOnce<Tracked,(),i32>> = ClosureSlab::new();
+    let keys:Vec<_> = (0..4)
+        .map(|n| slab.insert(closure!(t=Tracked::new(n, &drops) => move || t.value)))
+        .collect();
+    assert_eq!(slab.call_once(keys[1], ()), Some(1));
+    assert_eq!(drops.get(), 1);
+    let reused = slab.insert(closure!(t=Tracked::new(9, &drops) => move || t.value));
+    assert_eq!(reused.index(), keys[1].index());
+    assert!(slab.get(keys[1]).is_none());
+    slab.retain(|_, c| c.clone().stable_call_once(()) % 2 == 0);
+    // four clones dropped by the predicate, and the two odd closures removed
+    assert_eq!(drops.get(), 1 + 4 + 2);
+    slab.clear();
+    assert_eq!(drops.get(), 1 + 4 + 4);
+}
+
+#[cfg(feature="alloc")]
+struct Bus<'a> {
+    emitter: EventEmitter<ClosureRef<u32,(&'a Bus<'a>,u32),()>>,
+    log: RefCell<Vec<u32>>,
+}
+
+#[cfg(feature="alloc")]
+#[test]
+fn emitter_reentrancy() {
+    let bus = Bus { emitter: EventEmitter::new(), log: RefCell::new(Vec::new()) };
+    let key = bus.emitter.subscribe(closure!(ref id=1 => move |bus,n| {
+        bus.log.borrow_mut().push(*id*10+n);
+        if n < 3 {
+            bus.emitter.emit((bus,n+1));
+            bus.emitter.subscribe(closure!(ref id=2 => move |bus,n| bus.log.borrow_mut().push(*id*10+n)));
+        }
+    }));
+    bus.emitter.emit((&bus,0));
+    assert_eq!(*bus.log.borrow(), vec![10]);
+    assert_eq!(bus.emitter.len(), 2);
+    bus.emitter.unsubscribe(key);
+    bus.emitter.emit((&bus,5));
+    assert_eq!(*bus.log.borrow(), vec![10,25]);
+}
+
+#[test]
+fn defer_guard_runs_exactly_once() {
+    let drops = Rc::new(Cell::new(0));
+    let runs = Rc::new(Cell::new(0));
+    {
+        let g = guard((Tracked::new(1, &drops), runs.clone()), |(_t, runs)| runs.set(runs.get()+1));
+        assert_eq!(g.0.value, 1);
+    }
+    assert_eq!((runs.get(), drops.get()), (1, 1));
+    let g = guard((Tracked::new(2, &drops), runs.clone()), |(_t, runs)| runs.set(runs.get()+1));
+    let (t, _) = g.dismiss();
+    assert_eq!((runs.get(), drops.get()), (1, 1));
+    drop(t);
+    assert_eq!(drops.get(), 2);
+    let r = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _g = guard(Tracked::new(3, &drops), |_t| ());
+        panic!("unwind through guard");
+    }));
+    assert!(r.is_err());
+    assert_eq!(drops.get(), 3);
+}
+
+#[test]
+fn adapters_and_containers() {
+    let mut slot:CallbackSlot<ClosureRefMut<i32,(i32,),i32>> = CallbackSlot::new();
+    assert_eq!(slot.call_mut_if_set((1,)), None);
+    slot.set(closure!(ref mut s=0 => move |i| {*s+=i;*s}));
+    assert_eq!(slot.call_mut_if_set((2,)), Some(2));
+
+    let sum:ClosureRef<(),(i32,i32),i32> = closure!(ref _s=() => move |a,b| a+b);
+    assert_eq!(Arrayed(sum).stable_call([1,2]), 3);
+    assert_eq!(Tupled(Arrayed(sum)).stable_call((3,4)), 7);
+
+    let d:DisplayWith<i32> = display_closure!(n=5 => |f| write!(f, "<{}>", n));
+    assert_eq!(d.to_string(), "<5>");
+
+    let mut scratch = vec![0u8; 4];
+    let mut s:ScratchClosure<Vec<u8>,u8,(usize,),u8>
+        = ScratchClosure::new(|v, buf, (i,)| { buf[i] = *v; *v += 1; buf[i] }, 7);
+    assert_eq!(s.call_with_scratch(&mut scratch, (2,)), 7);
+    assert_eq!(scratch, vec![0,0,7,0]);
+
+    let mut any:AnyClosureKind<i32,(i32,),i32> = ClosureOnce::new(|s, (i,)| s+i, 1).into();
+    assert_eq!(any.try_call_mut((1,)), Err((1,)));
+    any = ClosureRefMut::new(|s, (i,)| {*s+=i;*s}, 1).into();
+    assert_eq!(any.try_call_mut((1,)), Ok(2));
+    assert_eq!(any.stable_call_once((1,)), 3);
+}